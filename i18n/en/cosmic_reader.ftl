@@ -0,0 +1,298 @@
+cosmic-reader = COSMIC Reader
+
+# Menu
+file = File
+edit = Edit
+view = View
+go = Go
+help = Help
+new-instance = New instance
+new-instance-failed = Failed to start a new instance: {$error}
+open = Open
+open-menu = Open...
+open-recent = Open recent
+settings-menu = Settings...
+export-menu = Export...
+export-image-menu = Export page as image...
+export-svg-menu = Export page as SVG...
+statistics-menu = Statistics...
+keyboard-shortcuts-menu = Keyboard shortcuts...
+layers-menu = Layers...
+signatures-menu = Unverified signatures...
+sidebar = Sidebar
+pages = Pages
+outline = Outline
+structure = Structure
+bookmarks = Bookmarks
+heading = Heading {$level} {$title}
+list = List {$title}
+table = Table {$title}
+page-number = Page {$number}
+note-on-page = Page {$page}: {$contents}
+remember-for-document = Remember for this document
+lock-view = Lock zoom and scroll
+trim-margins = Trim white margins
+rulers = Rulers
+grid = Grid
+night-mode = Night mode
+split-view = Split view
+sync-panes = Synchronize panes
+lock-pane-zoom = Zoom panes together
+open-second-pane = Open in second pane
+open-second-pane-menu = Open in second pane...
+compare-with = Compare with another document
+compare-with-menu = Compare with...
+not-a-local-file = Not a local file
+open-failed = Failed to open: {$error}
+zoom = Zoom
+zoom-percent = {$percent}%
+
+# Actions
+about = About
+actual-size = Actual size
+bookmark-page = Bookmark this page
+export = Export
+export-image = Export page as image
+export-svg = Export page as SVG
+fit-page = Fit page
+fit-width = Fit width
+focus-pane = Move focus between sidebar and page
+single-page = Single page
+continuous = Continuous
+facing-pages = Facing pages
+first-page = First page
+last-page = Last page
+next-page = Next page
+previous-page = Previous page
+next-document = Next document in folder
+previous-document = Previous document in folder
+go-to-page = Page {$page} of {$pages}
+go-to-page-menu = Go to page...
+page-total = / {$pages}
+scroll-down = Scroll down
+scroll-left = Scroll left
+scroll-right = Scroll right
+scroll-up = Scroll up
+pick-color = Pick color
+picked-color = {$hex}  rgb({$red}, {$green}, {$blue})  copied
+present = Present
+quit = Quit
+reading-scroll = Slow scroll
+remember-view = Remember view for this document
+right-to-left-pages = Right to left pages
+cover-page = First page is a cover
+rotate-left = Rotate left
+rotate-right = Rotate right
+rotate-all-left = Rotate all pages left
+rotate-all-right = Rotate all pages right
+screen-down = Scroll down a screen
+screen-up = Scroll up a screen
+settings = Settings
+statistics = Statistics
+layers = Layers
+no-layers = This document has no layers
+unnamed-layer = Unnamed layer
+signatures = Unverified signatures
+show-annotations = Show annotations
+show-bookmarks = Show bookmarks
+show-outline = Show outline
+show-pages = Show pages
+show-structure = Show structure
+toggle-grid = Toggle grid
+toggle-rulers = Toggle rulers
+toggle-sidebar = Toggle sidebar
+zoom-in = Zoom in
+zoom-out = Zoom out
+reset-zoom = Reset zoom
+
+# Mouse buttons
+double-click = Double-click
+middle-click = Middle-click
+back-button = Back button
+forward-button = Forward button
+
+# About
+version = Version {$version}
+license = Licensed under the GNU General Public License v3.0
+
+# Statistics
+calculating = Calculating...
+page-count = Pages
+word-count = Words
+character-count = Characters
+image-count = Images
+reading-time = Reading time
+conformance = Conformance
+not-claimed = Not claimed
+no-issues = No problems found
+issue-encrypted = Encrypted
+issue-font-not-embedded = Font not embedded: {$font}
+issue-transparency = Uses transparency, which PDF/A-1 does not allow
+issue-not-tagged = Not tagged
+issue-no-language = No document language
+issue-title-not-shown = Title not shown in the window title
+no-signatures = This document is not signed
+signatures-not-verified = Signatures are not verified, neither the signed contents nor the certificates of signers are checked
+unknown-signer = Unknown signer
+signed-at = Signed
+signature-reason = Reason
+signature-location = Location
+signature-status = Status
+signature-whole-document = Unverified signature covering the whole document
+signature-changed-after = Unverified signature, document changed after signing
+signature-broken = Unverified signature, signed contents are damaged
+signed-banner = {$signatures ->
+    [one] This document has an unverified signature
+    *[other] This document has {$signatures} unverified signatures
+}
+details = Details
+reading-minutes = {$minutes ->
+    [one] {$minutes} minute
+    *[other] {$minutes} minutes
+}
+
+# Export
+stamp = Stamp
+stamp-text = Text
+stamp-image = Image file
+stamp-position = Position
+stamp-rotation = Rotation
+stamp-opacity = Opacity
+degrees = {$degrees}°
+percent = {$percent}%
+center = Center
+top = Top
+bottom = Bottom
+top-left = Top left
+top-right = Top right
+bottom-left = Bottom left
+bottom-right = Bottom right
+crop = Crop
+crop-top = Top margin
+crop-right = Right margin
+crop-bottom = Bottom margin
+crop-left = Left margin
+crop-current-page = Only crop the current page
+image-format = Format
+png = PNG
+jpeg = JPEG
+resolution = Resolution
+dots-per-inch = {$dpi} DPI
+current-page = Current page
+invalid-pages = Not a range of pages: {$pages}
+exporting = Exporting...
+exported = Exported to {$path}
+export-failed = Failed to export: {$error}
+copy-link = Copy link to this page
+add-note = Add note
+note = Note
+note-placeholder = Write a note
+delete = Delete
+done = Done
+cancel = Cancel
+save = Save
+saved = Saved
+save-failed = Failed to save: {$error}
+save-notes = Save notes before closing the document?
+save-notes-body = Notes were changed since the document was saved. Changes not saved are lost.
+quit-without-saving = Close without saving
+find = Find
+find-menu = Find...
+undo = Undo
+redo = Redo
+find-next = Find next
+find-previous = Find previous
+search = Search
+indexing = Indexing...
+search-results = {$current} of {$matches} on this page, {$pages ->
+    [one] {$pages} page
+    *[other] {$pages} pages
+} in all
+link-copied = Link copied
+default-viewer = Default PDF viewer
+default-viewer-body = PDF documents open in another application. Open them with COSMIC Reader instead?
+is-default = COSMIC Reader opens PDF documents
+open-pdf-documents = Open PDF documents
+set-default = Set as default
+not-now = Not now
+continue-reading = Continue reading
+continue-reading-body = Go back to page {$page}, where you left this document?
+continue = Continue
+start-here = Start from the beginning
+print = Print
+print-menu = Print...
+printing = Printing...
+printed = Sent to the printer
+print-failed = Failed to print: {$error}
+print-with-cups = Print with CUPS?
+print-with-cups-body = The print dialog is not available ({$error}). The document can be sent to the default CUPS printer instead, with its default settings.
+share = Share by email
+share-menu = Share by email...
+share-failed = Failed to share: {$error}
+no-email-application = No email portal or xdg-email to open an email with
+play-media-failed = Failed to play media: {$error}
+link-page = Go to page {$page}
+open-link-failed = Failed to open link: {$error}
+
+# Settings
+default-zoom = Default zoom
+default-layout = Default layout
+zoom-presets = Zoom presets
+zoom-step = Zoom step
+arrow-pages = Arrow keys change pages at the edge of the page
+reduce-motion = Reduce motion
+save-power = Save power on battery
+power-source = Power source
+battery = Battery
+plugged-in = Plugged in
+smooth-rendering = Smooth rendering
+smooth-rendering-restart = Takes effect the next time the reader starts
+sharp-pixels = Align pages to pixels
+darken-text = Darken small text
+page-animation = Page animation
+fade = Fade
+slide = Slide
+resume = Continue reading where left
+resume-always = Always
+resume-ask = Ask
+resume-never = Never
+reading-speed = Slow scroll speed
+reading-speed-points = {$points} pt per second
+grid-spacing = Grid spacing
+grid-spacing-points = {$points} pt
+page-gap = Page gap
+page-gap-points = {$points} pt
+books = Books
+book-font-size = Font size
+book-margin = Margins
+book-width = Page width
+reflow-points = {$points} pt
+page-edge = Page edge
+border = Border
+shadow = Shadow
+page-order = Page order
+left-to-right = Left to right
+right-to-left = Right to left
+colors = Colors
+annotations = Annotations
+author = Author
+page-color-light = Page color with light theme
+page-color-dark = Page color with dark theme
+canvas-color-light = Background color with light theme
+canvas-color-dark = Background color with dark theme
+default = Default
+paper-tint = Paper tint
+paper-color = Paper color
+screen-dpi = Screen DPI
+automatic = Automatic
+mouse = Mouse
+wheel = Scroll wheel
+wheel-scroll = Scroll the page
+wheel-pages = Change pages
+wheel-zoom = Zoom
+keyboard-shortcuts = Keyboard shortcuts
+shortcuts-changed-in-settings = Shortcuts can be changed in the settings
+press-a-key = Press a key...
+disabled = Disabled
+reset-shortcuts = Reset shortcuts
+none = None
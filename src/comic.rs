@@ -0,0 +1,121 @@
+use lopdf::{dictionary, Document, Object, Stream};
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::Path,
+};
+
+/// Extensions of comic book archives
+const EXTENSIONS: [&str; 2] = ["cbz", "cbr"];
+
+/// Extensions of images shown as pages, others such as metadata files are skipped
+const IMAGE_EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "gif", "webp", "bmp"];
+
+/// Name of the image of each page in its resources
+const PAGE_IMAGE: &str = "Page";
+
+/// Whether a file is a comic book archive, by its extension as archives are not otherwise marked
+pub fn is_comic(path: &Path) -> bool {
+    has_extension(path, &EXTENSIONS)
+}
+
+/// Open a comic book archive as a document with an image filling each page, in order of the
+/// names of the images
+//TODO: CBR files that really are RAR archives need an unrar library, only zip is read
+pub fn load(path: &Path) -> Result<Document, String> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path).map_err(|err| err.to_string())?)
+        .map_err(|err| err.to_string())?;
+    let mut names: Vec<String> = archive
+        .file_names()
+        // Archives made on macOS may have resource forks of each image
+        .filter(|name| !name.starts_with("__MACOSX/"))
+        .filter(|name| has_extension(Path::new(name), &IMAGE_EXTENSIONS))
+        .map(str::to_string)
+        .collect();
+    //TODO: sort numbers by value, so page10 comes after page9 in archives without leading zeros
+    names.sort();
+    let mut images = Vec::with_capacity(names.len());
+    for name in names {
+        let mut data = Vec::new();
+        let mut file = archive.by_name(&name).map_err(|err| err.to_string())?;
+        file.read_to_end(&mut data).map_err(|err| err.to_string())?;
+        images.push((name, data));
+    }
+    document(images, path)
+}
+
+/// Document with an image filling each page, skipping images that cannot be read
+pub fn document(images: Vec<(String, Vec<u8>)>, path: &Path) -> Result<Document, String> {
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let mut kids = Vec::new();
+    for (name, data) in images {
+        let (image, width, height) = match image_stream(data) {
+            Ok(image) => image,
+            Err(err) => {
+                log::warn!("failed to read image {name:?} in {path:?}: {err}");
+                continue;
+            }
+        };
+        let image_id = doc.add_object(image);
+        // One unit per pixel, so pages are shown at the size of their images
+        let content = format!("q {width} 0 0 {height} 0 0 cm /{PAGE_IMAGE} Do Q");
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            "Contents" => content_id,
+            "Resources" => dictionary! {
+                "XObject" => dictionary! {
+                    PAGE_IMAGE => image_id,
+                },
+            },
+        });
+        kids.push(page_id.into());
+    }
+    if kids.is_empty() {
+        return Err(format!("no images in {path:?}"));
+    }
+    let count = kids.len() as i64;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    Ok(doc)
+}
+
+/// Image XObject keeping the image file as it is, as pages decode images from their files, and
+/// its size in pixels, read from its header so images are only decoded when shown
+//TODO: only JPEG images are valid PDF this way, printing or exporting needs others decoded
+fn image_stream(data: Vec<u8>) -> image::ImageResult<(Stream, u32, u32)> {
+    let reader = image::io::Reader::new(Cursor::new(&data)).with_guessed_format()?;
+    let jpeg = reader.format() == Some(image::ImageFormat::Jpeg);
+    let (width, height) = reader.into_dimensions()?;
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => width,
+        "Height" => height,
+        "ColorSpace" => "DeviceRGB",
+        "BitsPerComponent" => 8,
+    };
+    if jpeg {
+        dict.set("Filter", "DCTDecode");
+    }
+    Ok((Stream::new(dict, data), width, height))
+}
+
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .is_some_and(|extension| extensions.iter().any(|x| extension.eq_ignore_ascii_case(x)))
+}
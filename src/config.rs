@@ -0,0 +1,424 @@
+use cosmic::cosmic_config::{self, cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use cosmic::iced::Color;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    path::PathBuf,
+};
+
+use crate::{
+    fl,
+    key_bind::{key_binds, mouse_binds, Action, KeyBind, MouseBind},
+    layout::Layout,
+    pdf::Fit,
+};
+
+pub const CONFIG_VERSION: u64 = 1;
+
+/// View settings, saved as a global default and optionally per document
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct DocumentView {
+    #[serde(default)]
+    pub layout: Layout,
+    pub fit: Fit,
+    pub scale: f32,
+}
+
+impl Default for DocumentView {
+    fn default() -> Self {
+        Self {
+            layout: Layout::default(),
+            fit: Fit::default(),
+            scale: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, CosmicConfigEntry, Debug, Deserialize, PartialEq, Serialize)]
+#[version = 1]
+pub struct Config {
+    pub key_binds: BTreeMap<Action, KeyBind>,
+    pub mouse_binds: BTreeMap<MouseBind, Action>,
+    /// Physical pixels per inch of the screen, so actual size matches the size on paper.
+    /// Screens are assumed to be 96 DPI at their scale factor if this is not set.
+    pub screen_dpi: Option<u16>,
+    /// Background of pages, for documents without their own
+    pub page_color: ThemeColor,
+    /// Background around pages, the window background if not set
+    pub canvas_color: ThemeColor,
+    /// Show pages on a tinted paper color instead of white
+    pub paper_tint: bool,
+    /// Paper color for the tint, as a hex RGB color
+    pub paper_color: String,
+    /// Show pages with their lightness inverted, dark with light text, for reading at night
+    pub night_mode: bool,
+    /// Load fewer pages ahead of time and skip animations while on battery
+    pub save_power: bool,
+    /// Smooth the edges of page contents, applied when the reader starts
+    pub antialiasing: bool,
+    /// Align pages to whole screen pixels so lines and small text stay crisp
+    pub sharp_pixels: bool,
+    /// Thicken text outlines so small text does not wash out on low resolution screens
+    pub darken_text: bool,
+    /// Crop pages to their contents, so pages with wide margins fill more of the window
+    pub trim_margins: bool,
+    /// Show rulers in page units along the top and left of the view
+    pub rulers: bool,
+    /// Show a grid over pages
+    pub grid: bool,
+    /// Space between grid lines, in PDF units
+    pub grid_spacing: u16,
+    /// Animation when changing pages and zooming
+    pub page_animation: PageAnimation,
+    /// Space between pages, in PDF units
+    pub page_gap: u16,
+    /// Outline drawn around pages
+    pub page_edge: PageEdge,
+    /// Skip animations such as page slides, momentum scrolling, and slide transitions
+    pub reduce_motion: bool,
+    /// View used for documents without their own
+    pub view: DocumentView,
+    /// Zoom percentages shown in the zoom menu
+    pub zoom_presets: Vec<u16>,
+    /// Order of pages for swiping and facing pages
+    pub direction: Direction,
+    /// Speed of the slow scroll reading mode, in PDF units per second
+    pub reading_speed: u16,
+    /// What the scroll wheel does without modifiers
+    pub wheel: Wheel,
+    /// Arrow keys scroll within the current page and go to the next or previous page at its
+    /// edge, instead of only panning
+    pub arrow_pages: bool,
+    /// Percentage zoomed by each step of Ctrl+scroll
+    pub zoom_step: u16,
+    /// Name written as the author of new annotations
+    pub author: String,
+    /// Whether documents opened again go back to where they were left
+    pub resume: Resume,
+    /// Layout of documents without fixed pages, such as EPUB books
+    pub reflow: Reflow,
+}
+
+impl Config {
+    pub fn color(&self, setting: ColorSetting) -> &str {
+        match setting {
+            ColorSetting::PageLight => &self.page_color.light,
+            ColorSetting::PageDark => &self.page_color.dark,
+            ColorSetting::CanvasLight => &self.canvas_color.light,
+            ColorSetting::CanvasDark => &self.canvas_color.dark,
+        }
+    }
+
+    pub fn color_mut(&mut self, setting: ColorSetting) -> &mut String {
+        match setting {
+            ColorSetting::PageLight => &mut self.page_color.light,
+            ColorSetting::PageDark => &mut self.page_color.dark,
+            ColorSetting::CanvasLight => &mut self.canvas_color.light,
+            ColorSetting::CanvasDark => &mut self.canvas_color.dark,
+        }
+    }
+
+    pub fn action(&self, key_bind: &KeyBind) -> Option<Action> {
+        self.key_binds
+            .iter()
+            .find(|(_, x)| *x == key_bind)
+            .map(|(action, _)| *action)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            key_binds: key_binds(),
+            mouse_binds: mouse_binds(),
+            page_color: ThemeColor::default(),
+            canvas_color: ThemeColor::default(),
+            paper_tint: false,
+            paper_color: "#f4ecd8".to_string(),
+            night_mode: false,
+            page_animation: PageAnimation::default(),
+            page_gap: 16,
+            page_edge: PageEdge::default(),
+            reduce_motion: false,
+            save_power: true,
+            antialiasing: true,
+            sharp_pixels: false,
+            darken_text: false,
+            trim_margins: false,
+            rulers: false,
+            grid: false,
+            grid_spacing: 36,
+            screen_dpi: None,
+            view: DocumentView::default(),
+            direction: Direction::default(),
+            reading_speed: 6,
+            wheel: Wheel::default(),
+            arrow_pages: true,
+            zoom_presets: vec![50, 75, 100, 125, 150, 200, 300, 400],
+            zoom_step: 10,
+            author: user_full_name(),
+            resume: Resume::default(),
+            reflow: Reflow::default(),
+        }
+    }
+}
+
+/// Full name of the user from the password database, or the user name if it has none
+pub fn user_full_name() -> String {
+    let Some(user) = env::var_os("USER").or_else(|| env::var_os("LOGNAME")) else {
+        return String::new();
+    };
+    let user = user.to_string_lossy().into_owned();
+    let passwd = fs::read_to_string("/etc/passwd").unwrap_or_default();
+    // The comment field starts with the full name, other details follow after commas
+    passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&user.as_str()))
+        .and_then(|fields| fields.get(4)?.split(',').next().map(str::to_string))
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or(user)
+}
+
+/// Hex RGB colors used with the light and dark themes, empty for the default
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ThemeColor {
+    pub light: String,
+    pub dark: String,
+}
+
+impl ThemeColor {
+    pub fn get(&self, dark: bool) -> Option<Color> {
+        parse_color(if dark { &self.dark } else { &self.light })
+    }
+}
+
+/// Colors that can be chosen for each theme
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ColorSetting {
+    PageLight,
+    PageDark,
+    CanvasLight,
+    CanvasDark,
+}
+
+impl ColorSetting {
+    pub const ALL: [Self; 4] = [
+        Self::PageLight,
+        Self::PageDark,
+        Self::CanvasLight,
+        Self::CanvasDark,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::PageLight => fl!("page-color-light"),
+            Self::PageDark => fl!("page-color-dark"),
+            Self::CanvasLight => fl!("canvas-color-light"),
+            Self::CanvasDark => fl!("canvas-color-dark"),
+        }
+    }
+}
+
+/// Parse a hex RGB color like #f4ecd8, with or without the #
+pub fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Animation when changing pages, zooming is animated unless this is none
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PageAnimation {
+    None,
+    #[default]
+    Fade,
+    Slide,
+}
+
+impl PageAnimation {
+    pub const ALL: [Self; 3] = [Self::None, Self::Fade, Self::Slide];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::None => fl!("none"),
+            Self::Fade => fl!("fade"),
+            Self::Slide => fl!("slide"),
+        }
+    }
+}
+
+/// Outline drawn around pages, shaded for the theme
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum PageEdge {
+    #[default]
+    None,
+    Border,
+    Shadow,
+}
+
+impl PageEdge {
+    pub const ALL: [Self; 3] = [Self::None, Self::Border, Self::Shadow];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::None => fl!("none"),
+            Self::Border => fl!("border"),
+            Self::Shadow => fl!("shadow"),
+        }
+    }
+}
+
+/// Reading direction of pages, automatic uses the document's direction or language
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Direction {
+    #[default]
+    Automatic,
+    LeftToRight,
+    RightToLeft,
+}
+
+impl Direction {
+    pub const ALL: [Self; 3] = [Self::Automatic, Self::LeftToRight, Self::RightToLeft];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Automatic => fl!("automatic"),
+            Self::LeftToRight => fl!("left-to-right"),
+            Self::RightToLeft => fl!("right-to-left"),
+        }
+    }
+}
+
+/// Format a color as hex RGB like #f4ecd8
+pub fn format_color(color: Color) -> String {
+    let [red, green, blue, _] = color.into_rgba8();
+    format!("#{red:02x}{green:02x}{blue:02x}")
+}
+
+/// Scroll wheel behavior, Ctrl+scroll swaps zooming with scrolling and Shift+scroll pans sideways
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Wheel {
+    #[default]
+    Scroll,
+    Pages,
+    Zoom,
+}
+
+impl Wheel {
+    pub const ALL: [Self; 3] = [Self::Scroll, Self::Pages, Self::Zoom];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Scroll => fl!("wheel-scroll"),
+            Self::Pages => fl!("wheel-pages"),
+            Self::Zoom => fl!("wheel-zoom"),
+        }
+    }
+}
+
+/// Going back to the last page read when a document is opened again
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Resume {
+    #[default]
+    Always,
+    Ask,
+    Never,
+}
+
+impl Resume {
+    pub const ALL: [Self; 3] = [Self::Always, Self::Ask, Self::Never];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Always => fl!("resume-always"),
+            Self::Ask => fl!("resume-ask"),
+            Self::Never => fl!("resume-never"),
+        }
+    }
+}
+
+/// Layout of text laid out on pages by the reader, in PDF units
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Reflow {
+    pub font_size: u16,
+    /// Space around the text on each side of the page
+    pub margin: u16,
+    /// Width of pages, their height follows from it
+    pub width: u16,
+}
+
+impl Default for Reflow {
+    fn default() -> Self {
+        Self {
+            font_size: 12,
+            margin: 36,
+            width: 432,
+        }
+    }
+}
+
+/// Where a document was left, to continue reading it
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LastRead {
+    /// Position of the page, from 0
+    pub page: u16,
+    pub view: DocumentView,
+}
+
+/// Pane shown in the sidebar
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Sidebar {
+    #[default]
+    Pages,
+    Outline,
+    /// Headings, lists, and tables of tagged documents
+    Structure,
+    Bookmarks,
+    /// Notes on pages of the document
+    Annotations,
+}
+
+#[derive(Clone, CosmicConfigEntry, Debug, Deserialize, PartialEq, Serialize)]
+#[version = 1]
+pub struct State {
+    /// Views remembered for documents, by canonical path
+    pub documents: BTreeMap<PathBuf, DocumentView>,
+    /// Documents with right to left page order chosen for them, by canonical path
+    pub right_to_left: BTreeMap<PathBuf, bool>,
+    /// Documents with a cover shown alone in facing pages, by canonical path
+    pub cover_pages: BTreeSet<PathBuf>,
+    /// Opening PDF documents by default was offered, so it is not offered again
+    pub default_handler_offered: bool,
+    /// Documents opened recently, most recent first, by canonical path
+    pub recent: Vec<PathBuf>,
+    /// Where documents were left, by canonical path
+    pub last_read: BTreeMap<PathBuf, LastRead>,
+    /// Positions of pages bookmarked in documents, by canonical path
+    pub bookmarks: BTreeMap<PathBuf, BTreeSet<u16>>,
+    pub show_sidebar: bool,
+    pub sidebar: Sidebar,
+    /// Width the sidebar was resized to, in logical pixels
+    pub sidebar_width: f32,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            documents: BTreeMap::new(),
+            right_to_left: BTreeMap::new(),
+            cover_pages: BTreeSet::new(),
+            default_handler_offered: false,
+            recent: Vec::new(),
+            last_read: BTreeMap::new(),
+            bookmarks: BTreeMap::new(),
+            show_sidebar: true,
+            sidebar: Sidebar::default(),
+            sidebar_width: 280.0,
+        }
+    }
+}
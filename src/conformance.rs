@@ -0,0 +1,191 @@
+use lopdf::{Dictionary, Document, Object};
+
+use crate::fl;
+
+/// Standards a document claims to follow and problems found that break them
+#[derive(Clone, Debug, Default)]
+pub struct Conformance {
+    /// Claimed PDF/A part and level, such as PDF/A-2b
+    pub pdfa: Option<String>,
+    /// Claimed PDF/UA part, such as PDF/UA-1
+    pub pdfua: Option<String>,
+    pub issues: Vec<Issue>,
+}
+
+/// Common reason a document does not follow a standard it claims to
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Issue {
+    Encrypted,
+    /// Font by its base font name that is not embedded in the document
+    FontNotEmbedded(String),
+    /// Transparency, which PDF/A-1 does not allow
+    Transparency,
+    NotTagged,
+    NoLanguage,
+    TitleNotShown,
+}
+
+impl Issue {
+    pub fn name(&self) -> String {
+        match self {
+            Self::Encrypted => fl!("issue-encrypted"),
+            Self::FontNotEmbedded(font) => fl!("issue-font-not-embedded", font = font.as_str()),
+            Self::Transparency => fl!("issue-transparency"),
+            Self::NotTagged => fl!("issue-not-tagged"),
+            Self::NoLanguage => fl!("issue-no-language"),
+            Self::TitleNotShown => fl!("issue-title-not-shown"),
+        }
+    }
+}
+
+/// Check the claims of the XMP metadata against the document
+//TODO: this is a quick check of common problems, not a full validation like veraPDF
+pub fn conformance(doc: &Document) -> Conformance {
+    let mut conformance = Conformance::default();
+    let Ok(catalog) = doc.catalog() else {
+        return conformance;
+    };
+    let xmp = catalog
+        .get_deref(b"Metadata", doc)
+        .and_then(Object::as_stream)
+        .and_then(|stream| stream.get_plain_content())
+        .map(|content| String::from_utf8_lossy(&content).into_owned())
+        .unwrap_or_default();
+    let pdfa_part = xmp_value(&xmp, "pdfaid:part");
+    conformance.pdfa = pdfa_part.as_ref().map(|part| {
+        let level = xmp_value(&xmp, "pdfaid:conformance").unwrap_or_default();
+        format!("PDF/A-{part}{}", level.to_ascii_lowercase())
+    });
+    conformance.pdfua = xmp_value(&xmp, "pdfuaid:part").map(|part| format!("PDF/UA-{part}"));
+
+    if conformance.pdfa.is_some() {
+        if doc.trailer.has(b"Encrypt") {
+            conformance.issues.push(Issue::Encrypted);
+        }
+        for font in fonts_not_embedded(doc) {
+            conformance.issues.push(Issue::FontNotEmbedded(font));
+        }
+        if pdfa_part.as_deref() == Some("1") && has_transparency(doc) {
+            conformance.issues.push(Issue::Transparency);
+        }
+    }
+    if conformance.pdfua.is_some() {
+        let marked = catalog
+            .get_deref(b"MarkInfo", doc)
+            .and_then(Object::as_dict)
+            .and_then(|info| info.get(b"Marked"))
+            .and_then(Object::as_bool)
+            .unwrap_or(false);
+        if !marked || !catalog.has(b"StructTreeRoot") {
+            conformance.issues.push(Issue::NotTagged);
+        }
+        if !catalog.has(b"Lang") {
+            conformance.issues.push(Issue::NoLanguage);
+        }
+        let title_shown = catalog
+            .get_deref(b"ViewerPreferences", doc)
+            .and_then(Object::as_dict)
+            .and_then(|prefs| prefs.get(b"DisplayDocTitle"))
+            .and_then(Object::as_bool)
+            .unwrap_or(false);
+        if !title_shown {
+            conformance.issues.push(Issue::TitleNotShown);
+        }
+    }
+    conformance
+}
+
+/// Value of an XMP property, written either as an attribute or as an element
+fn xmp_value(xmp: &str, name: &str) -> Option<String> {
+    let rest = xmp[xmp.find(name)? + name.len()..].trim_start();
+    let value = match rest.strip_prefix('=') {
+        Some(rest) => {
+            let rest = rest.trim_start();
+            let quote = rest.chars().next()?;
+            let rest = &rest[quote.len_utf8()..];
+            &rest[..rest.find(quote)?]
+        }
+        None => {
+            let rest = rest.strip_prefix('>')?;
+            &rest[..rest.find('<')?]
+        }
+    };
+    Some(value.trim().to_string())
+}
+
+/// Base font names of fonts without a font file, other than Type 3 fonts drawn by the document
+fn fonts_not_embedded(doc: &Document) -> Vec<String> {
+    let mut fonts = Vec::new();
+    for object in doc.objects.values() {
+        let Ok(font) = object.as_dict() else {
+            continue;
+        };
+        if font.get(b"Type").and_then(Object::as_name).ok() != Some(b"Font") {
+            continue;
+        }
+        // Composite fonts keep the font file in their descendant font
+        let descriptor_font = match font.get(b"Subtype").and_then(Object::as_name) {
+            Ok(b"Type3") => continue,
+            Ok(b"Type0") => font
+                .get_deref(b"DescendantFonts", doc)
+                .and_then(Object::as_array)
+                .ok()
+                .and_then(|fonts| fonts.first())
+                .and_then(|font| doc.dereference(font).ok())
+                .and_then(|(_, font)| font.as_dict().ok()),
+            _ => Some(font),
+        };
+        let embedded = descriptor_font
+            .and_then(|font| font.get_deref(b"FontDescriptor", doc).ok())
+            .and_then(|descriptor| descriptor.as_dict().ok())
+            .is_some_and(|descriptor| {
+                [&b"FontFile"[..], b"FontFile2", b"FontFile3"]
+                    .iter()
+                    .any(|key| descriptor.has(key))
+            });
+        if !embedded {
+            let name = font
+                .get(b"BaseFont")
+                .and_then(Object::as_name_str)
+                .unwrap_or("?");
+            if !fonts.iter().any(|font| font == name) {
+                fonts.push(name.to_string());
+            }
+        }
+    }
+    fonts
+}
+
+/// Whether soft masks, constant opacity, blend modes, or transparency groups are used
+fn has_transparency(doc: &Document) -> bool {
+    doc.objects.values().any(|object| {
+        let dict = match object {
+            Object::Dictionary(dict) => dict,
+            Object::Stream(stream) => &stream.dict,
+            _ => return false,
+        };
+        transparent(dict)
+    })
+}
+
+fn transparent(dict: &Dictionary) -> bool {
+    let soft_mask = dict
+        .get(b"SMask")
+        .is_ok_and(|mask| mask.as_name().ok() != Some(b"None"));
+    let opacity = [&b"CA"[..], b"ca"].iter().any(|key| {
+        dict.get(key)
+            .and_then(Object::as_float)
+            .is_ok_and(|alpha| alpha < 1.0)
+    });
+    let blend = dict
+        .get(b"BM")
+        .and_then(Object::as_name)
+        .is_ok_and(|mode| mode != b"Normal" && mode != b"Compatible");
+    let group = dict
+        .get(b"Group")
+        .and_then(Object::as_dict)
+        .and_then(|group| group.get(b"S"))
+        .and_then(Object::as_name)
+        .is_ok_and(|kind| kind == b"Transparency");
+    soft_mask || opacity || blend || group
+}
@@ -0,0 +1,497 @@
+use lopdf::{dictionary, Document, Object, Stream};
+use std::{
+    fs,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use crate::config::Reflow;
+
+/// Names of the fonts of body text and headings in the resources of each page
+const FONT: &str = "Text";
+const HEADING_FONT: &str = "Heading";
+/// Average width of Helvetica characters, in ems, a little wide so lines do not run into the
+/// margin
+const CHAR_WIDTH: f32 = 0.55;
+/// Distance between baselines, in ems
+const LEADING: f32 = 1.4;
+/// Space after paragraphs, in ems
+const PARAGRAPH_SPACING: f32 = 0.6;
+/// Headings are this much larger than body text
+const HEADING_SCALE: f32 = 1.4;
+/// Pages are this much taller than they are wide, like a paperback
+const PAGE_RATIO: f32 = 1.5;
+
+/// Elements that start a new paragraph
+const BLOCKS: [&str; 18] = [
+    "address",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dt",
+    "figcaption",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "p",
+    "pre",
+    "tr",
+];
+/// Elements whose text is not part of the book
+const HIDDEN: [&str; 3] = ["head", "script", "style"];
+
+/// Paragraph of a chapter
+struct Block {
+    text: String,
+    heading: bool,
+}
+
+/// Open an EPUB document as a document with its text laid out on pages of the given size, in
+/// the reading order of its spine
+//TODO: images, styles, and links of the book, and fonts for text outside of Latin-1
+pub fn load(path: &Path, reflow: Reflow) -> Result<Document, String> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path).map_err(|err| err.to_string())?)
+        .map_err(|err| err.to_string())?;
+    let container = read(&mut archive, "META-INF/container.xml")?;
+    let package_path = tokens(&container)
+        .into_iter()
+        .find_map(|token| match token {
+            Token::Open("rootfile", attrs, _) => attribute(attrs, "full-path"),
+            _ => None,
+        })
+        .ok_or_else(|| format!("no package in {path:?}"))?;
+    let package = read(&mut archive, &package_path)?;
+    let package_tokens = tokens(&package);
+    let mut manifest = Vec::new();
+    let mut spine = Vec::new();
+    let mut title = None;
+    for (i, token) in package_tokens.iter().enumerate() {
+        match token {
+            Token::Open("item", attrs, _) => {
+                if let (Some(id), Some(href)) = (attribute(attrs, "id"), attribute(attrs, "href")) {
+                    manifest.push((id, href));
+                }
+            }
+            Token::Open("itemref", attrs, _) => spine.extend(attribute(attrs, "idref")),
+            Token::Open("title", _, false) if title.is_none() => {
+                if let Some(Token::Text(text)) = package_tokens.get(i + 1) {
+                    title = Some(collapse(&decode_entities(text)));
+                }
+            }
+            _ => {}
+        }
+    }
+    // Items are named relative to the package
+    let base = package_path
+        .rsplit_once('/')
+        .map_or("", |(base, _)| base)
+        .to_string();
+    let mut chapters = Vec::new();
+    for idref in spine {
+        let Some((_, href)) = manifest.iter().find(|(id, _)| *id == idref) else {
+            continue;
+        };
+        let name = resolve(&base, href);
+        match read(&mut archive, &name) {
+            Ok(xhtml) => chapters.push(blocks(&xhtml)),
+            Err(err) => log::warn!("failed to read {name:?} in {path:?}: {err}"),
+        }
+    }
+    document(chapters, title, reflow, path)
+}
+
+/// Document with each chapter starting on a new page
+fn document(
+    chapters: Vec<Vec<Block>>,
+    title: Option<String>,
+    reflow: Reflow,
+    path: &Path,
+) -> Result<Document, String> {
+    let width = f32::from(reflow.width);
+    let height = width * PAGE_RATIO;
+    let margin = f32::from(reflow.margin).min(width / 4.0);
+    let font_size = f32::from(reflow.font_size.max(1));
+    let mut pages: Vec<Vec<u8>> = Vec::new();
+    for chapter in chapters.iter().filter(|chapter| !chapter.is_empty()) {
+        let mut content = Vec::new();
+        let mut y = height - margin;
+        for block in chapter {
+            let (font, size) = if block.heading {
+                (HEADING_FONT, font_size * HEADING_SCALE)
+            } else {
+                (FONT, font_size)
+            };
+            for line in wrap(&block.text, width - 2.0 * margin, size) {
+                if y - size * LEADING < margin && !content.is_empty() {
+                    pages.push(content);
+                    content = Vec::new();
+                    y = height - margin;
+                }
+                y -= size * LEADING;
+                content.extend(format!("BT /{font} {size} Tf {margin} {y} Td ").into_bytes());
+                content.extend(literal(&line));
+                content.extend(b" Tj ET\n");
+            }
+            y -= size * PARAGRAPH_SPACING;
+        }
+        if !content.is_empty() {
+            pages.push(content);
+        }
+    }
+    if pages.is_empty() {
+        return Err(format!("no text in {path:?}"));
+    }
+
+    let mut doc = Document::with_version("1.7");
+    let pages_id = doc.new_object_id();
+    let font = |name: &str| {
+        dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => name,
+            "Encoding" => "WinAnsiEncoding",
+        }
+    };
+    let font_id = doc.add_object(font("Helvetica"));
+    let heading_font_id = doc.add_object(font("Helvetica-Bold"));
+    let mut kids = Vec::new();
+    for content in pages {
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
+            "Contents" => content_id,
+            "Resources" => dictionary! {
+                "Font" => dictionary! {
+                    FONT => font_id,
+                    HEADING_FONT => heading_font_id,
+                },
+            },
+        });
+        kids.push(page_id.into());
+    }
+    let count = kids.len() as i64;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    if let Some(title) = title {
+        let info_id = doc.add_object(dictionary! {
+            "Title" => Object::string_literal(title),
+        });
+        doc.trailer.set("Info", info_id);
+    }
+    Ok(doc)
+}
+
+fn read(archive: &mut zip::ZipArchive<impl Read + Seek>, name: &str) -> Result<String, String> {
+    let mut data = Vec::new();
+    archive
+        .by_name(name)
+        .map_err(|err| err.to_string())?
+        .read_to_end(&mut data)
+        .map_err(|err| err.to_string())?;
+    Ok(String::from_utf8_lossy(&data).into_owned())
+}
+
+/// Name in the archive of a link relative to a directory in it
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let mut parts: Vec<&str> = base.split('/').filter(|part| !part.is_empty()).collect();
+    for part in href.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    percent_decode(&parts.join("/"))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Part of an XML document
+enum Token<'a> {
+    /// Element name without its namespace prefix, its attributes, and whether it has no content
+    Open(&'a str, &'a str, bool),
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// Tags and text of an XML document, skipping comments, declarations, and processing
+/// instructions
+fn tokens(xml: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = xml;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            tokens.push(Token::Text(rest));
+            break;
+        };
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        rest = &rest[start..];
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            tokens.push(Token::Text(&cdata[..end]));
+            rest = cdata.get(end + 3..).unwrap_or_default();
+            continue;
+        }
+        let terminator = if rest.starts_with("<!--") { "-->" } else { ">" };
+        let Some(end) = rest.find(terminator) else {
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + terminator.len()..];
+        if tag.starts_with(['!', '?']) {
+            continue;
+        }
+        match tag.strip_prefix('/') {
+            Some(tag) => tokens.push(Token::Close(local_name(tag))),
+            None => {
+                let qualified = tag
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or_default();
+                let empty = tag.ends_with('/');
+                let attrs = tag[qualified.len()..].trim_end_matches('/');
+                tokens.push(Token::Open(local_name(qualified), attrs, empty));
+            }
+        }
+    }
+    tokens
+}
+
+/// Name of an element without its namespace prefix
+fn local_name(tag: &str) -> &str {
+    let name = tag
+        .split(|c: char| c.is_whitespace() || c == '/')
+        .next()
+        .unwrap_or_default();
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Value of an attribute of a tag
+fn attribute(attrs: &str, key: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(i) = rest.find(key) {
+        let before = rest[..i].chars().next_back();
+        let after = rest[i + key.len()..].trim_start();
+        rest = &rest[i + key.len()..];
+        if !before.map_or(true, char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+        let value = &value[1..];
+        let end = value.find(quote)?;
+        return Some(decode_entities(&value[..end]));
+    }
+    None
+}
+
+/// Paragraphs of the body of a chapter
+fn blocks(xhtml: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut hidden = 0;
+    let mut heading = 0;
+    let mut flush = |text: &mut String, heading: bool| {
+        let collapsed = collapse(text);
+        if !collapsed.is_empty() {
+            blocks.push(Block {
+                text: collapsed,
+                heading,
+            });
+        }
+        text.clear();
+    };
+    for token in tokens(xhtml) {
+        match token {
+            Token::Open(name, _, empty) => {
+                let name = name.to_ascii_lowercase();
+                if BLOCKS.contains(&name.as_str()) {
+                    flush(&mut text, heading > 0);
+                }
+                if empty {
+                    continue;
+                }
+                if HIDDEN.contains(&name.as_str()) {
+                    hidden += 1;
+                } else if is_heading(&name) {
+                    heading += 1;
+                }
+            }
+            Token::Close(name) => {
+                let name = name.to_ascii_lowercase();
+                if BLOCKS.contains(&name.as_str()) {
+                    flush(&mut text, heading > 0);
+                }
+                if HIDDEN.contains(&name.as_str()) {
+                    hidden -= 1;
+                } else if is_heading(&name) {
+                    heading -= 1;
+                }
+            }
+            Token::Text(content) if hidden == 0 => text.push_str(&decode_entities(content)),
+            Token::Text(_) => {}
+        }
+    }
+    flush(&mut text, false);
+    blocks
+}
+
+fn is_heading(name: &str) -> bool {
+    matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// Text with each run of white space made a single space, as HTML shows it
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';').filter(|end| *end <= 10) else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let c = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            _ => match entity.strip_prefix('#') {
+                Some(number) => match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => number.parse().ok(),
+                }
+                .and_then(char::from_u32),
+                None => None,
+            },
+        };
+        match c {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Lines of a paragraph that fit in a width, breaking words longer than a line
+fn wrap(text: &str, width: f32, size: f32) -> Vec<String> {
+    let max_chars = ((width / (size * CHAR_WIDTH)) as usize).max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..max_chars).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// PDF literal string of text in the Windows-1252 encoding of the fonts, with characters it does
+/// not have replaced
+fn literal(text: &str) -> Vec<u8> {
+    let mut literal = vec![b'('];
+    for c in text.chars() {
+        let byte = match c {
+            '(' | ')' | '\\' => {
+                literal.push(b'\\');
+                c as u8
+            }
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        };
+        literal.push(byte);
+    }
+    literal.push(b')');
+    literal
+}
@@ -0,0 +1,324 @@
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+use std::path::{Path, PathBuf};
+
+use crate::{fl, pdf};
+
+/// Resource names used by the stamp, unlikely to collide with those of the page
+const STAMP_FONT: &str = "CosmicReaderStampFont";
+const STAMP_IMAGE: &str = "CosmicReaderStampImage";
+const STAMP_STATE: &str = "CosmicReaderStampState";
+/// Average width of Helvetica characters, in ems
+const STAMP_CHAR_WIDTH: f32 = 0.55;
+/// Stamps are sized to this fraction of the page width before rotation
+const STAMP_WIDTH: f32 = 0.6;
+/// Space between corner stamps and the page edges, as a fraction of the page width
+const STAMP_MARGIN: f32 = 0.05;
+
+/// Where a stamp is placed on each page
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum StampPosition {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl StampPosition {
+    pub const ALL: [Self; 7] = [
+        Self::Center,
+        Self::Top,
+        Self::Bottom,
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomLeft,
+        Self::BottomRight,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Center => fl!("center"),
+            Self::Top => fl!("top"),
+            Self::Bottom => fl!("bottom"),
+            Self::TopLeft => fl!("top-left"),
+            Self::TopRight => fl!("top-right"),
+            Self::BottomLeft => fl!("bottom-left"),
+            Self::BottomRight => fl!("bottom-right"),
+        }
+    }
+
+    /// Fractions of the page width and height from the bottom left
+    fn anchor(&self) -> (f32, f32) {
+        match self {
+            Self::Center => (0.5, 0.5),
+            Self::Top => (0.5, 1.0),
+            Self::Bottom => (0.5, 0.0),
+            Self::TopLeft => (0.0, 1.0),
+            Self::TopRight => (1.0, 1.0),
+            Self::BottomLeft => (0.0, 0.0),
+            Self::BottomRight => (1.0, 0.0),
+        }
+    }
+}
+
+/// Text or image drawn over every page of an exported copy
+#[derive(Clone, Debug)]
+pub struct Stamp {
+    pub text: String,
+    /// Image drawn instead of the text, if set
+    pub image: Option<PathBuf>,
+    pub position: StampPosition,
+    /// Counterclockwise rotation in degrees
+    pub rotation: i16,
+    /// Opacity in percent
+    pub opacity: u8,
+}
+
+impl Default for Stamp {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            image: None,
+            position: StampPosition::default(),
+            rotation: 45,
+            opacity: 30,
+        }
+    }
+}
+
+/// Margins removed from pages of an exported copy by changing their crop box
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Crop {
+    /// Top, right, bottom, and left margins as displayed, in PDF units
+    pub margins: [f32; 4],
+    /// Only crop this page instead of all pages
+    pub page_id: Option<ObjectId>,
+}
+
+impl Crop {
+    /// Margins by the edges of the page before rotation, for a clockwise rotation in degrees
+    pub fn rotated_margins(&self, rotate: i64) -> [f32; 4] {
+        let turns = (rotate.rem_euclid(360) / 90) as usize;
+        let mut margins = [0.0; 4];
+        for (i, margin) in self.margins.iter().enumerate() {
+            margins[(i + 4 - turns) % 4] = *margin;
+        }
+        margins
+    }
+
+    pub fn applies_to(&self, page_id: ObjectId) -> bool {
+        self.page_id.map_or(true, |x| x == page_id)
+    }
+}
+
+/// Changes made to the exported copy of a document
+#[derive(Clone, Debug, Default)]
+pub struct Export {
+    pub crop: Option<Crop>,
+    pub stamp: Option<Stamp>,
+}
+
+/// Export a copy of a document with changes, leaving the document as it is
+pub fn export(doc: &Document, export: &Export, path: &Path) -> Result<(), lopdf::Error> {
+    let mut doc = doc.clone();
+    // Crop first so stamps are placed on the visible area
+    if let Some(crop) = &export.crop {
+        crop_pages(&mut doc, crop)?;
+    }
+    if let Some(stamp) = &export.stamp {
+        add_stamp(&mut doc, stamp)?;
+    }
+    doc.save(path)?;
+    Ok(())
+}
+
+/// File name suggested for an exported copy
+pub fn export_file_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    format!("{stem}-export.pdf")
+}
+
+fn crop_pages(doc: &mut Document, crop: &Crop) -> Result<(), lopdf::Error> {
+    let page_ids: Vec<ObjectId> = doc.page_iter().collect();
+    for page_id in page_ids {
+        if !crop.applies_to(page_id) {
+            continue;
+        }
+        let Some(page_box) = pdf::page_box(doc, page_id) else {
+            continue;
+        };
+        let [top, right, bottom, left] = crop.rotated_margins(page_box.rotate);
+        let rect = page_box.rect;
+        let (x1, y1) = (rect.x + left, rect.y + bottom);
+        let (x2, y2) = (rect.x + rect.width - right, rect.y + rect.height - top);
+        // Margins that would leave nothing of the page are ignored
+        if x2 <= x1 || y2 <= y1 {
+            log::warn!("crop margins larger than page {page_id:?}");
+            continue;
+        }
+        doc.get_object_mut(page_id)
+            .and_then(Object::as_dict_mut)?
+            .set(
+                "CropBox",
+                vec![
+                    Object::Real(x1),
+                    Object::Real(y1),
+                    Object::Real(x2),
+                    Object::Real(y2),
+                ],
+            );
+    }
+    Ok(())
+}
+
+fn add_stamp(doc: &mut Document, stamp: &Stamp) -> Result<(), lopdf::Error> {
+    let state_id = doc.add_object(dictionary! {
+        "Type" => "ExtGState",
+        "ca" => f32::from(stamp.opacity.min(100)) / 100.0,
+        "CA" => f32::from(stamp.opacity.min(100)) / 100.0,
+    });
+    let (stamp_id, stamp_size) = match &stamp.image {
+        Some(path) => {
+            let image = lopdf::xobject::image(path)?;
+            let width = image.dict.get(b"Width")?.as_float()?;
+            let height = image.dict.get(b"Height")?.as_float()?;
+            (doc.add_object(image), Some((width, height)))
+        }
+        None => (
+            doc.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Helvetica",
+                "Encoding" => "WinAnsiEncoding",
+            }),
+            None,
+        ),
+    };
+
+    let page_ids: Vec<ObjectId> = doc.page_iter().collect();
+    for page_id in page_ids {
+        let Some(page_box) = pdf::page_box(doc, page_id) else {
+            continue;
+        };
+        let rect = page_box.rect;
+        // Rotated pages are stamped upright as displayed
+        //TODO: place the stamp by the displayed edges of rotated pages too
+        let angle = (f32::from(stamp.rotation) + page_box.rotate as f32).to_radians();
+        let (sin, cos) = angle.sin_cos();
+        let width = rect.width * STAMP_WIDTH;
+        // Size of the stamp before rotation
+        let (stamp_width, stamp_height) = match stamp_size {
+            Some((image_width, image_height)) => (width, width * image_height / image_width),
+            None => {
+                let chars = stamp.text.chars().count().max(1) as f32;
+                let font_size = width / (chars * STAMP_CHAR_WIDTH);
+                (width, font_size)
+            }
+        };
+        // Keep the rotated stamp inside the page at corners and edges
+        let extent_x = (stamp_width * cos.abs() + stamp_height * sin.abs()) / 2.0;
+        let extent_y = (stamp_width * sin.abs() + stamp_height * cos.abs()) / 2.0;
+        let margin = rect.width * STAMP_MARGIN;
+        let (anchor_x, anchor_y) = stamp.position.anchor();
+        let center_x = rect.x
+            + margin
+            + extent_x
+            + anchor_x * (rect.width - 2.0 * (margin + extent_x)).max(0.0);
+        let center_y = rect.y
+            + margin
+            + extent_y
+            + anchor_y * (rect.height - 2.0 * (margin + extent_y)).max(0.0);
+        // Rotate about the center of the stamp
+        let x = center_x - (stamp_width * cos - stamp_height * sin) / 2.0;
+        let y = center_y - (stamp_width * sin + stamp_height * cos) / 2.0;
+
+        let content = match stamp_size {
+            Some(_) => {
+                doc.add_xobject(page_id, STAMP_IMAGE, stamp_id)?;
+                format!(
+                    "q /{STAMP_STATE} gs {} {} {} {} {x} {y} cm /{STAMP_IMAGE} Do Q",
+                    stamp_width * cos,
+                    stamp_width * sin,
+                    -stamp_height * sin,
+                    stamp_height * cos,
+                )
+            }
+            None => {
+                add_font(doc, page_id, stamp_id)?;
+                // Text is drawn from its baseline, a little above the bottom of the stamp
+                let baseline = stamp_height * 0.2;
+                format!(
+                    "q /{STAMP_STATE} gs BT /{STAMP_FONT} {} Tf 0.8 0 0 rg {cos} {sin} {} {cos} {} {} Tm {} Tj ET Q",
+                    stamp_height,
+                    -sin,
+                    x - baseline * sin,
+                    y + baseline * cos,
+                    literal(&stamp.text),
+                )
+            }
+        };
+        doc.add_graphics_state(page_id, STAMP_STATE, state_id)?;
+        wrap_contents(doc, page_id, content.into_bytes())?;
+    }
+    Ok(())
+}
+
+fn add_font(doc: &mut Document, page_id: ObjectId, font_id: ObjectId) -> Result<(), lopdf::Error> {
+    let resources = doc
+        .get_or_create_resources(page_id)
+        .and_then(Object::as_dict_mut)?;
+    if !resources.has(b"Font") {
+        resources.set("Font", lopdf::Dictionary::new());
+    }
+    resources
+        .get_mut(b"Font")
+        .and_then(Object::as_dict_mut)?
+        .set(STAMP_FONT, Object::Reference(font_id));
+    Ok(())
+}
+
+/// Add content after the contents of a page, isolated from any graphics state the page leaves
+fn wrap_contents(
+    doc: &mut Document,
+    page_id: ObjectId,
+    content: Vec<u8>,
+) -> Result<(), lopdf::Error> {
+    let mut contents = match doc.get_dictionary(page_id)?.get(b"Contents") {
+        Ok(Object::Array(array)) => array.clone(),
+        Ok(contents) => vec![contents.clone()],
+        Err(_) => Vec::new(),
+    };
+    let before = doc.add_object(Stream::new(lopdf::Dictionary::new(), b"q".to_vec()));
+    let mut after = b"Q ".to_vec();
+    after.extend(content);
+    let after = doc.add_object(Stream::new(lopdf::Dictionary::new(), after));
+    contents.insert(0, Object::Reference(before));
+    contents.push(Object::Reference(after));
+    doc.get_object_mut(page_id)
+        .and_then(Object::as_dict_mut)?
+        .set("Contents", contents);
+    Ok(())
+}
+
+/// PDF literal string, with characters outside of WinAnsi replaced
+fn literal(text: &str) -> String {
+    let mut literal = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            ' '..='~' => literal.push(c),
+            _ => literal.push('?'),
+        }
+    }
+    literal.push(')');
+    literal
+}
@@ -0,0 +1,29 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{comic, picture};
+
+/// Document after or before this one in its folder, by file name
+pub fn sibling(path: &Path, next: bool) -> Option<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+                || comic::is_comic(path)
+                || picture::is_picture(path)
+        })
+        .collect();
+    paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    let index = paths.iter().position(|x| x == path)?;
+    if next {
+        paths.get(index + 1).cloned()
+    } else {
+        paths.get(index.checked_sub(1)?).cloned()
+    }
+}
@@ -0,0 +1,28 @@
+use lopdf::Document;
+use std::{fs, io::Read, path::Path};
+
+use crate::{comic, config::Reflow, epub, picture};
+
+/// Load a document of any format that can be opened, with books laid out as reflow says
+pub fn load(path: &Path, reflow: Reflow) -> Result<Document, String> {
+    if is_epub(path) {
+        epub::load(path, reflow)
+    } else if comic::is_comic(path) {
+        comic::load(path)
+    } else if picture::is_picture(path) {
+        picture::load(path)
+    } else {
+        Document::load(path).map_err(|err| err.to_string())
+    }
+}
+
+/// Whether a file is an EPUB document, from its contents as names may be wrong or missing
+pub fn is_epub(path: &Path) -> bool {
+    let mut header = [0; 58];
+    let len = fs::File::open(path)
+        .and_then(|mut file| file.read(&mut header))
+        .unwrap_or(0);
+    // EPUB requires an uncompressed mimetype file first in its zip archive
+    header[..len].starts_with(b"PK\x03\x04")
+        && header.get(30..len) == Some(b"mimetypeapplication/epub+zip")
+}
@@ -0,0 +1,269 @@
+use cosmic::iced::{touch::Finger, Point, Vector};
+use std::time::{Duration, Instant};
+
+const SWIPE_DURATION: Duration = Duration::from_millis(200);
+/// Fraction of the width a swipe must travel to turn the page
+pub const SWIPE_THRESHOLD: f32 = 0.2;
+/// Swipes past the first or last page move this much of the finger's distance
+pub const EDGE_RESISTANCE: f32 = 0.3;
+
+/// Finger being tracked on the canvas
+pub struct Touch {
+    pub id: Finger,
+    pub start: Point,
+    pub position: Point,
+    /// The page fits the width of the view, so dragging sideways turns pages
+    pub swipe: bool,
+    /// Smoothed velocity in pixels per second, and when the finger last moved
+    pub velocity: Vector,
+    pub moved: Instant,
+}
+
+impl Touch {
+    pub fn new(id: Finger, position: Point, swipe: bool) -> Self {
+        Self {
+            id,
+            start: position,
+            position,
+            swipe,
+            velocity: Vector::new(0.0, 0.0),
+            moved: Instant::now(),
+        }
+    }
+
+    pub fn moved(&mut self, position: Point) -> Vector {
+        let now = Instant::now();
+        let delta = position - self.position;
+        let dt = now.duration_since(self.moved).as_secs_f32();
+        if dt > 0.0 {
+            self.velocity = delta * (0.8 / dt) + self.velocity * 0.2;
+        }
+        self.position = position;
+        self.moved = now;
+        delta
+    }
+}
+
+/// Horizontal offset of the pages while swiping, in pixels
+pub struct Swipe {
+    offset: f32,
+    released: Option<Instant>,
+}
+
+impl Swipe {
+    pub fn dragging(offset: f32) -> Self {
+        Self {
+            offset,
+            released: None,
+        }
+    }
+
+    /// Ease back to no offset, which slides in the new page if it was turned
+    pub fn released(offset: f32) -> Self {
+        Self {
+            offset,
+            released: Some(Instant::now()),
+        }
+    }
+
+    pub fn offset(&self) -> f32 {
+        match self.released {
+            Some(released) => {
+                let progress =
+                    (released.elapsed().as_secs_f32() / SWIPE_DURATION.as_secs_f32()).min(1.0);
+                // Ease out
+                self.offset * (1.0 - progress).powi(3)
+            }
+            None => self.offset,
+        }
+    }
+
+    pub fn animating(&self) -> bool {
+        self.released.is_some()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.released
+            .is_some_and(|released| released.elapsed() >= SWIPE_DURATION)
+    }
+}
+
+/// Value eased out to zero, for animating page and zoom changes
+pub struct Ease {
+    from: f32,
+    started: Instant,
+}
+
+impl Ease {
+    pub fn new(from: f32) -> Self {
+        Self {
+            from,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn value(&self) -> f32 {
+        let progress =
+            (self.started.elapsed().as_secs_f32() / SWIPE_DURATION.as_secs_f32()).min(1.0);
+        self.from * (1.0 - progress).powi(3)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.started.elapsed() >= SWIPE_DURATION
+    }
+}
+
+/// Velocity decays by a factor of e per this fraction of a second
+const FLING_FRICTION: f32 = 4.0;
+/// Flings stop below this speed, in PDF units per second
+const FLING_MIN_SPEED: f32 = 10.0;
+/// A finger that rests this long before lifting does not fling
+pub const FLING_REST: Duration = Duration::from_millis(100);
+
+/// Panning that continues after a touch drag, slowed by friction
+pub struct Fling {
+    velocity: Vector,
+    last: Instant,
+}
+
+impl Fling {
+    /// Velocity is in PDF units per second, None if it is too slow to fling
+    pub fn new(velocity: Vector) -> Option<Self> {
+        if speed(velocity) < FLING_MIN_SPEED {
+            return None;
+        }
+        Some(Self {
+            velocity,
+            last: Instant::now(),
+        })
+    }
+
+    /// Pan since the last step, None once the fling has stopped
+    pub fn step(&mut self, now: Instant) -> Option<Vector> {
+        let dt = now.saturating_duration_since(self.last).as_secs_f32();
+        self.last = now;
+        let offset = self.velocity * dt;
+        self.velocity = self.velocity * (-FLING_FRICTION * dt).exp();
+        if speed(self.velocity) < FLING_MIN_SPEED {
+            None
+        } else {
+            Some(offset)
+        }
+    }
+}
+
+fn speed(velocity: Vector) -> f32 {
+    velocity.x.hypot(velocity.y)
+}
+
+/// Distance the cursor can move from the autoscroll anchor without scrolling, in pixels
+const AUTOSCROLL_DEAD_ZONE: f32 = 8.0;
+/// Pixels scrolled per second for each pixel of cursor distance past the dead zone
+const AUTOSCROLL_SPEED: f32 = 4.0;
+
+/// Scrolling started by a middle-click, moving toward the cursor at a speed set by its
+/// distance from where the click was
+pub struct Autoscroll {
+    /// Velocity in PDF units per second
+    pub velocity: Vector,
+    last: Instant,
+}
+
+impl Autoscroll {
+    pub fn new(velocity: Vector) -> Self {
+        Self {
+            velocity,
+            last: Instant::now(),
+        }
+    }
+
+    /// Velocity in pixels per second for the cursor's distance from the anchor
+    pub fn velocity(displacement: Vector) -> Vector {
+        let distance = speed(displacement);
+        if distance <= AUTOSCROLL_DEAD_ZONE {
+            return Vector::new(0.0, 0.0);
+        }
+        displacement * ((distance - AUTOSCROLL_DEAD_ZONE) * AUTOSCROLL_SPEED / distance)
+    }
+
+    /// Pan since the last step
+    pub fn step(&mut self, now: Instant) -> Vector {
+        let dt = now.saturating_duration_since(self.last).as_secs_f32();
+        self.last = now;
+        self.velocity * dt
+    }
+}
+
+/// Two fingers on the canvas, zooming when pinched, panning when moved together, and turning
+/// the page when twisted
+pub struct TwoFinger {
+    pub fingers: [(Finger, Point); 2],
+    start_angle: f32,
+}
+
+impl TwoFinger {
+    pub fn new(first: (Finger, Point), second: (Finger, Point)) -> Self {
+        let fingers = [first, second];
+        Self {
+            fingers,
+            start_angle: angle(&fingers),
+        }
+    }
+
+    /// Zoom factor and pan in pixels of moving a finger, from the change in distance between
+    /// the fingers and the movement of the point between them
+    pub fn pinched(&mut self, id: Finger, position: Point) -> (f32, Vector) {
+        let (spread, middle) = (distance(&self.fingers), center(&self.fingers));
+        self.moved(id, position);
+        let factor = if spread > 0.0 {
+            distance(&self.fingers) / spread
+        } else {
+            1.0
+        };
+        (factor, center(&self.fingers) - middle)
+    }
+
+    /// Point between the fingers
+    pub fn center(&self) -> Point {
+        center(&self.fingers)
+    }
+
+    pub fn contains(&self, id: Finger) -> bool {
+        self.fingers.iter().any(|(finger, _)| *finger == id)
+    }
+
+    pub fn moved(&mut self, id: Finger, position: Point) {
+        for finger in self.fingers.iter_mut() {
+            if finger.0 == id {
+                finger.1 = position;
+            }
+        }
+    }
+
+    /// Clockwise rotation since the fingers were placed, in degrees from -180 to 180
+    pub fn rotation(&self) -> f32 {
+        (angle(&self.fingers) - self.start_angle + 180.0).rem_euclid(360.0) - 180.0
+    }
+
+    /// Rotation snapped to the nearest quarter turn
+    pub fn snapped_rotation(&self) -> i64 {
+        (self.rotation() / 90.0).round() as i64 * 90
+    }
+}
+
+fn distance(fingers: &[(Finger, Point); 2]) -> f32 {
+    fingers[0].1.distance(fingers[1].1)
+}
+
+fn center(fingers: &[(Finger, Point); 2]) -> Point {
+    Point::new(
+        (fingers[0].1.x + fingers[1].1.x) / 2.0,
+        (fingers[0].1.y + fingers[1].1.y) / 2.0,
+    )
+}
+
+/// Clockwise angle of the line between two fingers, in degrees, as y points down
+fn angle(fingers: &[(Finger, Point); 2]) -> f32 {
+    let delta = fingers[1].1 - fingers[0].1;
+    delta.y.atan2(delta.x).to_degrees()
+}
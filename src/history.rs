@@ -0,0 +1,91 @@
+use lopdf::ObjectId;
+
+use crate::{export, note};
+
+/// Part of the document changed by an edit, as it was before the edit
+#[derive(Clone, Debug)]
+pub enum Edit {
+    /// Notes of a page
+    Notes(ObjectId, Vec<note::Note>),
+    /// Crop and stamp of exported copies
+    Export(Box<ExportEdit>),
+}
+
+/// Crop and stamp of exported copies, with the text typed for them
+#[derive(Clone, Debug)]
+pub struct ExportEdit {
+    pub crop_inputs: [String; 4],
+    pub crop_margins: [f32; 4],
+    pub crop_current_page: bool,
+    pub stamp: export::Stamp,
+    pub stamp_image_input: String,
+}
+
+/// What an edit does, so edits of the same field in a row, such as typing, are undone together
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EditKind {
+    NoteAdd,
+    NoteDelete,
+    /// Text of a note, by its page and index
+    NoteText(ObjectId, usize),
+    CropMargin(usize),
+    CropCurrentPage,
+    StampImage,
+    StampOpacity,
+    StampPosition,
+    StampRotation,
+    StampText,
+}
+
+impl EditKind {
+    fn merges(&self) -> bool {
+        !matches!(self, Self::NoteAdd | Self::NoteDelete)
+    }
+}
+
+/// Edits of the document that can be undone and redone
+#[derive(Debug, Default)]
+pub struct History {
+    /// State before each edit, for undo, and before each undo, for redo
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    last: Option<EditKind>,
+}
+
+impl History {
+    /// Remember the state before an edit, unless the edit continues the last one
+    pub fn push(&mut self, kind: EditKind, before: Edit) {
+        if kind.merges() && self.last == Some(kind) {
+            return;
+        }
+        self.undo.push(before);
+        self.redo.clear();
+        self.last = Some(kind);
+    }
+
+    /// State that undo restores, to find the current state of what it changed
+    pub fn next_undo(&self) -> Option<&Edit> {
+        self.undo.last()
+    }
+
+    /// State that redo restores, to find the current state of what it changed
+    pub fn next_redo(&self) -> Option<&Edit> {
+        self.redo.last()
+    }
+
+    /// State to restore to undo the last edit, given the current state of what it changed
+    pub fn undo(&mut self, current: Edit) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        self.redo.push(current);
+        self.last = None;
+        Some(edit)
+    }
+
+    /// State to restore to redo the last undone edit, given the current state of what it changed
+    pub fn redo(&mut self, current: Edit) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.undo.push(current);
+        self.last = None;
+        Some(edit)
+    }
+}
@@ -0,0 +1,122 @@
+use cosmic::iced::{futures::channel::mpsc, stream, Subscription};
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::DirBuilderExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::Arc,
+    thread,
+};
+
+/// Socket of the instance showing a document, named by a hash of its canonical path
+fn socket_path(path: &Path) -> Option<PathBuf> {
+    Some(
+        PathBuf::from(env::var_os("XDG_RUNTIME_DIR")?)
+            .join("cosmic-reader")
+            .join(format!(
+                "{:016x}.sock",
+                fnv1a(path.as_os_str().as_encoded_bytes())
+            )),
+    )
+}
+
+/// 64 bit FNV-1a hash, which unlike the hasher of the standard library is the same in every
+/// build, so instances built by different compilers find each other
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Socket listening for other instances, removed when dropped
+pub struct Listener {
+    socket: PathBuf,
+    listener: UnixListener,
+}
+
+impl Listener {
+    /// Another handle to the socket, for accepting connections
+    pub fn try_clone(&self) -> Option<UnixListener> {
+        self.listener
+            .try_clone()
+            .map_err(|err| log::warn!("failed to clone listener: {}", err))
+            .ok()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket);
+    }
+}
+
+/// Tell an instance already showing the document to go to a page and raise its window,
+/// returning false if there is none
+pub fn forward(path: &Path, page: Option<u16>) -> bool {
+    let Some(mut stream) = socket_path(path).and_then(|socket| UnixStream::connect(socket).ok())
+    else {
+        return false;
+    };
+    let command = match page {
+        Some(page) => format!("goto {page}\n"),
+        None => "raise\n".to_string(),
+    };
+    match stream.write_all(command.as_bytes()) {
+        Ok(()) => true,
+        Err(err) => {
+            log::warn!("failed to forward to running instance: {}", err);
+            false
+        }
+    }
+}
+
+/// Listen for other instances opening the document, unless another instance already is
+pub fn listen(path: &Path) -> Option<Listener> {
+    let socket = socket_path(path)?;
+    let _ = fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(socket.parent()?);
+    if UnixStream::connect(&socket).is_ok() {
+        return None;
+    }
+    // Nothing answered on the socket, so it was left by an instance that did not exit cleanly
+    let _ = fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)
+        .map_err(|err| log::warn!("failed to listen on {:?}: {}", socket, err))
+        .ok()?;
+    Some(Listener { socket, listener })
+}
+
+/// Pages forwarded by other instances, none to only raise the window
+pub fn subscription(listener: Arc<UnixListener>) -> Subscription<Option<u16>> {
+    struct Forwarded;
+
+    Subscription::run_with_id(
+        std::any::TypeId::of::<Forwarded>(),
+        stream::channel(1, move |mut output: mpsc::Sender<Option<u16>>| async move {
+            // Accepting blocks, so it has its own thread
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let mut line = String::new();
+                    if BufReader::new(stream).read_line(&mut line).is_err() {
+                        continue;
+                    }
+                    let page = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                        ["goto", page] => page.parse().ok(),
+                        _ => None,
+                    };
+                    if let Err(err) = output.try_send(page) {
+                        if err.is_disconnected() {
+                            break;
+                        }
+                    }
+                }
+            });
+            std::future::pending::<()>().await;
+        }),
+    )
+}
@@ -0,0 +1,457 @@
+use cosmic::{
+    iced::keyboard::{key::Named, Key, Modifiers},
+    widget::menu,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+use crate::fl;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Modifier {
+    Super,
+    Ctrl,
+    Alt,
+    Shift,
+}
+
+/// Key combination, with the key stored by name so it can be saved in the config
+#[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct KeyBind {
+    pub modifiers: Vec<Modifier>,
+    pub key: String,
+}
+
+impl KeyBind {
+    pub fn new(modifiers: &[Modifier], key: &str) -> Self {
+        Self {
+            modifiers: modifiers.to_vec(),
+            key: key.to_string(),
+        }
+    }
+
+    /// Key bind for a key press, None if only a modifier was pressed
+    pub fn from_event(key: &Key, modifiers: Modifiers) -> Option<Self> {
+        let key = match key {
+            Key::Named(Named::Alt | Named::Control | Named::Meta | Named::Shift | Named::Super) => {
+                return None
+            }
+            Key::Named(named) => format!("{:?}", named),
+            Key::Character(c) => c.to_lowercase(),
+            Key::Unidentified => return None,
+        };
+        let mut bind = Self {
+            modifiers: Vec::new(),
+            key,
+        };
+        for (modifier, pressed) in [
+            (Modifier::Super, modifiers.logo()),
+            (Modifier::Ctrl, modifiers.control()),
+            (Modifier::Alt, modifiers.alt()),
+            (Modifier::Shift, modifiers.shift()),
+        ] {
+            if pressed {
+                bind.modifiers.push(modifier);
+            }
+        }
+        Some(bind)
+    }
+
+    /// Key bind shown in menus, None if the key name is not known
+    pub fn to_menu(&self) -> Option<menu::KeyBind> {
+        let key = if self.key.chars().count() == 1 {
+            Key::Character(self.key.as_str().into())
+        } else {
+            Key::Named(
+                NAMED_KEYS
+                    .into_iter()
+                    .find(|named| format!("{:?}", named) == self.key)?,
+            )
+        };
+        Some(menu::KeyBind {
+            modifiers: self
+                .modifiers
+                .iter()
+                .map(|modifier| match modifier {
+                    Modifier::Super => menu::key_bind::Modifier::Super,
+                    Modifier::Ctrl => menu::key_bind::Modifier::Ctrl,
+                    Modifier::Alt => menu::key_bind::Modifier::Alt,
+                    Modifier::Shift => menu::key_bind::Modifier::Shift,
+                })
+                .collect(),
+            key,
+        })
+    }
+}
+
+//TODO: is there a way to parse any named key?
+const NAMED_KEYS: [Named; 31] = [
+    Named::ArrowDown,
+    Named::ArrowLeft,
+    Named::ArrowRight,
+    Named::ArrowUp,
+    Named::Backspace,
+    Named::Delete,
+    Named::End,
+    Named::Enter,
+    Named::Escape,
+    Named::F1,
+    Named::F2,
+    Named::F3,
+    Named::F4,
+    Named::F5,
+    Named::F6,
+    Named::F7,
+    Named::F8,
+    Named::F9,
+    Named::F10,
+    Named::F11,
+    Named::F12,
+    Named::Home,
+    Named::Insert,
+    Named::PageDown,
+    Named::PageUp,
+    Named::Space,
+    Named::Tab,
+    Named::Pause,
+    Named::PrintScreen,
+    Named::ContextMenu,
+    Named::Help,
+];
+
+impl fmt::Display for KeyBind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for modifier in self.modifiers.iter() {
+            write!(f, "{:?}+", modifier)?;
+        }
+        if self.key.chars().count() == 1 {
+            write!(f, "{}", self.key.to_uppercase())
+        } else {
+            write!(f, "{}", self.key)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Action {
+    About,
+    ActualSize,
+    AddNote,
+    /// Toggle a bookmark on the current page
+    Bookmark,
+    /// Open another document in the second pane, with its differences highlighted
+    CompareWith,
+    CopyLink,
+    CoverPage,
+    DocumentNext,
+    DocumentPrev,
+    Export,
+    ExportImage,
+    ExportSvg,
+    Find,
+    FindNext,
+    FindPrev,
+    FitPage,
+    FitWidth,
+    FocusPane,
+    GoToPage,
+    KeyboardShortcuts,
+    Layers,
+    LayoutContinuous,
+    LayoutFacing,
+    LayoutSingle,
+    LockPaneZoom,
+    NewInstance,
+    NightMode,
+    Open,
+    /// Open a recent document by its index in the list
+    OpenRecent(usize),
+    OpenSecondPane,
+    PageFirst,
+    PageLast,
+    PageNext,
+    PagePrev,
+    PanDown,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PickColor,
+    Present,
+    Print,
+    Quit,
+    ReadingScroll,
+    Redo,
+    RememberView,
+    RightToLeft,
+    RotateAllLeft,
+    RotateAllRight,
+    RotateLeft,
+    RotateRight,
+    Save,
+    ScreenDown,
+    ScreenUp,
+    Settings,
+    Share,
+    SidebarAnnotations,
+    SidebarBookmarks,
+    SidebarOutline,
+    SidebarPages,
+    SidebarStructure,
+    Signatures,
+    SplitView,
+    Statistics,
+    SyncPanes,
+    ToggleGrid,
+    ToggleRulers,
+    ToggleSidebar,
+    TrimMargins,
+    Undo,
+    ViewLock,
+    ZoomIn,
+    ZoomOut,
+    /// Zoom to a percentage of actual size
+    ZoomPreset(u16),
+    ZoomReset,
+}
+
+impl Action {
+    pub const ALL: [Self; 72] = [
+        Self::PageFirst,
+        Self::PageLast,
+        Self::PageNext,
+        Self::PagePrev,
+        Self::GoToPage,
+        Self::Bookmark,
+        Self::DocumentNext,
+        Self::DocumentPrev,
+        Self::PanUp,
+        Self::PanDown,
+        Self::PanLeft,
+        Self::PanRight,
+        Self::ScreenDown,
+        Self::ScreenUp,
+        Self::ReadingScroll,
+        Self::ZoomIn,
+        Self::ZoomOut,
+        Self::ZoomReset,
+        Self::ActualSize,
+        Self::FitPage,
+        Self::FitWidth,
+        Self::LayoutSingle,
+        Self::LayoutContinuous,
+        Self::LayoutFacing,
+        Self::RightToLeft,
+        Self::CoverPage,
+        Self::RotateLeft,
+        Self::RotateRight,
+        Self::RotateAllLeft,
+        Self::RotateAllRight,
+        Self::RememberView,
+        Self::ViewLock,
+        Self::TrimMargins,
+        Self::ToggleRulers,
+        Self::ToggleGrid,
+        Self::NightMode,
+        Self::ToggleSidebar,
+        Self::SidebarPages,
+        Self::SidebarOutline,
+        Self::SidebarStructure,
+        Self::SidebarBookmarks,
+        Self::SidebarAnnotations,
+        Self::Layers,
+        Self::SplitView,
+        Self::SyncPanes,
+        Self::LockPaneZoom,
+        Self::OpenSecondPane,
+        Self::CompareWith,
+        Self::FocusPane,
+        Self::PickColor,
+        Self::Present,
+        Self::Find,
+        Self::FindNext,
+        Self::FindPrev,
+        Self::Undo,
+        Self::Redo,
+        Self::CopyLink,
+        Self::AddNote,
+        Self::Open,
+        Self::Save,
+        Self::Export,
+        Self::ExportImage,
+        Self::ExportSvg,
+        Self::Print,
+        Self::Share,
+        Self::NewInstance,
+        Self::Statistics,
+        Self::Signatures,
+        Self::Settings,
+        Self::KeyboardShortcuts,
+        Self::About,
+        Self::Quit,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::About => fl!("about"),
+            Self::ActualSize => fl!("actual-size"),
+            Self::AddNote => fl!("add-note"),
+            Self::Bookmark => fl!("bookmark-page"),
+            Self::CompareWith => fl!("compare-with"),
+            Self::CopyLink => fl!("copy-link"),
+            Self::CoverPage => fl!("cover-page"),
+            Self::DocumentNext => fl!("next-document"),
+            Self::DocumentPrev => fl!("previous-document"),
+            Self::Export => fl!("export"),
+            Self::ExportImage => fl!("export-image"),
+            Self::ExportSvg => fl!("export-svg"),
+            Self::Find => fl!("find"),
+            Self::FindNext => fl!("find-next"),
+            Self::FindPrev => fl!("find-previous"),
+            Self::FitPage => fl!("fit-page"),
+            Self::FitWidth => fl!("fit-width"),
+            Self::FocusPane => fl!("focus-pane"),
+            Self::GoToPage => fl!("go-to-page-menu"),
+            Self::KeyboardShortcuts => fl!("keyboard-shortcuts"),
+            Self::Layers => fl!("layers"),
+            Self::LayoutContinuous => fl!("continuous"),
+            Self::LayoutFacing => fl!("facing-pages"),
+            Self::LayoutSingle => fl!("single-page"),
+            Self::LockPaneZoom => fl!("lock-pane-zoom"),
+            Self::NewInstance => fl!("new-instance"),
+            Self::NightMode => fl!("night-mode"),
+            Self::Open => fl!("open"),
+            Self::OpenRecent(_) => fl!("open-recent"),
+            Self::OpenSecondPane => fl!("open-second-pane"),
+            Self::PageFirst => fl!("first-page"),
+            Self::PageLast => fl!("last-page"),
+            Self::PageNext => fl!("next-page"),
+            Self::PagePrev => fl!("previous-page"),
+            Self::PanDown => fl!("scroll-down"),
+            Self::PanLeft => fl!("scroll-left"),
+            Self::PanRight => fl!("scroll-right"),
+            Self::PanUp => fl!("scroll-up"),
+            Self::PickColor => fl!("pick-color"),
+            Self::Present => fl!("present"),
+            Self::Print => fl!("print"),
+            Self::Quit => fl!("quit"),
+            Self::ReadingScroll => fl!("reading-scroll"),
+            Self::Redo => fl!("redo"),
+            Self::RememberView => fl!("remember-view"),
+            Self::RightToLeft => fl!("right-to-left-pages"),
+            Self::RotateAllLeft => fl!("rotate-all-left"),
+            Self::RotateAllRight => fl!("rotate-all-right"),
+            Self::RotateLeft => fl!("rotate-left"),
+            Self::RotateRight => fl!("rotate-right"),
+            Self::Save => fl!("save"),
+            Self::ScreenDown => fl!("screen-down"),
+            Self::ScreenUp => fl!("screen-up"),
+            Self::Settings => fl!("settings"),
+            Self::Share => fl!("share"),
+            Self::SidebarAnnotations => fl!("show-annotations"),
+            Self::SidebarBookmarks => fl!("show-bookmarks"),
+            Self::SidebarOutline => fl!("show-outline"),
+            Self::SidebarPages => fl!("show-pages"),
+            Self::SidebarStructure => fl!("show-structure"),
+            Self::Signatures => fl!("signatures"),
+            Self::SplitView => fl!("split-view"),
+            Self::Statistics => fl!("statistics"),
+            Self::SyncPanes => fl!("sync-panes"),
+            Self::ToggleGrid => fl!("toggle-grid"),
+            Self::ToggleRulers => fl!("toggle-rulers"),
+            Self::ToggleSidebar => fl!("toggle-sidebar"),
+            Self::TrimMargins => fl!("trim-margins"),
+            Self::Undo => fl!("undo"),
+            Self::ViewLock => fl!("lock-view"),
+            Self::ZoomIn => fl!("zoom-in"),
+            Self::ZoomOut => fl!("zoom-out"),
+            Self::ZoomPreset(percent) => fl!("zoom-percent", percent = percent),
+            Self::ZoomReset => fl!("reset-zoom"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum MouseBind {
+    DoubleClick,
+    MiddleClick,
+    Back,
+    Forward,
+}
+
+impl MouseBind {
+    pub const ALL: [Self; 4] = [
+        Self::DoubleClick,
+        Self::MiddleClick,
+        Self::Back,
+        Self::Forward,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::DoubleClick => fl!("double-click"),
+            Self::MiddleClick => fl!("middle-click"),
+            Self::Back => fl!("back-button"),
+            Self::Forward => fl!("forward-button"),
+        }
+    }
+}
+
+pub fn key_binds() -> BTreeMap<Action, KeyBind> {
+    use Modifier::*;
+
+    let mut key_binds = BTreeMap::new();
+    macro_rules! bind {
+        ([$($modifier:ident),*], $key:expr, $action:ident) => {{
+            key_binds.insert(Action::$action, KeyBind::new(&[$($modifier),*], $key));
+        }};
+    }
+
+    bind!([Ctrl], "0", ActualSize);
+    bind!([Ctrl], "d", Bookmark);
+    bind!([Ctrl], "Home", PageFirst);
+    bind!([Ctrl], "End", PageLast);
+    bind!([Ctrl], "f", Find);
+    bind!([Ctrl], "g", GoToPage);
+    bind!([Ctrl], "i", NightMode);
+    bind!([Ctrl, Shift], "?", KeyboardShortcuts);
+    bind!([Ctrl], "n", NewInstance);
+    bind!([Ctrl], "o", Open);
+    bind!([], "F3", FindNext);
+    bind!([Shift], "F3", FindPrev);
+    bind!([Ctrl], "PageDown", PageNext);
+    bind!([Ctrl], "PageUp", PagePrev);
+    bind!([Alt], "PageDown", DocumentNext);
+    bind!([Alt], "PageUp", DocumentPrev);
+    bind!([], "ArrowDown", PanDown);
+    bind!([], "ArrowLeft", PanLeft);
+    bind!([], "ArrowRight", PanRight);
+    bind!([], "ArrowUp", PanUp);
+    bind!([], "F5", Present);
+    bind!([Ctrl], "p", Print);
+    bind!([], "F6", FocusPane);
+    bind!([Ctrl], "ArrowLeft", RotateLeft);
+    bind!([Ctrl], "ArrowRight", RotateRight);
+    bind!([Ctrl, Shift], "ArrowLeft", RotateAllLeft);
+    bind!([Ctrl, Shift], "ArrowRight", RotateAllRight);
+    bind!([], "Space", ScreenDown);
+    bind!([Shift], "Space", ScreenUp);
+    bind!([Ctrl], "q", Quit);
+    bind!([Ctrl], "s", Save);
+    bind!([Ctrl], "z", Undo);
+    bind!([Ctrl, Shift], "z", Redo);
+    bind!([], "F8", ReadingScroll);
+    bind!([Ctrl], ",", Settings);
+    bind!([], "F9", ToggleSidebar);
+    bind!([], "PageUp", ZoomIn);
+    bind!([], "PageDown", ZoomOut);
+    bind!([], "Home", ZoomReset);
+
+    key_binds
+}
+
+pub fn mouse_binds() -> BTreeMap<MouseBind, Action> {
+    let mut mouse_binds = BTreeMap::new();
+    mouse_binds.insert(MouseBind::Back, Action::PagePrev);
+    mouse_binds.insert(MouseBind::Forward, Action::PageNext);
+    mouse_binds
+}
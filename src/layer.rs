@@ -0,0 +1,155 @@
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use std::collections::HashSet;
+
+/// Optional content group, a layer of the document that can be shown or hidden
+#[derive(Clone, Debug)]
+pub struct Layer {
+    pub id: ObjectId,
+    pub name: String,
+    pub visible: bool,
+}
+
+/// Layers of the document in the order of its default configuration, visible as that
+/// configuration sets them
+pub fn layers(doc: &Document) -> Vec<Layer> {
+    let Some(properties) = properties(doc) else {
+        return Vec::new();
+    };
+    let config = properties
+        .get(b"D")
+        .and_then(|x| resolve(doc, x).as_dict())
+        .ok();
+    let ids = |key: &[u8]| -> HashSet<ObjectId> {
+        config
+            .and_then(|config| config.get(key).ok())
+            .and_then(|x| resolve(doc, x).as_array().ok())
+            .map(|array| array.iter().filter_map(|x| x.as_reference().ok()).collect())
+            .unwrap_or_default()
+    };
+    // Groups start on unless the configuration turns everything off by default
+    let base_off = config
+        .and_then(|config| config.get(b"BaseState").ok())
+        .and_then(|x| x.as_name().ok())
+        == Some(&b"OFF"[..]);
+    let (on, off) = (ids(b"ON"), ids(b"OFF"));
+    let Ok(groups) = properties
+        .get(b"OCGs")
+        .and_then(|x| resolve(doc, x).as_array())
+    else {
+        return Vec::new();
+    };
+    groups
+        .iter()
+        .filter_map(|group| {
+            let id = group.as_reference().ok()?;
+            let dict = doc.get_dictionary(id).ok()?;
+            let name = dict
+                .get(b"Name")
+                .ok()
+                .and_then(|x| x.as_str().ok())
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .unwrap_or_default();
+            let visible = if base_off {
+                on.contains(&id)
+            } else {
+                !off.contains(&id)
+            };
+            Some(Layer { id, name, visible })
+        })
+        .collect()
+}
+
+/// Groups of the layers that are hidden
+pub fn hidden(layers: &[Layer]) -> HashSet<ObjectId> {
+    layers
+        .iter()
+        .filter(|layer| !layer.visible)
+        .map(|layer| layer.id)
+        .collect()
+}
+
+/// Groups hidden by the default configuration of a document
+pub fn default_hidden(doc: &Document) -> HashSet<ObjectId> {
+    hidden(&layers(doc))
+}
+
+/// Whether contents marked with an optional content group or membership dictionary are hidden
+//TODO: visibility expressions (VE) of membership dictionaries are not evaluated
+pub fn is_hidden(doc: &Document, object: &Object, hidden: &HashSet<ObjectId>) -> bool {
+    if hidden.is_empty() {
+        return false;
+    }
+    let id = object.as_reference().ok();
+    if id.is_some_and(|id| hidden.contains(&id)) {
+        return true;
+    }
+    let Ok(dict) = resolve(doc, object).as_dict() else {
+        return false;
+    };
+    if dict.get(b"Type").and_then(Object::as_name).ok() != Some(b"OCMD") {
+        return false;
+    }
+    let groups: Vec<ObjectId> = match dict.get(b"OCGs").map(|x| resolve(doc, x)) {
+        Ok(Object::Array(array)) => array.iter().filter_map(|x| x.as_reference().ok()).collect(),
+        Ok(Object::Reference(id)) => vec![*id],
+        _ => return false,
+    };
+    if groups.is_empty() {
+        return false;
+    }
+    let policy = dict
+        .get(b"P")
+        .and_then(Object::as_name)
+        .unwrap_or(&b"AnyOn"[..]);
+    let mut on = groups.iter().map(|id| !hidden.contains(id));
+    match policy {
+        b"AllOn" => !on.all(|x| x),
+        b"AnyOff" => !on.any(|x| !x),
+        b"AllOff" => !on.all(|x| !x),
+        _ => !on.any(|x| x),
+    }
+}
+
+/// Optional content of the marked content properties named in a page's resources
+pub fn marked_content<'a>(doc: &'a Document, page_id: ObjectId, name: &[u8]) -> Option<&'a Object> {
+    let page = doc.get_dictionary(page_id).ok()?;
+    let resources = doc.get_dict_in_dict(page, b"Resources").ok()?;
+    let properties = doc.get_dict_in_dict(resources, b"Properties").ok()?;
+    properties.get(name).ok()
+}
+
+/// Whether an XObject named in a page's resources is in a hidden layer
+pub fn xobject_hidden(
+    doc: &Document,
+    page_id: ObjectId,
+    name: &str,
+    hidden: &HashSet<ObjectId>,
+) -> bool {
+    if hidden.is_empty() {
+        return false;
+    }
+    let xobject = || -> Result<&Dictionary, lopdf::Error> {
+        let page = doc.get_dictionary(page_id)?;
+        let resources = doc.get_dict_in_dict(page, b"Resources")?;
+        let xobjects = doc.get_dict_in_dict(resources, b"XObject")?;
+        let id = xobjects.get(name.as_bytes())?.as_reference()?;
+        Ok(&doc.get_object(id)?.as_stream()?.dict)
+    };
+    xobject()
+        .and_then(|dict| dict.get(b"OC"))
+        .is_ok_and(|oc| is_hidden(doc, oc, hidden))
+}
+
+fn properties(doc: &Document) -> Option<&Dictionary> {
+    let catalog = doc.catalog().ok()?;
+    resolve(doc, catalog.get(b"OCProperties").ok()?)
+        .as_dict()
+        .ok()
+}
+
+fn resolve<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+    match object {
+        Object::Reference(id) => doc.get_object(*id).unwrap_or(object),
+        _ => object,
+    }
+}
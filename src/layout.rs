@@ -0,0 +1,144 @@
+use cosmic::iced::{Size, Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::fl;
+
+/// How pages are arranged
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Layout {
+    #[default]
+    Single,
+    Continuous,
+    Facing,
+}
+
+impl Layout {
+    pub const ALL: [Self; 3] = [Self::Single, Self::Continuous, Self::Facing];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Single => fl!("single-page"),
+            Self::Continuous => fl!("continuous"),
+            Self::Facing => fl!("facing-pages"),
+        }
+    }
+
+    /// First page shown along with the given page, facing pages with a cover show the first page
+    /// alone and pair the rest from the second page
+    pub fn first(&self, position: u16, cover: bool) -> u16 {
+        match self {
+            Self::Facing if cover => match position {
+                0 => 0,
+                _ => position - (position - 1) % 2,
+            },
+            Self::Facing => position - position % 2,
+            _ => position,
+        }
+    }
+
+    /// First page shown by next page
+    pub fn next(&self, position: u16, cover: bool) -> u16 {
+        let first = self.first(position, cover);
+        match self {
+            Self::Facing if cover && first == 0 => 1,
+            Self::Facing => first + 2,
+            _ => first + 1,
+        }
+    }
+
+    /// First page shown by previous page
+    pub fn prev(&self, position: u16, cover: bool) -> Option<u16> {
+        let before = self.first(position, cover).checked_sub(1)?;
+        Some(self.first(before, cover))
+    }
+
+    /// Pages shown with the center of each, in PDF units with y up, relative to the layout origin,
+    /// with a gap in PDF units between pages
+    pub fn place(
+        &self,
+        page_sizes: &[Size],
+        position: u16,
+        gap: f32,
+        cover: bool,
+    ) -> Vec<(u16, Vector)> {
+        match self {
+            Self::Single => vec![(position, Vector::new(0.0, 0.0))],
+            Self::Continuous => {
+                let mut top = 0.0;
+                let mut placed = Vec::with_capacity(page_sizes.len());
+                for (i, size) in page_sizes.iter().enumerate() {
+                    placed.push((i as u16, Vector::new(0.0, -(top + size.height / 2.0))));
+                    top += size.height + gap;
+                }
+                placed
+            }
+            Self::Facing => {
+                let first = self.first(position, cover);
+                let Some(left) = page_sizes.get(usize::from(first)) else {
+                    return Vec::new();
+                };
+                match page_sizes
+                    .get(usize::from(first) + 1)
+                    .filter(|_| !(cover && first == 0))
+                {
+                    Some(right) => vec![
+                        (first, Vector::new(-(left.width + gap) / 2.0, 0.0)),
+                        (first + 1, Vector::new((right.width + gap) / 2.0, 0.0)),
+                    ],
+                    None => vec![(first, Vector::new(0.0, 0.0))],
+                }
+            }
+        }
+    }
+
+    /// Size fit to the window by the fit modes
+    pub fn size(&self, page_sizes: &[Size], position: u16, gap: f32, cover: bool) -> Option<Size> {
+        match self {
+            Self::Facing => {
+                let first = usize::from(self.first(position, cover));
+                let left = *page_sizes.get(first)?;
+                Some(
+                    match page_sizes.get(first + 1).filter(|_| !(cover && first == 0)) {
+                        Some(right) => Size::new(
+                            left.width + gap + right.width,
+                            left.height.max(right.height),
+                        ),
+                        None => left,
+                    },
+                )
+            }
+            _ => page_sizes.get(usize::from(position)).copied(),
+        }
+    }
+
+    /// Pan that centers the given page
+    pub fn scroll(&self, page_sizes: &[Size], position: u16, gap: f32) -> Option<Vector> {
+        match self {
+            Self::Continuous => {
+                let (_, center) = self
+                    .place(page_sizes, position, gap, false)
+                    .into_iter()
+                    .nth(position.into())?;
+                Some(Vector::new(0.0, -center.y))
+            }
+            _ => None,
+        }
+    }
+
+    /// Page at the center of the view for continuous layout, given the vertical pan
+    pub fn position_at(&self, page_sizes: &[Size], y: f32, gap: f32) -> Option<u16> {
+        match self {
+            Self::Continuous => {
+                let mut top = 0.0;
+                for (i, size) in page_sizes.iter().enumerate() {
+                    top += size.height + gap;
+                    if y < top {
+                        return Some(i as u16);
+                    }
+                }
+                page_sizes.len().checked_sub(1).map(|i| i as u16)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use std::process::Command;
+
+/// Schemes of links opened from documents, others such as file: could run programs
+const SCHEMES: [&str; 4] = ["http", "https", "mailto", "ftp"];
+
+/// Open a web or email link in the default application for it
+pub fn open(uri: &str) -> Result<(), String> {
+    let scheme = uri.split_once(':').map_or("", |(scheme, _)| scheme);
+    if !SCHEMES.iter().any(|x| x.eq_ignore_ascii_case(scheme)) {
+        return Err(format!("unsupported link {uri:?}"));
+    }
+    Command::new("xdg-open")
+        .arg(uri)
+        .spawn()
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
@@ -1,29 +1,195 @@
+use config::{
+    format_color, parse_color, ColorSetting, Config, Direction, DocumentView, LastRead,
+    PageAnimation, PageEdge, Reflow, Resume, Sidebar, State, Wheel, CONFIG_VERSION,
+};
 use cosmic::{
     app::{Core, Settings, Task},
+    cosmic_config::{self, CosmicConfigEntry},
+    dialog::file_chooser,
     executor,
     iced::{
+        alignment::{Horizontal, Vertical},
+        clipboard,
         keyboard::{self, key::Named, Key},
         mouse,
         mouse::Cursor,
+        time, touch,
         widget::canvas::{self, event::Status},
-        Color, Length, Point, Rectangle, Size, Vector,
+        window, Color, Length, Pixels, Point, Rectangle, Size, Subscription, Vector,
     },
+    iced_core::mouse::{click, Click},
     iced_renderer,
-    widget::{self, image, nav_bar::Model},
+    widget::{self, image, menu::action::MenuAction, nav_bar::Model},
     Application, Element, Renderer, Theme,
 };
+use export::StampPosition;
+use gesture::{Autoscroll, Ease, Fling, Swipe, Touch, TwoFinger};
+use history::{Edit, EditKind, ExportEdit, History};
+use key_bind::{Action, KeyBind, MouseBind};
+use layout::Layout;
 use lopdf::{Document, ObjectId};
-use std::{collections::HashMap, env, sync::Mutex};
+use presentation::{Ink, OverviewLayout, Pointer, Presentation, INK_COLORS, OVERVIEW_PADDING};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    env,
+    ffi::OsString,
+    fs,
+    os::unix::{ffi::OsStringExt, net::UnixListener},
+    path::PathBuf,
+    process, str,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use structure::StructKind;
 
+mod comic;
+mod config;
+mod conformance;
+mod epub;
+mod export;
+mod folder;
+mod format;
+mod gesture;
+mod history;
+mod instance;
+mod key_bind;
+mod layer;
+mod layout;
+mod link;
+mod localize;
+mod media;
+mod menu;
+mod mime;
+mod night;
+mod note;
 mod pdf;
+mod picture;
+mod portal;
+mod power;
+mod presentation;
+mod print;
+mod raster;
+mod runtime;
+mod search;
+mod share;
+mod signature;
+mod structure;
+mod svg;
 mod text;
 mod ttf;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    localize::localize();
+
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
 
-    let path = env::args().nth(1).unwrap();
-    let doc = Document::load(path).unwrap();
+    let mut path_opt = None;
+    let mut compare_path = None;
+    let mut kiosk = false;
+    let mut new_instance = false;
+    let mut interval = None;
+    let mut duration = None;
+    let mut transitions = true;
+    let mut page = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--kiosk" => kiosk = true,
+            "--new-instance" => new_instance = true,
+            "--interval" => match args.next().map(|x| x.parse::<f32>()) {
+                Some(Ok(secs)) if secs > 0.0 => match Duration::try_from_secs_f32(secs) {
+                    Ok(secs) => interval = Some(secs),
+                    Err(err) => log::warn!("invalid --interval {}: {}", secs, err),
+                },
+                other => log::warn!("invalid --interval {:?}", other),
+            },
+            "--duration" => match args.next().map(|x| x.parse::<f32>()) {
+                Some(Ok(mins)) if mins > 0.0 => match Duration::try_from_secs_f32(mins * 60.0) {
+                    Ok(secs) => duration = Some(secs),
+                    Err(err) => log::warn!("invalid --duration {}: {}", mins, err),
+                },
+                other => log::warn!("invalid --duration {:?}", other),
+            },
+            "--no-transitions" => transitions = false,
+            "--page" => match args.next().map(|x| x.parse::<u16>()) {
+                Some(Ok(number)) if number > 0 => page = Some(number),
+                other => log::warn!("invalid --page {:?}", other),
+            },
+            "--compare" => match args.next() {
+                Some(arg) => compare_path = Some(document_arg(arg).0),
+                None => log::warn!("missing --compare FILE"),
+            },
+            _ => {
+                let (path, link_page) = document_arg(arg);
+                path_opt = Some(path);
+                page = page.or(link_page);
+            }
+        }
+    }
+    let path = path_opt
+        .expect("usage: cosmic-reader [--kiosk] [--new-instance] [--interval SECONDS] [--duration MINUTES] [--no-transitions] [--compare FILE] [--page NUMBER] FILE");
+    // Documents are remembered by canonical path, and documents passed in through the document
+    // portal by their path outside the sandbox, as the portal may give them another path later
+    let host_path = portal::host_path(&path);
+    let path = fs::canonicalize(&path).unwrap_or(path);
+    let key = host_path.unwrap_or_else(|| path.clone());
+    // An instance already showing the document goes to the page instead, unless another instance
+    // was asked for, which leaves the first window listening
+    if !kiosk && !new_instance && instance::forward(&key, page) {
+        return Ok(());
+    }
+    let (config_handler, config) = match cosmic_config::Config::new(App::APP_ID, CONFIG_VERSION) {
+        Ok(config_handler) => {
+            let config = match Config::get_entry(&config_handler) {
+                Ok(ok) => ok,
+                Err((errs, config)) => {
+                    log::info!("errors loading config: {:?}", errs);
+                    config
+                }
+            };
+            (Some(config_handler), config)
+        }
+        Err(err) => {
+            log::error!("failed to create config handler: {}", err);
+            (None, Config::default())
+        }
+    };
+
+    let doc = format::load(&path, config.reflow)?;
+    // Only instances showing the document listen, kiosks and extra instances leave the socket to
+    // the first, and it is removed once the application exits
+    let listener = if kiosk || new_instance {
+        None
+    } else {
+        instance::listen(&key)
+    };
+    let compare = compare_path.and_then(|path| match format::load(&path, config.reflow) {
+        Ok(doc) => Some(Arc::new(doc)),
+        Err(err) => {
+            log::error!("failed to load {:?} to compare: {}", path, err);
+            None
+        }
+    });
+
+    let (state_handler, state) = match cosmic_config::Config::new_state(App::APP_ID, CONFIG_VERSION)
+    {
+        Ok(state_handler) => {
+            let state = match State::get_entry(&state_handler) {
+                Ok(ok) => ok,
+                Err((errs, state)) => {
+                    log::info!("errors loading state: {:?}", errs);
+                    state
+                }
+            };
+            (Some(state_handler), state)
+        }
+        Err(err) => {
+            log::error!("failed to create state handler: {}", err);
+            (None, State::default())
+        }
+    };
 
     /*
     println!("{:#?}", doc.get_toc());
@@ -42,17 +208,517 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     */
 
-    cosmic::app::run::<App>(Settings::default(), Flags { doc })?;
+    cosmic::app::run::<App>(
+        Settings::default().antialiasing(config.antialiasing),
+        Flags {
+            config_handler,
+            config,
+            state_handler,
+            state,
+            path,
+            key,
+            doc: Arc::new(doc),
+            compare,
+            kiosk,
+            interval,
+            duration,
+            transitions,
+            page,
+            listener: listener
+                .as_ref()
+                .and_then(instance::Listener::try_clone)
+                .map(Arc::new),
+        },
+    )?;
+    drop(listener);
     Ok(())
 }
 
+/// Percent-encode bytes for a URL, keeping unreserved characters and those given
+fn percent_encode(bytes: &[u8], keep: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) || keep.contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Decode a percent-encoded URL component
+fn percent_decode(encoded: &str) -> Vec<u8> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// Path of a document argument, which may be a file URI like those desktop entries and the
+/// document portal give sandboxed applications, and the page number its #page= fragment goes to
+fn document_arg(arg: String) -> (PathBuf, Option<u16>) {
+    let Some(uri) = arg.strip_prefix("file://") else {
+        return (PathBuf::from(arg), None);
+    };
+    let (uri, fragment) = uri.split_once('#').unwrap_or((uri, ""));
+    let uri = uri.split('?').next().unwrap_or(uri);
+    // The host is empty or localhost for local files
+    let path = uri.find('/').map_or("", |i| &uri[i..]);
+    let page = fragment
+        .split('&')
+        .find_map(|param| param.strip_prefix("page="))
+        .and_then(|page| page.parse().ok())
+        .filter(|page| *page > 0);
+    (
+        PathBuf::from(OsString::from_vec(percent_decode(path))),
+        page,
+    )
+}
+
 struct Flags {
-    doc: Document,
+    config_handler: Option<cosmic_config::Config>,
+    config: Config,
+    state_handler: Option<cosmic_config::Config>,
+    state: State,
+    path: PathBuf,
+    /// Path the document is remembered by, outside the sandbox for documents in the document portal
+    key: PathBuf,
+    doc: Arc<Document>,
+    /// Document shown next to this one with differences highlighted
+    compare: Option<Arc<Document>>,
+    kiosk: bool,
+    /// Seconds per page when auto-advancing presentations
+    interval: Option<Duration>,
+    duration: Option<Duration>,
+    transitions: bool,
+    /// Page number to start at
+    page: Option<u16>,
+    /// Socket other instances forward to when opening this document
+    listener: Option<Arc<UnixListener>>,
 }
 
 #[derive(Clone, Debug)]
-enum Message {
+pub enum Message {
+    Antialiasing(bool),
+    ArrowPages(bool),
+    AuthorInput(String),
+    Autoscroll(Option<Vector>),
+    AutoscrollTick(Instant),
+    /// Toggle a bookmark on the current page
+    Bookmark,
     CanvasClearCache,
+    ColorInput(ColorSetting, String),
+    /// Pages of the document in the second pane, by the generation of that document
+    CompareOps(usize, ObjectId, Arc<Vec<pdf::PageOp>>),
+    /// Document chosen for the second pane, none if choosing was cancelled
+    CompareOpened(Result<Option<Arc<Document>>, String>),
+    Conformance(conformance::Conformance),
+    CopyLink,
+    /// Toggle showing the first page alone in facing pages for this document
+    CoverPage,
+    DarkenText(bool),
+    /// Whether PDF documents open with this application by default, if known
+    DefaultHandler(Option<bool>),
+    DefaultHandlerDismiss,
+    DefaultHandlerSet,
+    DefaultLayout(Layout),
+    /// Crop margin input by index, top, right, bottom, and left
+    CropMargin(usize, String),
+    CropCurrentPage(bool),
+    DefaultZoom(usize),
+    DocumentNext,
+    DocumentPrev,
+    Export,
+    /// The exported copy, none if choosing where was cancelled
+    Exported(Result<Option<PathBuf>, String>),
+    /// Export the current page, or a range of pages, as images
+    ExportImage,
+    /// The first image exported, none if choosing where was cancelled
+    ExportedImage(Result<Option<PathBuf>, String>),
+    /// Export the current page as SVG
+    ExportSvg,
+    /// The exported SVG, none if choosing where was cancelled
+    ExportedSvg(Result<Option<PathBuf>, String>),
+    Fit(pdf::Fit),
+    Eyedropper(bool),
+    /// Show or hide the search field
+    Find,
+    Fling(Option<Vector>),
+    FollowLink(pdf::LinkTarget),
+    FocusPane,
+    FlingTick(Instant),
+    GridSpacing(u16),
+    ImageDpiInput(String),
+    ImageFormat(raster::Format),
+    ImagePagesInput(String),
+    KeyBindCapture(Option<Action>),
+    KeyBindSet(Action, KeyBind),
+    KeyBindsReset,
+    /// Show or hide a layer by its index
+    Layer(usize, bool),
+    Layout(Layout),
+    /// Toggle whether the panes of the split view zoom together
+    LockPaneZoom,
+    MediaPlayed(Result<(), String>),
+    MouseBindSet(MouseBind, Option<Action>),
+    NewInstance,
+    NightMode,
+    /// Add a note to a page at a position in PDF units
+    NoteAdd(ObjectId, Point),
+    NoteDelete,
+    /// Open a note by page and index, or close the open note
+    NoteEdit(Option<(ObjectId, usize)>),
+    NoteInput(String),
+    /// Place a note with the next click
+    NoteTool(bool),
+    /// Choose a document to open
+    Open,
+    /// Document chosen to open, none if choosing was cancelled
+    OpenChosen(Result<Option<PathBuf>, String>),
+    OpenRecent(usize),
+    OpenSecondPane,
+    PageGoto(u16),
+    PageInput(String),
+    /// The page field in the header gained or lost focus
+    PageInputFocus(bool),
+    PageInputSubmit,
+    PageLast,
+    PageNext,
+    PageNumber(Option<String>),
+    PageAnimation(PageAnimation),
+    PageEdge(PageEdge),
+    PageGap(u16),
+    PageOrder(Direction),
+    /// Operations of a page, with the layers that were hidden when it was loaded
+    PageOps(ObjectId, Arc<HashSet<ObjectId>>, Arc<Vec<pdf::PageOp>>),
+    PagePrev,
+    PaperColorInput(String),
+    PaperTint(bool),
+    Pan(Vector),
+    /// Pick the color at a position in the window
+    PickColor(Point),
+    PickedColor(Option<Color>),
+    /// Another instance opened this document, with a page number to go to
+    Forwarded(Option<u16>),
+    /// Play media of an annotation by page and index
+    PlayMedia(ObjectId, usize),
+    /// Check whether the computer is on battery
+    PowerCheck,
+    Present(bool),
+    PresentationBlank(Option<Color>),
+    PresentationInk(Ink),
+    PresentationOverview(Option<u16>),
+    PresentationPause,
+    PresentationPointer(Pointer),
+    PresentationTick,
+    PresentationTimer,
+    Print,
+    /// Whether to print with CUPS directly when there is no print portal
+    PrintFallback(bool),
+    /// The CUPS request of the print job, if printed with CUPS
+    Printed(Result<Option<String>, print::Error>),
+    Quit,
+    /// Answer to quitting with unsaved notes, none to keep reading or whether to save them first
+    QuitUnsaved(Option<bool>),
+    ReadingScroll,
+    ReadingScrollTick(Instant),
+    ReadingSpeed(u16),
+    /// Make the last undone edit of the document again
+    Redo,
+    ReduceMotion(bool),
+    /// Layout of books, which are laid out again with it
+    Reflow(Reflow),
+    RememberView,
+    Resume(Resume),
+    /// Go back to where the document was left, or start from here if false
+    ResumeReading(bool),
+    /// Toggle right to left page order for this document
+    RightToLeft,
+    /// Rotate the current page clockwise, in degrees
+    Rotate(i64),
+    /// Rotate every page clockwise, in degrees
+    RotateAll(i64),
+    /// Write changed notes into the document
+    Save,
+    /// The saved document, with the notes saved by page
+    Saved(
+        Result<Arc<Document>, String>,
+        Arc<HashMap<ObjectId, Vec<note::Note>>>,
+    ),
+    SavePower(bool),
+    ScaleFactor(f32),
+    ScreenDpiInput(String),
+    ScrollScreen(f32),
+    /// Scroll by a step and the height of the screen, both in PDF units, changing pages at the
+    /// edge of the current one
+    ScrollStep(f32, f32),
+    SearchIndex(Arc<search::Index>),
+    SearchInput(String),
+    /// Select the next match of the search term, going to the next page with it after the last
+    SearchNext,
+    SearchPrev,
+    SharpPixels(bool),
+    StampImage(String),
+    StampOpacity(u8),
+    StampPosition(StampPosition),
+    StampRotation(i16),
+    StampText(String),
+    Statistics(pdf::Statistics),
+    Share,
+    Shared(Result<(), String>),
+    Sidebar(Sidebar),
+    /// Width the sidebar is being resized to, in logical pixels
+    SidebarWidth(f32),
+    /// Select an entry of the sidebar by position
+    SidebarSelect(usize),
+    /// Toggle showing pages of this document side by side
+    SplitView,
+    /// Save the view and reading position once they stop changing
+    StateTimeout,
+    StatusTimeout,
+    /// Toggle whether the compared pages follow page changes
+    SyncPanes,
+    Tick,
+    ViewAnimationTick,
+    /// Toggle keeping the zoom and horizontal pan when changing pages
+    ViewLock,
+    SwipeDrag(f32),
+    SwipeEnd(f32, f32),
+    SwipeTick,
+    ToggleContextPage(ContextPage),
+    ToggleGrid,
+    ToggleRulers,
+    ToggleSidebar,
+    TrimMargins,
+    /// Undo the last edit of the notes, crop, or stamp
+    Undo,
+    /// Load a page ahead of time if there has been no input for a while
+    WarmCache,
+    Wheel(Wheel),
+    Zoom(f32),
+    /// Zoom by a factor without animating and pan in PDF units, so the point under the cursor
+    /// or fingers stays in place
+    ZoomPan(f32, Vector),
+    ZoomPreset(u16),
+    ZoomPresetsInput(String),
+    ZoomReset,
+    ZoomStep(u16),
+}
+
+const ZOOM_STEPS: [u16; 5] = [5, 10, 20, 25, 50];
+/// Distance in pixels pages slide in from when changing pages
+const PAGE_SLIDE: f32 = 48.0;
+/// Slow scroll speeds in PDF units per second, around a line of text every few seconds
+const READING_SPEEDS: [u16; 6] = [2, 4, 6, 8, 12, 16];
+/// Slow scrolling waits this long after the last input before continuing
+const READING_SCROLL_PAUSE: Duration = Duration::from_secs(3);
+/// Pages are loaded ahead of time after this long without input, until this many are loaded
+const WARM_IDLE: Duration = Duration::from_secs(3);
+const WARM_PAGES_MAX: usize = 64;
+/// How often the power source is checked
+const POWER_POLL: Duration = Duration::from_secs(30);
+/// Time between animation frames while saving power
+const POWER_SAVING_FRAME: Duration = Duration::from_millis(33);
+/// Grid spacings in PDF units, from an eighth of an inch to an inch
+const GRID_SPACINGS: [u16; 4] = [9, 18, 36, 72];
+/// Shade over media annotations, and the largest size of their play buttons in pixels
+const MEDIA_SHADE: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.4);
+const MEDIA_BUTTON_SIZE: f32 = 48.0;
+/// Page gaps offered in settings, in PDF units
+const PAGE_GAPS: [u16; 5] = [0, 4, 8, 16, 32];
+/// Layouts of books offered in settings, in PDF units
+const REFLOW_FONT_SIZES: [u16; 6] = [9, 10, 12, 14, 16, 20];
+const REFLOW_MARGINS: [u16; 4] = [18, 36, 54, 72];
+const REFLOW_WIDTHS: [u16; 5] = [288, 360, 432, 504, 576];
+/// Width of page borders and offset of page shadows, in pixels
+const PAGE_EDGE_WIDTH: f32 = 1.0;
+const PAGE_SHADOW_OFFSET: f32 = 3.0;
+/// Width added around text outlines when darkening text, in pixels
+const TEXT_DARKENING: f32 = 0.3;
+const GRID_COLOR: Color = Color::from_rgba(0.2, 0.5, 0.9, 0.5);
+const RULER_SIZE: f32 = 20.0;
+/// Ruler ticks are a step from this list, in PDF units, so ticks are at least RULER_TICK_MIN apart
+const RULER_STEPS: [f32; 10] = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0];
+const RULER_TICK_MIN: f32 = 6.0;
+const AUTOSCROLL_MARKER_RADIUS: f32 = 12.0;
+/// Fraction of the screen scrolled by Space, keeping some of the previous screen in view
+const SCREEN_SCROLL: f32 = 0.9;
+/// PDF units scrolled by the arrow keys
+const ARROW_SCROLL: f32 = 16.0;
+/// Space kept around the contents of pages when trimming margins, in PDF units
+const TRIM_MARGIN: f32 = 6.0;
+/// Documents kept in the recent documents menu, including this one
+const RECENT_MAX: usize = 10;
+/// Typed page numbers and picked colors are hidden after this long
+const STATUS_TIMEOUT: Duration = Duration::from_secs(3);
+/// Changes of the view and reading position are saved once they stop for this long
+const STATE_SAVE_DELAY: Duration = Duration::from_secs(1);
+const STATUS_TEXT_SIZE: f32 = 32.0;
+/// Width of the edge of the page area next to the sidebar that resizes it, in logical pixels
+const SIDEBAR_EDGE: f32 = 6.0;
+const SIDEBAR_WIDTH_MIN: f32 = 160.0;
+const SIDEBAR_WIDTH_MAX: f32 = 640.0;
+const FOCUS_WIDTH: f32 = 2.0;
+/// Highlight over words that differ between compared pages
+const DIFFERENCE_HIGHLIGHT: Color = Color::from_rgba(0.9, 0.2, 0.2, 0.35);
+/// Highlight over words of the search term
+const SEARCH_HIGHLIGHT: Color = Color::from_rgba(1.0, 0.8, 0.0, 0.4);
+const SEARCH_SELECTED: Color = Color::from_rgba(1.0, 0.5, 0.0, 0.6);
+/// Fill and outline of the icons of notes
+const NOTE_COLOR: Color = Color::from_rgb(1.0, 0.85, 0.3);
+const NOTE_EDGE: Color = Color::from_rgb(0.5, 0.4, 0.1);
+/// Shade over the parts of pages removed by cropping
+const CROP_SHADE: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.5);
+/// Pixels scrolled to change pages when the scroll wheel changes pages
+const WHEEL_PAGE: f32 = 120.0;
+
+/// Part of the window with the keyboard focus, moved with F6
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Pane {
+    /// Pages of the compared document, navigated on their own
+    Compare,
+    Page,
+    Sidebar,
+}
+
+/// How the window leaves the document, once changed notes are saved or discarded
+#[derive(Clone, Debug)]
+enum Leave {
+    Quit,
+    /// Open another document in a new process, closing this window
+    Open(PathBuf),
+    /// Open this document again in a new instance, closing this window
+    Reopen,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContextPage {
+    About,
+    Export,
+    ExportImage,
+    KeyboardShortcuts,
+    Layers,
+    Settings,
+    Signatures,
+    Statistics,
+}
+
+impl ContextPage {
+    fn title(&self) -> String {
+        match self {
+            Self::About => fl!("about"),
+            Self::Export => fl!("export"),
+            Self::ExportImage => fl!("export-image"),
+            Self::KeyboardShortcuts => fl!("keyboard-shortcuts"),
+            Self::Layers => fl!("layers"),
+            Self::Settings => fl!("settings"),
+            Self::Signatures => fl!("signatures"),
+            Self::Statistics => fl!("statistics"),
+        }
+    }
+}
+
+impl MenuAction for Action {
+    type Message = Message;
+
+    fn message(&self) -> Message {
+        match self {
+            Self::About => Message::ToggleContextPage(ContextPage::About),
+            Self::ActualSize => Message::Fit(pdf::Fit::ActualSize),
+            Self::AddNote => Message::NoteTool(true),
+            Self::Bookmark => Message::Bookmark,
+            Self::CopyLink => Message::CopyLink,
+            Self::CoverPage => Message::CoverPage,
+            Self::DocumentNext => Message::DocumentNext,
+            Self::DocumentPrev => Message::DocumentPrev,
+            Self::Export => Message::ToggleContextPage(ContextPage::Export),
+            Self::ExportImage => Message::ToggleContextPage(ContextPage::ExportImage),
+            Self::ExportSvg => Message::ExportSvg,
+            Self::FitPage => Message::Fit(pdf::Fit::Page),
+            Self::FitWidth => Message::Fit(pdf::Fit::Width),
+            Self::Find => Message::Find,
+            Self::FindNext => Message::SearchNext,
+            Self::FindPrev => Message::SearchPrev,
+            Self::FocusPane => Message::FocusPane,
+            // Digits typed afterwards go to the page, as with typing them right away
+            Self::GoToPage => Message::PageNumber(Some(String::new())),
+            Self::KeyboardShortcuts => Message::ToggleContextPage(ContextPage::KeyboardShortcuts),
+            Self::Layers => Message::ToggleContextPage(ContextPage::Layers),
+            Self::LayoutContinuous => Message::Layout(Layout::Continuous),
+            Self::LayoutFacing => Message::Layout(Layout::Facing),
+            Self::LayoutSingle => Message::Layout(Layout::Single),
+            Self::LockPaneZoom => Message::LockPaneZoom,
+            Self::NewInstance => Message::NewInstance,
+            Self::NightMode => Message::NightMode,
+            Self::Open => Message::Open,
+            Self::OpenRecent(index) => Message::OpenRecent(*index),
+            // Documents in the second pane are compared with this one
+            Self::CompareWith | Self::OpenSecondPane => Message::OpenSecondPane,
+            Self::PageFirst => Message::PageGoto(0),
+            Self::PageLast => Message::PageLast,
+            Self::PageNext => Message::PageNext,
+            Self::PagePrev => Message::PagePrev,
+            // Scrolling to other pages needs the size of the canvas, see App::action_message
+            Self::PanDown => Message::Pan(Vector::new(0.0, 16.0)),
+            Self::PanLeft => Message::Pan(Vector::new(16.0, 0.0)),
+            Self::PanRight => Message::Pan(Vector::new(-16.0, 0.0)),
+            Self::PanUp => Message::Pan(Vector::new(0.0, -16.0)),
+            Self::PickColor => Message::Eyedropper(true),
+            // Scrolling by a screen needs the size of the canvas, see App::action_message
+            Self::ScreenDown => Message::PageNext,
+            Self::ScreenUp => Message::PagePrev,
+            Self::Present => Message::Present(true),
+            Self::Print => Message::Print,
+            Self::Quit => Message::Quit,
+            Self::ReadingScroll => Message::ReadingScroll,
+            Self::Redo => Message::Redo,
+            Self::RememberView => Message::RememberView,
+            Self::RightToLeft => Message::RightToLeft,
+            Self::RotateAllLeft => Message::RotateAll(-90),
+            Self::RotateAllRight => Message::RotateAll(90),
+            Self::RotateLeft => Message::Rotate(-90),
+            Self::RotateRight => Message::Rotate(90),
+            Self::Save => Message::Save,
+            Self::Settings => Message::ToggleContextPage(ContextPage::Settings),
+            Self::Share => Message::Share,
+            Self::Signatures => Message::ToggleContextPage(ContextPage::Signatures),
+            Self::Statistics => Message::ToggleContextPage(ContextPage::Statistics),
+            Self::SidebarAnnotations => Message::Sidebar(Sidebar::Annotations),
+            Self::SidebarBookmarks => Message::Sidebar(Sidebar::Bookmarks),
+            Self::SidebarOutline => Message::Sidebar(Sidebar::Outline),
+            Self::SidebarPages => Message::Sidebar(Sidebar::Pages),
+            Self::SidebarStructure => Message::Sidebar(Sidebar::Structure),
+            Self::ToggleGrid => Message::ToggleGrid,
+            Self::ToggleRulers => Message::ToggleRulers,
+            Self::ToggleSidebar => Message::ToggleSidebar,
+            Self::TrimMargins => Message::TrimMargins,
+            Self::Undo => Message::Undo,
+            Self::SplitView => Message::SplitView,
+            Self::SyncPanes => Message::SyncPanes,
+            Self::ViewLock => Message::ViewLock,
+            Self::ZoomIn => Message::Zoom(1.1),
+            Self::ZoomOut => Message::Zoom(1.0 / 1.1),
+            Self::ZoomPreset(percent) => Message::ZoomPreset(*percent),
+            Self::ZoomReset => Message::ZoomReset,
+        }
+    }
 }
 
 struct App {
@@ -60,218 +726,5484 @@ struct App {
     flags: Flags,
     canvas_cache: canvas::Cache,
     nav_model: Model,
-    page_cache: Mutex<HashMap<ObjectId, Vec<pdf::PageOp>>>,
+    outline_model: Model,
+    /// Headings, lists, and tables of a tagged document
+    structure_model: Model,
+    /// Pages bookmarked in this document
+    bookmarks_model: Model,
+    /// Notes on pages, as edited
+    annotations_model: Model,
+    /// Level, title, and first page of each section of the outline, for the breadcrumb
+    sections: Vec<(usize, String, u16)>,
+    page_ids: Vec<ObjectId>,
+    /// Displayed size of each page in PDF units, for layout
+    page_sizes: Vec<Size>,
+    /// Clockwise rotation of pages by the user, in degrees by page position
+    rotations: HashMap<u16, i64>,
+    swipe: Option<Swipe>,
+    fling: Option<Fling>,
+    /// Opacity of the background fading out over new pages
+    fade: Option<Ease>,
+    /// Log of the scale shown relative to the zoom, easing to the new zoom
+    zoom_ease: Option<Ease>,
+    autoscroll: Option<Autoscroll>,
+    /// Slow scrolling for reading, paused after any input to the page
+    reading_scroll: Option<Autoscroll>,
+    /// Last input to the page, for pausing slow scrolling and loading pages while idle
+    last_input: Cell<Option<Instant>>,
+    /// Page being loaded ahead of time while idle
+    warming: Option<ObjectId>,
+    /// Size of the canvas when it was last drawn, to load the pages in view
+    canvas_size: Cell<Size>,
+    /// Pages being loaded, so pages are not loaded again before they arrive
+    pages_loading: RefCell<HashSet<ObjectId>>,
+    on_battery: bool,
+    /// Page number being typed, and when the last digit was typed
+    page_number: Option<(String, Instant)>,
+    /// Page number being edited in the header, while its field is focused
+    page_input: Option<String>,
+    /// Clicking the view picks the color under the cursor
+    eyedropper: bool,
+    /// Notes of loaded pages, as edited
+    notes: HashMap<ObjectId, Vec<note::Note>>,
+    /// Pages with notes changed since the document was saved
+    notes_changed: HashSet<ObjectId>,
+    /// Edits of notes, crop, and stamp that can be undone
+    history: History,
+    /// Asking whether to save changed notes before leaving the document, and how it is left
+    quit_prompt: Option<Leave>,
+    /// Leave the document once the notes are saved
+    quit_after_save: Option<Leave>,
+    note_tool: bool,
+    /// Note open for editing, by page and index
+    note_edit: Option<(ObjectId, usize)>,
+    /// Color picked from the view, and when
+    picked_color: Option<(Color, Instant)>,
+    /// Message shown at the bottom of the view for a while, such as the result of printing
+    status: Option<(String, Instant)>,
+    /// When the view or reading position last changed, while the change is not saved yet
+    state_changed: Option<Instant>,
+    /// Search term, if the search field is shown
+    search: Option<String>,
+    search_id: widget::text_input::Id,
+    /// Index of the document's words, loaded when first searching
+    search_index: Option<Arc<search::Index>>,
+    /// Positions of pages with the search term
+    search_results: Vec<u16>,
+    /// Selected match, by page position and index on the page
+    search_match: Option<(u16, usize)>,
+    page_cache: HashMap<ObjectId, Arc<Vec<pdf::PageOp>>>,
+    /// Media of the annotations of loaded pages
+    page_media: HashMap<ObjectId, Vec<pdf::Media>>,
+    /// Links of loaded pages
+    page_links: HashMap<ObjectId, Vec<pdf::Link>>,
+    /// Area drawn on of loaded pages, for trimming margins
+    page_content: HashMap<ObjectId, Rectangle>,
+    /// Whether a second pane shows pages next to the pages, of the compared document if there is
+    /// one or else of this document
+    split_view: bool,
+    /// Pages of the document in the second pane
+    compare_ids: Vec<ObjectId>,
+    /// Compared pages are shown this many pages after the page next to them,
+    /// changed by navigating while they have the focus
+    compare_offset: i32,
+    /// Whether compared pages follow the page next to them, otherwise they stay where they are
+    compare_sync: bool,
+    /// Active position when the compared pages were last placed
+    compare_position: u16,
+    compare_cache: HashMap<ObjectId, Arc<Vec<pdf::PageOp>>>,
+    /// Compared pages being loaded
+    compare_loading: RefCell<HashSet<ObjectId>>,
+    /// Changed with the document in the second pane, so its pages are not mixed with those of
+    /// the document before
+    compare_generation: usize,
+    /// Whether the panes zoom together, otherwise zooming the focused second pane scales it alone
+    pane_zoom_lock: bool,
+    /// Scale of the second pane relative to the pages when zoomed alone
+    compare_zoom: f32,
+    /// Words that differ between pages and the compared pages next to them, by both page ids,
+    /// worked out as they are drawn
+    compare_differences: RefCell<HashMap<(ObjectId, ObjectId), Arc<[Vec<Rectangle>; 2]>>>,
+    presentation: Option<Presentation>,
+    /// Whether the sidebar was shown when the presentation started, to show it again after
+    presentation_sidebar: bool,
+    view: pdf::PageView,
+    /// Size of the pages when zoom and scroll were locked, pages are scaled as if they had this
+    /// size so all of them are shown at the same zoom
+    view_lock: Option<Size>,
+    context_page: ContextPage,
+    /// Stamp added to exported copies, if it has text or an image
+    stamp: export::Stamp,
+    stamp_image_input: String,
+    stamp_position_names: Vec<String>,
+    /// Crop margins as typed, only valid ones are used
+    crop_inputs: [String; 4],
+    crop_margins: [f32; 4],
+    crop_current_page: bool,
+    /// Result of the last export
+    export_status: Option<String>,
+    image_format: raster::Format,
+    image_format_names: Vec<String>,
+    /// Resolution of exported images as typed, only valid ones are used
+    image_dpi_input: String,
+    image_dpi: u16,
+    /// Pages to export images of as typed, such as "1-3, 5", the active page if empty
+    image_pages_input: String,
+    /// Statistics of the document, calculated when first shown
+    statistics: Option<pdf::Statistics>,
+    conformance: Option<conformance::Conformance>,
+    /// Digital signatures, read when the document is opened
+    signatures: Vec<signature::Signature>,
+    /// Optional content groups, with their visibility chosen in the layers panel
+    layers: Vec<layer::Layer>,
+    /// Groups of the hidden layers, left out when pages are loaded
+    hidden_layers: Arc<HashSet<ObjectId>>,
+    /// Choices for mouse button actions
+    action_names: Vec<String>,
+    /// Fit modes followed by zoom presets
+    default_zoom_names: Vec<String>,
+    layout_names: Vec<String>,
+    wheel_names: Vec<String>,
+    direction_names: Vec<String>,
+    page_animation_names: Vec<String>,
+    page_edge_names: Vec<String>,
+    page_gap_names: Vec<String>,
+    reflow_font_size_names: Vec<String>,
+    reflow_margin_names: Vec<String>,
+    reflow_width_names: Vec<String>,
+    /// The document says it is read right to left
+    document_rtl: bool,
+    zoom_step_names: Vec<String>,
+    grid_spacing_names: Vec<String>,
+    reading_speed_names: Vec<String>,
+    /// Comma separated zoom presets being edited in the settings
+    zoom_presets_input: String,
+    /// Action waiting for a key press to rebind it
+    key_bind_capture: Option<Action>,
+    /// Screen DPI being edited in the settings
+    screen_dpi_input: String,
+    /// Paper color being edited in the settings
+    paper_color_input: String,
+    /// Colors being edited in the settings, until they are valid
+    color_inputs: HashMap<ColorSetting, String>,
+    /// Physical pixels per logical pixel of the window
+    scale_factor: f32,
+    /// Pane focused with the keyboard, None until F6 is used
+    pane_focus: Option<Pane>,
+    /// PDF documents open with this application by default, None if unknown
+    default_handler: Option<bool>,
+    /// Offering to open PDF documents by default
+    default_handler_dialog: bool,
+    /// Where the document was left, while asking whether to go back there
+    resume_prompt: Option<LastRead>,
+    resume_names: Vec<String>,
+    /// Why the print portal is unavailable, while asking to print with CUPS instead
+    print_fallback: Option<String>,
 }
 
-impl canvas::Program<Message, Theme, Renderer> for App {
-    type State = pdf::CanvasState;
+/// Keys that edit text in a field, which pages should leave to it
+fn editing_key(key: &Key, modifiers: keyboard::Modifiers) -> bool {
+    let command = modifiers.control() || modifiers.alt() || modifiers.logo();
+    !command
+        && matches!(
+            key,
+            Key::Character(_)
+                | Key::Named(
+                    Named::Space
+                        | Named::ArrowLeft
+                        | Named::ArrowRight
+                        | Named::Home
+                        | Named::End
+                        | Named::Backspace
+                        | Named::Delete,
+                )
+        )
+}
 
-    fn update(
-        &self,
-        state: &mut Self::State,
-        event: canvas::Event,
-        bounds: Rectangle,
-        cursor: Cursor,
-    ) -> (Status, Option<Message>) {
-        match event {
-            canvas::Event::Keyboard(keyboard::Event::KeyPressed {
-                key,
-                location,
-                modifiers,
-                text,
-                ..
-            }) => {
-                match key {
-                    Key::Named(Named::Home) => {
-                        *state = pdf::CanvasState::default();
-                        state.modifiers = modifiers;
-                    }
-                    Key::Named(Named::ArrowUp) => {
-                        state.translate.y -= 16.0;
-                    }
-                    Key::Named(Named::ArrowDown) => {
-                        state.translate.y += 16.0;
-                    }
-                    Key::Named(Named::ArrowLeft) => {
-                        state.translate.x += 16.0;
-                    }
-                    Key::Named(Named::ArrowRight) => {
-                        state.translate.x -= 16.0;
-                    }
-                    Key::Named(Named::PageUp) => {
-                        state.scale *= 1.1;
-                    }
-                    Key::Named(Named::PageDown) => {
-                        state.scale /= 1.1;
-                    }
-                    _ => return (Status::Ignored, None),
-                }
-                (Status::Captured, Some(Message::CanvasClearCache))
+/// Operations of a page generated on a blocking thread, none if generating them panicked
+fn blocking_ops(
+    page_id: ObjectId,
+    ops: Result<Vec<pdf::PageOp>, tokio::task::JoinError>,
+) -> Arc<Vec<pdf::PageOp>> {
+    Arc::new(ops.unwrap_or_else(|err| {
+        log::error!("failed to load page {page_id:?}: {err}");
+        Vec::new()
+    }))
+}
+
+/// Play buttons over media, with the frame in PDF units of the page
+fn draw_media(frame: &mut canvas::Frame, media: &[pdf::Media], scale: f32) {
+    for media in media {
+        let rect = media.rect;
+        frame.fill_rectangle(rect.position(), rect.size(), MEDIA_SHADE);
+        let size = (rect.width.min(rect.height) / 2.0).min(MEDIA_BUTTON_SIZE / scale);
+        let center = rect.center();
+        let triangle = canvas::Path::new(|builder| {
+            builder.move_to(Point::new(center.x - size / 3.0, center.y - size / 2.0));
+            builder.line_to(Point::new(center.x + size * 2.0 / 3.0, center.y));
+            builder.line_to(Point::new(center.x - size / 3.0, center.y + size / 2.0));
+            builder.close();
+        });
+        frame.fill(&triangle, Color::WHITE);
+    }
+}
+
+/// Icons of notes, with the frame in PDF units of the page
+fn draw_notes(frame: &mut canvas::Frame, notes: &[note::Note]) {
+    for note in notes {
+        let rect = note.rect;
+        let icon = canvas::Path::rectangle(rect.position(), rect.size());
+        frame.fill(&icon, NOTE_COLOR);
+        frame.stroke(
+            &icon,
+            canvas::Stroke::default()
+                .with_color(NOTE_EDGE)
+                .with_width(rect.width / 20.0),
+        );
+        // Lines of text on the icon, from the top as the frame has y up
+        let lines = canvas::Path::new(|builder| {
+            for i in 1..4 {
+                let y = rect.y + rect.height * (1.0 - i as f32 / 4.0);
+                builder.move_to(Point::new(rect.x + rect.width / 5.0, y));
+                builder.line_to(Point::new(rect.x + rect.width * 4.0 / 5.0, y));
             }
-            canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
-                state.modifiers = modifiers;
-                (Status::Captured, None)
+        });
+        frame.stroke(
+            &lines,
+            canvas::Stroke::default()
+                .with_color(NOTE_EDGE)
+                .with_width(rect.height / 16.0),
+        );
+    }
+}
+
+/// Color of a screenshot at a position in logical pixels
+fn screenshot_color(
+    screenshot: &window::Screenshot,
+    position: Point,
+    scale_factor: f32,
+) -> Option<Color> {
+    let x = (position.x * scale_factor) as u32;
+    let y = (position.y * scale_factor) as u32;
+    if x >= screenshot.size.width || y >= screenshot.size.height {
+        return None;
+    }
+    // Screenshots are RGBA with 8 bits per channel
+    let i = (y as usize * screenshot.size.width as usize + x as usize) * 4;
+    let rgba = screenshot.bytes.get(i..i + 4)?;
+    Some(Color::from_rgb8(rgba[0], rgba[1], rgba[2]))
+}
+
+impl App {
+    /// Generate the operations for a page on a blocking thread, so complex pages hold up
+    /// neither the UI nor the executor, unless the page is loaded or being loaded
+    fn load_page(&self, page_id: ObjectId) -> Task<Message> {
+        if self.page_cache.contains_key(&page_id)
+            || !self.pages_loading.borrow_mut().insert(page_id)
+        {
+            return Task::none();
+        }
+        let doc = self.flags.doc.clone();
+        let hidden = self.hidden_layers.clone();
+        Task::perform(
+            async move {
+                let ops = tokio::task::spawn_blocking({
+                    let hidden = hidden.clone();
+                    move || pdf::page_ops(&doc, page_id, &hidden)
+                })
+                .await;
+                (hidden, blocking_ops(page_id, ops))
+            },
+            move |(hidden, ops)| cosmic::app::Message::App(Message::PageOps(page_id, hidden, ops)),
+        )
+    }
+
+    /// Page not yet loaded that is nearest to the active page, to load ahead of time
+    fn warm_page(&self) -> Option<ObjectId> {
+        if self.page_cache.len() >= WARM_PAGES_MAX {
+            return None;
+        }
+        let position = usize::from(self.position());
+        // Following pages first, as reading goes forward
+        (1..self.page_ids.len())
+            .flat_map(|distance| {
+                [
+                    position.checked_add(distance),
+                    position.checked_sub(distance),
+                ]
+            })
+            .flatten()
+            .filter_map(|i| self.page_ids.get(i))
+            .find(|page_id| !self.page_cache.contains_key(page_id))
+            .copied()
+    }
+
+    fn load_compare_page(&self, page_id: ObjectId) -> Task<Message> {
+        let Some(compare) = self.compare_doc().cloned() else {
+            return Task::none();
+        };
+        if self.compare_cache.contains_key(&page_id)
+            || !self.compare_loading.borrow_mut().insert(page_id)
+        {
+            return Task::none();
+        }
+        let generation = self.compare_generation;
+        Task::perform(
+            async move {
+                let ops = tokio::task::spawn_blocking(move || {
+                    pdf::page_ops(&compare, page_id, &layer::default_hidden(&compare))
+                })
+                .await;
+                blocking_ops(page_id, ops)
+            },
+            move |ops| cosmic::app::Message::App(Message::CompareOps(generation, page_id, ops)),
+        )
+    }
+
+    /// Document shown in the second pane of the split view, none if it is not split
+    fn compare_doc(&self) -> Option<&Arc<Document>> {
+        self.split_view
+            .then(|| self.flags.compare.as_ref().unwrap_or(&self.flags.doc))
+    }
+
+    /// Whether zooming scales the second pane alone, when it has the focus and is not locked
+    fn zooms_compare_alone(&self) -> bool {
+        !self.pane_zoom_lock && self.split_view && self.pane_focus == Some(Pane::Compare)
+    }
+
+    /// Show a document in the second pane, or this document if there is none
+    fn set_compare_doc(&mut self, compare: Option<Arc<Document>>) -> Task<Message> {
+        // The document is compared with itself starting with the next page, and another
+        // document starting with the same page
+        self.compare_offset = i32::from(compare.is_none());
+        self.flags.compare = compare;
+        self.split_view = true;
+        self.compare_ids = self
+            .compare_doc()
+            .map(|compare| compare.page_iter().collect())
+            .unwrap_or_default();
+        self.compare_cache.clear();
+        self.compare_loading.get_mut().clear();
+        self.compare_generation += 1;
+        self.compare_differences.get_mut().clear();
+        self.compare_position = self.position();
+        self.page_changed()
+    }
+
+    /// Words that differ between a page and the page of the compared document next to it, in
+    /// PDF units of each, once both pages are loaded
+    fn text_differences(&self, position: u16) -> Option<Arc<[Vec<Rectangle>; 2]>> {
+        // A document shown next to itself is not compared
+        self.flags.compare.as_ref()?;
+        let page_id = *self.page_ids.get(usize::from(position))?;
+        let compare_id = *self.compare_ids.get(self.compare_index(position)?)?;
+        let ops = self.page_cache.get(&page_id)?;
+        let compare_ops = self.compare_cache.get(&compare_id)?;
+        let differences = self
+            .compare_differences
+            .borrow_mut()
+            .entry((page_id, compare_id))
+            .or_insert_with(|| Arc::new(pdf::text_run_differences(ops, compare_ops)))
+            .clone();
+        Some(differences)
+    }
+
+    /// Pages placed by the layout that are at least partly in view on a canvas of a size, at a
+    /// scale, with their centers in PDF units
+    fn visible_pages(&self, canvas: Size, scale: f32) -> Vec<(u16, Vector)> {
+        // Presentations show the page centered, as the pan may be for another layout
+        let translate = if self.presentation.is_some() {
+            Vector::new(0.0, 0.0)
+        } else {
+            self.view.translate
+        };
+        // Half of the visible height in PDF units
+        let visible = canvas.height / 2.0 / scale;
+        self.layout()
+            .place(
+                &self.page_sizes,
+                self.position(),
+                self.page_gap(),
+                self.cover_page(),
+            )
+            .into_iter()
+            .filter(|(position, center)| {
+                self.page_sizes
+                    .get(usize::from(*position))
+                    .is_some_and(|size| {
+                        (center.y + translate.y).abs() <= visible + size.height / 2.0
+                    })
+            })
+            .collect()
+    }
+
+    /// Load the pages shown by the layout, and those next to them
+    fn load_layout_pages(&self) -> Task<Message> {
+        let position = self.position();
+        let layout = self.layout();
+        let canvas = self.canvas_size.get();
+        let mut positions: Vec<u16> = if canvas.width > 0.0 && canvas.height > 0.0 {
+            self.visible_pages(canvas, self.page_scale(canvas))
+                .into_iter()
+                .map(|(i, _)| i)
+                .collect()
+        } else {
+            // Before the canvas is shown the pages around the active page are loaded
+            (position.saturating_sub(1)..=position.saturating_add(2))
+                .filter(|i| usize::from(*i) < self.page_ids.len())
+                .collect()
+        };
+        // Scrolling shows the pages just outside the view, so load those too
+        if let (Some(&first), Some(&last)) = (positions.iter().min(), positions.iter().max()) {
+            positions.extend(first.checked_sub(1));
+            positions.extend(Some(last + 1).filter(|i| usize::from(*i) < self.page_ids.len()));
+        }
+        // Turning pages shows them empty until they load, so load those either side too
+        if layout != Layout::Continuous {
+            for next in [self.next_position(), self.prev_position()]
+                .into_iter()
+                .flatten()
+            {
+                positions.extend(
+                    layout
+                        .place(&self.page_sizes, next, self.page_gap(), self.cover_page())
+                        .into_iter()
+                        .map(|(i, _)| i),
+                );
             }
-            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
-                if let Some(pos) = cursor.position_in(bounds) {
-                    let (x, y) = match delta {
-                        mouse::ScrollDelta::Lines { x, y } => {
-                            //TODO: best value to translate line scroll to pixels
-                            (x * 16.0, y * 16.0)
-                        }
-                        mouse::ScrollDelta::Pixels { x, y } => (x, y),
-                    };
-                    if state.modifiers.contains(keyboard::Modifiers::CTRL) {
-                        state.scale *= 1.1f32.powf(y / 16.0);
-                    } else {
-                        state.translate.x += x;
-                        state.translate.y -= y;
-                    }
-                    (Status::Captured, Some(Message::CanvasClearCache))
-                } else {
-                    (Status::Ignored, None)
-                }
+        }
+        Task::batch(
+            positions
+                .into_iter()
+                .flat_map(|i| {
+                    [
+                        self.page_ids
+                            .get(usize::from(i))
+                            .map(|&page_id| self.load_page(page_id)),
+                        self.compare_index(i)
+                            .map(|i| self.load_compare_page(self.compare_ids[i])),
+                    ]
+                })
+                .flatten(),
+        )
+    }
+
+    /// Index of the compared page shown next to the page at a position
+    fn compare_index(&self, position: u16) -> Option<usize> {
+        usize::try_from(i32::from(position) + self.compare_offset)
+            .ok()
+            .filter(|i| *i < self.compare_ids.len())
+    }
+
+    /// Show a page of the compared document next to the active page
+    fn compare_goto(&mut self, index: i32) -> Task<Message> {
+        let Some(last) = self.compare_ids.len().checked_sub(1) else {
+            return Task::none();
+        };
+        let index = index.clamp(0, last as i32);
+        self.compare_offset = index - i32::from(self.position());
+        self.canvas_cache.clear();
+        self.load_layout_pages()
+    }
+
+    /// Size of the page compared with the page at a position, as zoomed relative to the pages
+    fn compare_size(&self, position: u16) -> Option<Size> {
+        let compare = self.compare_doc()?;
+        let page_id = *self.compare_ids.get(self.compare_index(position)?)?;
+        let size = pdf::page_box(compare, page_id)
+            .map_or(Size::new(612.0, 792.0), |page_box| page_box.size());
+        Some(Size::new(
+            size.width * self.compare_zoom,
+            size.height * self.compare_zoom,
+        ))
+    }
+
+    /// Horizontal offsets of a page and the page it is compared with, so both are centered
+    fn compare_offsets(&self, position: u16, size: Size) -> Option<(f32, f32)> {
+        let compare_size = self.compare_size(position)?;
+        let gap = self.page_gap();
+        Some((-(compare_size.width + gap) / 2.0, (size.width + gap) / 2.0))
+    }
+
+    /// Space between pages, in PDF units
+    fn page_gap(&self) -> f32 {
+        f32::from(self.flags.config.page_gap)
+    }
+
+    /// Layout used for the pages, presentations always show single pages, and so does compare
+    /// instead of facing pages as each page is already shown next to another
+    fn layout(&self) -> Layout {
+        if self.presentation.is_some() || (self.split_view && self.view.layout == Layout::Facing) {
+            Layout::Single
+        } else {
+            self.view.layout
+        }
+    }
+
+    /// Pan to the active page, if the layout shows more than one screen of pages
+    fn scroll_to_page(&mut self) {
+        if let Some(translate) =
+            self.layout()
+                .scroll(&self.page_sizes, self.position(), self.page_gap())
+        {
+            // Locked views keep their horizontal pan
+            if self.view_lock.is_some() {
+                self.view.translate.y = translate.y;
+            } else {
+                self.view.translate = translate;
             }
-            _ => (Status::Ignored, None),
         }
     }
 
-    fn draw(
-        &self,
-        state: &Self::State,
-        renderer: &Renderer,
-        _theme: &Theme,
-        bounds: Rectangle,
-        _cursor: Cursor,
-    ) -> Vec<widget::canvas::Geometry> {
-        let geo = self.canvas_cache.draw(renderer, bounds.size(), |frame| {
-            if let Some(&page_id) = self.nav_model.active_data::<ObjectId>() {
-                let doc = &self.flags.doc;
-                let page_dict = doc.get_object(page_id).and_then(|obj| obj.as_dict());
-                println!("{:#?}", page_dict);
-                let media_box = page_dict.ok().and_then(|dict| {
-                    let rect = dict.get(b"MediaBox").ok()?.as_array().ok()?;
-                    Some(Rectangle::new(
-                        Point::new(rect.get(0)?.as_float().ok()?, rect.get(1)?.as_float().ok()?),
-                        Size::new(rect.get(2)?.as_float().ok()?, rect.get(3)?.as_float().ok()?),
-                    ))
-                });
-                println!("{:#?}", media_box);
+    /// Page box of the page at a position, with the page's rotation by the user added, and
+    /// trimmed to its contents if margins are trimmed
+    fn page_box(&self, position: u16) -> Option<pdf::PageBox> {
+        let page_id = *self.page_ids.get(usize::from(position))?;
+        let mut page_box = pdf::page_box(&self.flags.doc, page_id)?;
+        if self.flags.config.trim_margins {
+            if let Some(rect) = self
+                .page_content
+                .get(&page_id)
+                .and_then(|content| content.expand(TRIM_MARGIN).intersection(&page_box.rect))
+            {
+                page_box.rect = rect;
+            }
+        }
+        if let Some(rotate) = self.rotations.get(&position) {
+            page_box.rotate = (page_box.rotate + rotate).rem_euclid(360);
+        }
+        Some(page_box)
+    }
 
-                // PDF's origin is the bottom left while the canvas origin is the top right, so flip it
-                {
-                    frame.translate(Vector::new(0.0, frame.size().height));
-                    frame.scale_nonuniform(Vector::new(1.0, -1.0));
-                }
+    /// Add to the user's rotation of a page, in degrees clockwise
+    fn rotate(&mut self, position: u16, degrees: i64) {
+        let rotate = self.rotations.entry(position).or_default();
+        *rotate = (*rotate + degrees).rem_euclid(360);
+        self.update_page_size(position);
+    }
 
-                // Apply zoom and pan
-                //TODO: can user's pan and zoom be applied without having to regenerate entire frame?
-                {
-                    // Move to center
-                    frame.translate(Vector::new(
-                        frame.size().width / 2.0,
-                        frame.size().height / 2.0,
-                    ));
-                    // Zoom
-                    frame.scale(state.scale);
-                    // Apply pan
-                    frame.translate(state.translate);
-                }
-                if let Some(rect) = media_box {
-                    // Move back to origin
-                    frame.translate(Vector::new(
-                        -rect.size().width / 2.0,
-                        -rect.size().height / 2.0,
-                    ));
-                    // Fill background
-                    frame.fill_rectangle(rect.position(), rect.size(), Color::WHITE);
-                }
+    /// Size of a page for layout from its page box, after it is rotated or trimmed
+    fn update_page_size(&mut self, position: u16) {
+        if let Some(page_box) = self.page_box(position) {
+            self.page_sizes[usize::from(position)] = page_box.size();
+        }
+    }
 
-                {
-                    let mut page_cache = self.page_cache.lock().unwrap();
-                    let ops = page_cache
-                        .entry(page_id)
-                        .or_insert_with(|| pdf::page_ops(doc, page_id));
-                    for op in ops.iter() {
-                        if let Some(path) = &op.path {
-                            if let Some(fill) = &op.fill {
-                                frame.fill(path, fill.clone());
-                            }
-                            if let Some(stroke) = &op.stroke {
-                                frame.stroke(path, stroke.clone());
-                            }
-                        }
-                        if let Some(image) = &op.image {
-                            println!("Draw image {:?} at {:?}", image.name, image.rect);
-                            frame.draw_image(image.rect, &image.handle);
-                        }
-                    }
-                }
+    fn position(&self) -> u16 {
+        self.nav_model
+            .position(self.nav_model.active())
+            .unwrap_or(0)
+    }
+
+    fn pan(&mut self, offset: Vector) -> Task<Message> {
+        self.view.translate = self.view.translate + offset;
+        self.canvas_cache.clear();
+        // Scrolling through a continuous layout changes the active page
+        if let Some(position) =
+            self.layout()
+                .position_at(&self.page_sizes, self.view.translate.y, self.page_gap())
+        {
+            if position != self.position() {
+                self.nav_model.activate_position(position);
             }
-        });
-        vec![geo]
+        }
+        // Pages come into view while scrolling without the active page changing
+        self.load_layout_pages()
     }
-}
 
-impl Application for App {
-    type Executor = executor::Default;
-    type Flags = Flags;
-    type Message = Message;
-    const APP_ID: &'static str = "com.system76.CosmicReader";
+    /// Pan in PDF units that keeps a point of the view, in pixels, in place when zooming by a
+    /// factor
+    fn zoom_anchor(&self, bounds: Size, point: Point, factor: f32) -> Vector {
+        let scale = self.page_scale(bounds);
+        // Pages are centered in the view, with PDF units going up
+        let offset = Vector::new(point.x - bounds.width / 2.0, bounds.height / 2.0 - point.y);
+        offset * ((1.0 / factor - 1.0) / scale)
+    }
 
-    fn core(&self) -> &Core {
-        &self.core
+    /// Message for an action run from the canvas, which knows the size of the view
+    fn action_message(&self, action: Action, bounds: Size) -> Message {
+        let screen = bounds.height / self.page_scale(bounds);
+        match action {
+            Action::ScreenDown => Message::ScrollScreen(screen),
+            Action::ScreenUp => Message::ScrollScreen(-screen),
+            Action::PanDown if self.flags.config.arrow_pages => {
+                Message::ScrollStep(ARROW_SCROLL, screen)
+            }
+            Action::PanUp if self.flags.config.arrow_pages => {
+                Message::ScrollStep(-ARROW_SCROLL, screen)
+            }
+            _ => action.message(),
+        }
     }
 
-    fn core_mut(&mut self) -> &mut Core {
-        &mut self.core
+    /// Furthest vertical pan from the center that keeps the current pages filling the view
+    fn pan_limit(&self, screen: f32) -> f32 {
+        match self.layout().size(
+            &self.page_sizes,
+            self.position(),
+            self.page_gap(),
+            self.cover_page(),
+        ) {
+            Some(size) => ((size.height - screen) / 2.0).max(0.0),
+            None => 0.0,
+        }
     }
 
-    fn init(core: Core, flags: Self::Flags) -> (Self, Task<Message>) {
-        let mut nav_model = Model::default();
-        for (i, page_id) in flags.doc.page_iter().enumerate() {
-            nav_model
-                .insert()
-                .text(format!("Page {}", i + 1))
-                .data::<ObjectId>(page_id);
+    /// Scroll by most of a screen, going to the next or previous page once the edge of the
+    /// current one is shown. The screen height is in PDF units, negative to scroll up.
+    fn scroll_screen(&mut self, screen: f32) -> Task<Message> {
+        self.scroll_step(screen * SCREEN_SCROLL, screen.abs())
+    }
+
+    /// Scroll by a step, going to the next or previous page once the edge of the current one is
+    /// shown. The step and screen height are in PDF units, with the step negative to scroll up.
+    fn scroll_step(&mut self, step: f32, screen: f32) -> Task<Message> {
+        if self.layout() == Layout::Continuous {
+            return self.pan(Vector::new(0.0, step));
         }
-        nav_model.activate_position(0);
+        let limit = self.pan_limit(screen);
+        let y = self.view.translate.y;
+        // Tolerate rounding when checking if the edge is shown
+        if (step > 0.0 && y < limit - 0.5) || (step < 0.0 && y > -limit + 0.5) {
+            self.view.translate.y = (y + step).clamp(-limit, limit);
+            self.canvas_cache.clear();
+            return Task::none();
+        }
+        let position = if step > 0.0 {
+            self.next_position()
+        } else {
+            self.prev_position()
+        };
+        let Some(position) = position else {
+            return Task::none();
+        };
+        let task = self.activate_position(position);
+        // Start at the top of the next page, or the bottom of the previous one
+        let limit = self.pan_limit(screen);
+        self.view.translate.y = if step > 0.0 { -limit } else { limit };
+        task
+    }
 
-        (
-            Self {
-                core,
-                flags,
-                canvas_cache: canvas::Cache::new(),
-                nav_model,
-                page_cache: Mutex::new(HashMap::new()),
-            },
-            Task::none(),
-        )
+    fn next_position(&self) -> Option<u16> {
+        let position = self.layout().next(self.position(), self.cover_page());
+        if usize::from(position) < self.nav_model.len() {
+            Some(position)
+        } else {
+            None
+        }
     }
 
-    fn nav_model(&self) -> Option<&Model> {
-        Some(&self.nav_model)
+    fn prev_position(&self) -> Option<u16> {
+        self.layout().prev(self.position(), self.cover_page())
     }
 
-    fn on_nav_select(&mut self, id: widget::nav_bar::Id) -> Task<Message> {
+    /// The pages are no wider than the view, so there is nothing to pan sideways
+    fn fits_width(&self, bounds: Size) -> bool {
+        match self.layout().size(
+            &self.page_sizes,
+            self.position(),
+            self.page_gap(),
+            self.cover_page(),
+        ) {
+            Some(size) => size.width * self.page_scale(bounds) <= bounds.width + 1.0,
+            None => true,
+        }
+    }
+
+    fn activate_position(&mut self, position: u16) -> Task<Message> {
+        let previous = self.position();
+        self.nav_model.activate_position(position);
+        self.animate_page_change(previous, position);
+        self.page_changed()
+    }
+
+    /// Animations replace the previous pages instead of showing them empty while they load,
+    /// scrolling already shows continuous layout moving
+    fn animate_page_change(&mut self, previous: u16, position: u16) {
+        if previous == position
+            || self.layout() == Layout::Continuous
+            || self.presentation.is_some()
+            || self.reduce_motion()
+            // Swipes already slide the new page in
+            || self.swipe.is_some()
+        {
+            return;
+        }
+        match self.flags.config.page_animation {
+            PageAnimation::None => {}
+            PageAnimation::Fade => self.fade = Some(Ease::new(1.0)),
+            PageAnimation::Slide => {
+                // Pages come in from the side they are on, mirrored for right to left
+                let forward = (position > previous) != self.right_to_left();
+                self.swipe = Some(Swipe::released(if forward {
+                    PAGE_SLIDE
+                } else {
+                    -PAGE_SLIDE
+                }));
+            }
+        }
+    }
+
+    fn animates_zoom(&self) -> bool {
+        self.flags.config.page_animation != PageAnimation::None
+            && self.presentation.is_none()
+            && !self.reduce_motion()
+    }
+
+    fn page_changed(&mut self) -> Task<Message> {
         self.canvas_cache.clear();
-        self.nav_model.activate(id);
-        Task::none()
+        // Unsynchronized compared pages stay on the same page
+        let position = self.position();
+        if !self.compare_sync {
+            self.compare_offset -= i32::from(position) - i32::from(self.compare_position);
+        }
+        self.compare_position = position;
+        if let Some(&page_id) = self.nav_model.active_data::<ObjectId>() {
+            if let Some(presentation) = &mut self.presentation {
+                presentation.page_changed(
+                    position,
+                    pdf::page_duration(&self.flags.doc, page_id),
+                    pdf::page_transition(&self.flags.doc, page_id),
+                );
+            }
+        }
+        self.save_last_read();
+        self.scroll_to_page();
+        self.load_layout_pages()
     }
 
-    fn update(&mut self, message: Message) -> Task<Message> {
-        match message {
-            Message::CanvasClearCache => {
-                self.canvas_cache.clear();
+    fn save_config(&self) {
+        if let Some(config_handler) = &self.flags.config_handler {
+            if let Err(err) = self.flags.config.write_entry(config_handler) {
+                log::error!("failed to save config: {}", err);
             }
         }
-        Task::none()
     }
 
-    fn view(&self) -> Element<Message> {
-        canvas::Canvas::new(self)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
+    /// Put this document first in the recent documents, dropping ones that no longer exist
+    fn add_recent(&mut self) {
+        let path = self.flags.key.clone();
+        let recent = &mut self.flags.state.recent;
+        recent.retain(|x| *x != path && x.exists());
+        recent.insert(0, path);
+        recent.truncate(RECENT_MAX);
+        self.save_state();
+    }
+
+    fn save_state(&self) {
+        if let Some(state_handler) = &self.flags.state_handler {
+            if let Err(err) = self.flags.state.write_entry(state_handler) {
+                log::error!("failed to save state: {}", err);
+            }
+        }
+    }
+
+    /// Close the window, saving changes of the view and reading position that are not yet saved
+    fn close(&self) -> Task<Message> {
+        if self.state_changed.is_some() {
+            self.save_state();
+        }
+        match self.core.main_window_id() {
+            Some(id) => window::close(id),
+            None => Task::none(),
+        }
+    }
+
+    /// Scale from PDF units to pixels for the active page
+    fn page_scale(&self, bounds: Size) -> f32 {
+        // Presentations fill the screen with the whole page, leaving the zoom of the view as it is
+        if self.presentation.is_some() {
+            return pdf::PageView::new(Layout::Single, pdf::Fit::Page, 1.0).page_scale(
+                bounds,
+                self.view_size(),
+                self.pixels_per_inch(),
+            );
+        }
+        let size = self.view_lock.or_else(|| self.view_size());
+        self.view.page_scale(bounds, size, self.pixels_per_inch())
+    }
+
+    /// Size of the pages fit to the view, with the compared page next to them
+    fn view_size(&self) -> Option<Size> {
+        let position = self.position();
+        self.layout()
+            .size(
+                &self.page_sizes,
+                position,
+                self.page_gap(),
+                self.cover_page(),
+            )
+            .map(|size| {
+                match self
+                    .compare_size(position)
+                    .filter(|_| self.presentation.is_none())
+                {
+                    Some(compare_size) => Size::new(
+                        size.width + self.page_gap() + compare_size.width,
+                        size.height.max(compare_size.height),
+                    ),
+                    None => size,
+                }
+            })
+    }
+
+    /// Logical pixels per inch, from the screen DPI setting and the window's scale factor
+    fn pixels_per_inch(&self) -> f32 {
+        match self.flags.config.screen_dpi {
+            Some(dpi) => f32::from(dpi) / self.scale_factor,
+            None => 96.0,
+        }
+    }
+
+    /// Open the next or previous document in the folder in place of this one
+    fn open_sibling(&mut self, next: bool) -> Task<Message> {
+        match folder::sibling(&self.flags.path, next) {
+            Some(path) => self.leave(Leave::Open(path)),
+            None => Task::none(),
+        }
+    }
+
+    /// Leave the document, asking first whether to save changed notes
+    fn leave(&mut self, leave: Leave) -> Task<Message> {
+        if !self.notes_changed.is_empty() {
+            self.quit_prompt = Some(leave);
+            return Task::none();
+        }
+        let spawned = match &leave {
+            Leave::Quit => return self.close(),
+            Leave::Open(path) => {
+                env::current_exe().and_then(|exe| process::Command::new(exe).arg(path).spawn())
+            }
+            // The new instance must not hand the document back to this one as it closes
+            Leave::Reopen => env::current_exe().and_then(|exe| {
+                process::Command::new(exe)
+                    .arg("--new-instance")
+                    .arg(&self.flags.path)
+                    .spawn()
+            }),
+        };
+        match spawned {
+            Ok(_) => self.close(),
+            Err(err) => {
+                log::error!("failed to open after {:?}: {}", leave, err);
+                Task::none()
+            }
+        }
+    }
+
+    /// Desktop entry of the application, used to open documents by default
+    fn desktop_id() -> String {
+        format!("{}.desktop", Self::APP_ID)
+    }
+
+    /// Title of print jobs, the file name of the document
+    fn print_title(&self) -> String {
+        self.flags
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// The document as it is rendered, a PDF even when a comic book or image was opened, so
+    /// printers get what is shown
+    async fn print_data(doc: Arc<Document>) -> Result<Vec<u8>, print::Error> {
+        tokio::task::spawn_blocking(move || {
+            let mut data = Vec::new();
+            Document::clone(&doc)
+                .save_to(&mut data)
+                .map(|_| data)
+                .map_err(|err| print::Error::Failed(err.to_string()))
+        })
+        .await
+        .map_err(|err| print::Error::Failed(err.to_string()))?
+    }
+
+    /// File URL of the active page, going to a named destination on it if there is one
+    fn page_link(&self) -> String {
+        let path = percent_encode(self.flags.key.as_os_str().as_encoded_bytes(), b"/");
+        let mut link = format!("file://{path}");
+        let position = self.position();
+        link.push_str(&format!("#page={}", position + 1));
+        if let Some(name) = self
+            .page_ids
+            .get(usize::from(position))
+            .and_then(|&page_id| pdf::named_destination(&self.flags.doc, page_id))
+        {
+            link.push_str("&nameddest=");
+            link.push_str(&percent_encode(name.as_bytes(), b""));
+        }
+        link
+    }
+
+    /// The first page of this document is shown alone in facing pages, like the cover of a book
+    fn cover_page(&self) -> bool {
+        self.flags.state.cover_pages.contains(&self.flags.key)
+    }
+
+    //TODO: move the nav bar to the right for right to left documents once COSMIC allows it
+    fn right_to_left(&self) -> bool {
+        if let Some(&right_to_left) = self.flags.state.right_to_left.get(&self.flags.key) {
+            return right_to_left;
+        }
+        match self.flags.config.direction {
+            Direction::Automatic => self.document_rtl,
+            Direction::LeftToRight => false,
+            Direction::RightToLeft => true,
+        }
+    }
+
+    /// Background of pages, only changing white so images are left as they are
+    fn page_color(&self, dark: bool) -> Color {
+        let config = &self.flags.config;
+        let color = match parse_color(&config.paper_color).filter(|_| config.paper_tint) {
+            Some(color) => color,
+            None => config.page_color.get(dark).unwrap_or(Color::WHITE),
+        };
+        if config.night_mode {
+            night::color(color)
+        } else {
+            color
+        }
+    }
+
+    /// Titles of the sections containing the active page, from the outermost
+    fn breadcrumb(&self) -> Vec<&str> {
+        let position = self.position();
+        let mut breadcrumb: Vec<(usize, &str)> = Vec::new();
+        for (level, title, start) in self.sections.iter() {
+            if *start > position {
+                continue;
+            }
+            // A section ends where the next one at the same or an outer level starts
+            while breadcrumb.last().is_some_and(|(x, _)| x >= level) {
+                breadcrumb.pop();
+            }
+            breadcrumb.push((*level, title));
+        }
+        breadcrumb.into_iter().map(|(_, title)| title).collect()
+    }
+
+    /// Pane shown in the sidebar, pages if the document has nothing to show in the chosen one
+    fn sidebar(&self) -> Sidebar {
+        let empty = match self.flags.state.sidebar {
+            Sidebar::Pages => false,
+            Sidebar::Outline => self.outline_model.len() == 0,
+            Sidebar::Structure => self.structure_model.len() == 0,
+            Sidebar::Bookmarks => self.bookmarks_model.len() == 0,
+            Sidebar::Annotations => self.annotations_model.len() == 0,
+        };
+        if empty {
+            Sidebar::Pages
+        } else {
+            self.flags.state.sidebar
+        }
+    }
+
+    /// Whether a window position is on the edge of the page area next to the sidebar
+    fn on_sidebar_edge(&self, bounds: Rectangle, position: Point) -> bool {
+        self.core.nav_bar_active()
+            && !self.core.is_condensed()
+            && self.presentation.is_none()
+            && bounds.contains(position)
+            && position.x - bounds.x <= SIDEBAR_EDGE
+    }
+
+    /// Activate an entry of a sidebar model that goes to a page, returning the page position
+    fn select_position(model: &mut Model, id: widget::nav_bar::Id) -> Option<u16> {
+        model.activate(id);
+        model.data::<u16>(id).copied()
+    }
+
+    fn update_bookmarks_model(&mut self) {
+        let mut bookmarks_model = Model::default();
+        if let Some(bookmarks) = self.flags.state.bookmarks.get(&self.flags.key) {
+            for &position in bookmarks.iter() {
+                if usize::from(position) < self.page_ids.len() {
+                    bookmarks_model
+                        .insert()
+                        .text(fl!("page-number", number = position + 1))
+                        .data::<u16>(position);
+                }
+            }
+        }
+        self.bookmarks_model = bookmarks_model;
+    }
+
+    /// List the notes of every page, as edited on pages that were loaded
+    fn update_annotations_model(&mut self) {
+        let mut annotations_model = Model::default();
+        for (position, page_id) in self.page_ids.iter().enumerate() {
+            let notes = match self.notes.get(page_id) {
+                Some(notes) => Cow::Borrowed(notes),
+                None => Cow::Owned(note::page_notes(&self.flags.doc, *page_id)),
+            };
+            for note in notes.iter() {
+                let contents = note.contents.lines().next().unwrap_or_default().trim();
+                let contents = if contents.is_empty() {
+                    fl!("note")
+                } else {
+                    contents.to_string()
+                };
+                annotations_model
+                    .insert()
+                    .text(fl!(
+                        "note-on-page",
+                        page = position + 1,
+                        contents = contents
+                    ))
+                    .data::<u16>(position as u16);
+            }
+        }
+        self.annotations_model = annotations_model;
+    }
+
+    //TODO: also follow the system preference once COSMIC provides one
+    /// Move the center of a page so its top left corner is on a whole physical pixel
+    fn snap_to_pixels(
+        &self,
+        center: Vector,
+        size: Size,
+        translate: Vector,
+        scale: f32,
+        bounds: Size,
+    ) -> Vector {
+        let ratio = self.scale_factor;
+        let x = (bounds.width / 2.0 + (translate.x + center.x - size.width / 2.0) * scale) * ratio;
+        // The frame is flipped, so the top of the page has the greatest y
+        let y =
+            (bounds.height / 2.0 - (translate.y + center.y + size.height / 2.0) * scale) * ratio;
+        Vector::new(
+            center.x + (x.round() - x) / ratio / scale,
+            center.y - (y.round() - y) / ratio / scale,
+        )
+    }
+
+    /// Number of matches of the search term on the page at a position
+    fn page_search_matches(&self, position: u16) -> usize {
+        self.page_ids
+            .get(usize::from(position))
+            .map_or(0, |&page_id| self.search_matches(page_id).len())
+    }
+
+    /// Boxes around the search term on a loaded page, in PDF units of the page
+    fn search_matches(&self, page_id: ObjectId) -> Vec<Rectangle> {
+        match (&self.search, self.page_cache.get(&page_id)) {
+            (Some(search), Some(ops)) => search::matches(ops, search),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Index of the selected match on a page with some number of matches
+    fn selected_match(&self, position: u16, matches: usize) -> Option<usize> {
+        let (selected_position, index) = self.search_match?;
+        (selected_position == position && matches > 0).then(|| index.min(matches - 1))
+    }
+
+    fn reduce_motion(&self) -> bool {
+        self.flags.config.reduce_motion || self.save_power()
+    }
+
+    fn save_power(&self) -> bool {
+        self.flags.config.save_power && self.on_battery
+    }
+
+    /// Animation frames, at a lower rate while saving power
+    fn frames(&self) -> Subscription<Instant> {
+        if self.save_power() {
+            time::every(POWER_SAVING_FRAME)
+        } else {
+            window::frames()
+        }
+    }
+
+    /// Slide transitions are shown unless disabled on the command line or motion is reduced
+    fn transitions(&self) -> bool {
+        self.flags.transitions && !self.reduce_motion()
+    }
+
+    /// Where the document was left, if anywhere past the start
+    fn last_read(&self) -> Option<LastRead> {
+        if self.flags.kiosk {
+            return None;
+        }
+        self.flags
+            .state
+            .last_read
+            .get(&self.flags.key)
+            .copied()
+            .filter(|last_read| {
+                last_read.page > 0 && usize::from(last_read.page) < self.page_ids.len()
+            })
+    }
+
+    /// Go back to where the document was left, with the view it had unless it remembers its own
+    fn resume(&mut self, last_read: LastRead) {
+        if !self.remembers_view() {
+            let view = last_read.view;
+            self.view = pdf::PageView::new(view.layout, view.fit, view.scale);
+        }
+        self.nav_model.activate_position(last_read.page);
+    }
+
+    /// Remember where the document is, to continue reading it when it is opened again
+    fn save_last_read(&mut self) {
+        if self.flags.kiosk || self.resume_prompt.is_some() {
+            return;
+        }
+        let last_read = LastRead {
+            page: self.position(),
+            view: self.document_view(),
+        };
+        let previous = self
+            .flags
+            .state
+            .last_read
+            .insert(self.flags.key.clone(), last_read);
+        if previous != Some(last_read) {
+            self.state_changed = Some(Instant::now());
+        }
+    }
+
+    fn remembers_view(&self) -> bool {
+        self.flags.state.documents.contains_key(&self.flags.key)
+    }
+
+    fn document_view(&self) -> DocumentView {
+        DocumentView {
+            layout: self.view.layout,
+            fit: self.view.fit,
+            scale: self.view.scale,
+        }
+    }
+
+    fn set_view(&mut self, document_view: DocumentView) -> Task<Message> {
+        self.view =
+            pdf::PageView::new(document_view.layout, document_view.fit, document_view.scale);
+        self.scroll_to_page();
+        self.view_changed();
+        self.load_layout_pages()
+    }
+
+    fn view_changed(&mut self) {
+        self.canvas_cache.clear();
+        self.save_last_read();
+        if self.remembers_view() {
+            let document_view = self.document_view();
+            self.flags
+                .state
+                .documents
+                .insert(self.flags.key.clone(), document_view);
+            self.state_changed = Some(Instant::now());
+        }
+    }
+
+    fn update_default_zoom_names(&mut self) {
+        self.default_zoom_names = vec![pdf::Fit::Page.name(), pdf::Fit::Width.name()];
+        for &percent in self.flags.config.zoom_presets.iter() {
+            self.default_zoom_names
+                .push(fl!("zoom-percent", percent = percent));
+        }
+    }
+
+    fn about(&self) -> Element<Message> {
+        widget::column::with_children(vec![
+            widget::text::title3(fl!("cosmic-reader")).into(),
+            widget::text::body(fl!("version", version = env!("CARGO_PKG_VERSION"))).into(),
+            widget::text::body(fl!("license")).into(),
+            widget::text::body("https://github.com/pop-os/cosmic-reader").into(),
+        ])
+        .spacing(8)
+        .into()
+    }
+
+    fn export(&self) -> Element<Message> {
+        let stamp = &self.stamp;
+        let position = StampPosition::ALL
+            .iter()
+            .position(|position| *position == stamp.position);
+        let stamp_section = widget::settings::section()
+            .title(fl!("stamp"))
+            .add(widget::settings::item(
+                fl!("stamp-text"),
+                widget::text_input("DRAFT", &stamp.text).on_input(Message::StampText),
+            ))
+            .add(widget::settings::item(
+                fl!("stamp-image"),
+                widget::text_input(fl!("none"), &self.stamp_image_input)
+                    .on_input(Message::StampImage),
+            ))
+            .add(widget::settings::item(
+                fl!("stamp-position"),
+                widget::dropdown(&self.stamp_position_names, position, |i| {
+                    Message::StampPosition(StampPosition::ALL[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("stamp-rotation"),
+                widget::row::with_children(vec![
+                    widget::slider(-90..=90, stamp.rotation, Message::StampRotation).into(),
+                    widget::text::body(fl!("degrees", degrees = stamp.rotation)).into(),
+                ])
+                .spacing(8),
+            ))
+            .add(widget::settings::item(
+                fl!("stamp-opacity"),
+                widget::row::with_children(vec![
+                    widget::slider(5..=100, stamp.opacity, Message::StampOpacity).into(),
+                    widget::text::body(fl!("percent", percent = stamp.opacity)).into(),
+                ])
+                .spacing(8),
+            ));
+        let mut crop_section = widget::settings::section().title(fl!("crop"));
+        for (i, name) in [
+            fl!("crop-top"),
+            fl!("crop-right"),
+            fl!("crop-bottom"),
+            fl!("crop-left"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            crop_section = crop_section.add(widget::settings::item(
+                name,
+                widget::text_input("0", &self.crop_inputs[i])
+                    .on_input(move |input| Message::CropMargin(i, input)),
+            ));
+        }
+        crop_section = crop_section.add(widget::settings::item(
+            fl!("crop-current-page"),
+            widget::toggler(self.crop_current_page).on_toggle(Message::CropCurrentPage),
+        ));
+        let mut children = vec![
+            crop_section.into(),
+            stamp_section.into(),
+            widget::button::suggested(fl!("export"))
+                .on_press(Message::Export)
+                .into(),
+        ];
+        if let Some(status) = &self.export_status {
+            children.push(widget::text::body(status).into());
+        }
+        widget::settings::view_column(children).into()
+    }
+
+    fn export_image(&self) -> Element<Message> {
+        let format = raster::Format::ALL
+            .iter()
+            .position(|format| *format == self.image_format);
+        let section = widget::settings::section()
+            .add(widget::settings::item(
+                fl!("image-format"),
+                widget::dropdown(&self.image_format_names, format, |i| {
+                    Message::ImageFormat(raster::Format::ALL[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("resolution"),
+                widget::row::with_children(vec![
+                    widget::text_input(raster::DPI.to_string(), &self.image_dpi_input)
+                        .on_input(Message::ImageDpiInput)
+                        .width(Length::Fixed(80.0))
+                        .into(),
+                    widget::text::body(fl!("dots-per-inch", dpi = self.image_dpi)).into(),
+                ])
+                .align_y(Vertical::Center)
+                .spacing(8),
+            ))
+            .add(widget::settings::item(
+                fl!("pages"),
+                widget::text_input(fl!("current-page"), &self.image_pages_input)
+                    .on_input(Message::ImagePagesInput)
+                    .on_submit(Message::ExportImage)
+                    .width(Length::Fixed(120.0)),
+            ));
+        let mut children = vec![
+            section.into(),
+            widget::button::suggested(fl!("export"))
+                .on_press(Message::ExportImage)
+                .into(),
+        ];
+        if let Some(status) = &self.export_status {
+            children.push(widget::text::body(status).into());
+        }
+        widget::settings::view_column(children).into()
+    }
+
+    /// Crop shown on pages while the export options are open
+    fn export_crop(&self) -> Option<export::Crop> {
+        if self.presentation.is_some()
+            || !self.core.window.show_context
+            || self.context_page != ContextPage::Export
+        {
+            return None;
+        }
+        self.export_options().crop
+    }
+
+    /// Notes of a page as they are now, to undo an edit of them
+    fn notes_edit(&self, page_id: ObjectId) -> Edit {
+        Edit::Notes(
+            page_id,
+            self.notes.get(&page_id).cloned().unwrap_or_default(),
+        )
+    }
+
+    /// Crop and stamp as they are now, to undo an edit of them
+    fn export_edit(&self) -> Edit {
+        Edit::Export(Box::new(ExportEdit {
+            crop_inputs: self.crop_inputs.clone(),
+            crop_margins: self.crop_margins,
+            crop_current_page: self.crop_current_page,
+            stamp: self.stamp.clone(),
+            stamp_image_input: self.stamp_image_input.clone(),
+        }))
+    }
+
+    /// What an edit changed as it is now
+    fn edit_state(&self, edit: &Edit) -> Edit {
+        match edit {
+            Edit::Notes(page_id, _) => self.notes_edit(*page_id),
+            Edit::Export(_) => self.export_edit(),
+        }
+    }
+
+    /// Put back what an edit changed as it was
+    fn restore_edit(&mut self, edit: Edit) {
+        match edit {
+            Edit::Notes(page_id, notes) => {
+                self.notes.insert(page_id, notes);
+                self.notes_changed.insert(page_id);
+                // The note being edited may be gone
+                self.note_edit = None;
+                self.update_annotations_model();
+            }
+            Edit::Export(export) => {
+                let ExportEdit {
+                    crop_inputs,
+                    crop_margins,
+                    crop_current_page,
+                    stamp,
+                    stamp_image_input,
+                } = *export;
+                self.crop_inputs = crop_inputs;
+                self.crop_margins = crop_margins;
+                self.crop_current_page = crop_current_page;
+                self.stamp = stamp;
+                self.stamp_image_input = stamp_image_input;
+            }
+        }
+        self.canvas_cache.clear();
+    }
+
+    /// Changes made to exported copies
+    fn export_options(&self) -> export::Export {
+        let stamp = &self.stamp;
+        export::Export {
+            crop: self
+                .crop_margins
+                .iter()
+                .any(|x| *x > 0.0)
+                .then(|| export::Crop {
+                    margins: self.crop_margins,
+                    page_id: if self.crop_current_page {
+                        self.page_ids.get(usize::from(self.position())).copied()
+                    } else {
+                        None
+                    },
+                }),
+            stamp: (!stamp.text.trim().is_empty() || stamp.image.is_some()).then(|| stamp.clone()),
+        }
+    }
+
+    /// Search field with the number of results
+    fn search_field<'a>(&'a self, search: &'a str) -> Element<'a, Message> {
+        let results = if search.trim().is_empty() {
+            String::new()
+        } else if self.search_index.is_none() {
+            fl!("indexing")
+        } else {
+            let matches = self
+                .nav_model
+                .active_data::<ObjectId>()
+                .map_or(0, |&page_id| self.search_matches(page_id).len());
+            let current = self
+                .selected_match(self.position(), matches)
+                .map_or(0, |index| index + 1);
+            fl!(
+                "search-results",
+                current = current,
+                matches = matches,
+                pages = self.search_results.len()
+            )
+        };
+        widget::row::with_children(vec![
+            widget::text::body(results).into(),
+            widget::search_input(fl!("search"), search)
+                .id(self.search_id.clone())
+                .on_input(Message::SearchInput)
+                .width(Length::Fixed(240.0))
+                .into(),
+        ])
+        .spacing(8)
+        .align_y(Vertical::Center)
+        .into()
+    }
+
+    fn statistics(&self) -> Element<Message> {
+        let Some(statistics) = self.statistics else {
+            return widget::text::body(fl!("calculating")).into();
+        };
+        let mut sections = vec![widget::settings::section()
+            .add(widget::settings::item(
+                fl!("page-count"),
+                widget::text::body(statistics.pages.to_string()),
+            ))
+            .add(widget::settings::item(
+                fl!("word-count"),
+                widget::text::body(statistics.words.to_string()),
+            ))
+            .add(widget::settings::item(
+                fl!("character-count"),
+                widget::text::body(statistics.characters.to_string()),
+            ))
+            .add(widget::settings::item(
+                fl!("image-count"),
+                widget::text::body(statistics.images.to_string()),
+            ))
+            .add(widget::settings::item(
+                fl!("reading-time"),
+                widget::text::body(fl!(
+                    "reading-minutes",
+                    minutes = statistics.reading_minutes()
+                )),
+            ))
+            .into()];
+        if let Some(conformance) = &self.conformance {
+            let claim =
+                |claim: &Option<String>| claim.clone().unwrap_or_else(|| fl!("not-claimed"));
+            let mut section = widget::settings::section()
+                .title(fl!("conformance"))
+                .add(widget::settings::item(
+                    "PDF/A",
+                    widget::text::body(claim(&conformance.pdfa)),
+                ))
+                .add(widget::settings::item(
+                    "PDF/UA",
+                    widget::text::body(claim(&conformance.pdfua)),
+                ));
+            if conformance.pdfa.is_some() || conformance.pdfua.is_some() {
+                if conformance.issues.is_empty() {
+                    section = section.add(widget::text::body(fl!("no-issues")));
+                }
+                for issue in conformance.issues.iter() {
+                    section = section.add(widget::text::body(issue.name()));
+                }
+            }
+            sections.push(section.into());
+        }
+        widget::settings::view_column(sections).into()
+    }
+
+    fn keyboard_shortcuts(&self) -> Element<Message> {
+        let mut keys = widget::settings::section();
+        for action in Action::ALL {
+            if let Some(key_bind) = self.flags.config.key_binds.get(&action) {
+                keys = keys.add(widget::settings::item(
+                    action.name(),
+                    widget::text::body(key_bind.to_string()),
+                ));
+            }
+        }
+        let mut mouse = widget::settings::section().title(fl!("mouse"));
+        for mouse_bind in MouseBind::ALL {
+            if let Some(action) = self.flags.config.mouse_binds.get(&mouse_bind) {
+                mouse = mouse.add(widget::settings::item(
+                    mouse_bind.name(),
+                    widget::text::body(action.name()),
+                ));
+            }
+        }
+        widget::settings::view_column(vec![
+            keys.into(),
+            mouse.into(),
+            widget::text::caption(fl!("shortcuts-changed-in-settings")).into(),
+        ])
+        .into()
+    }
+
+    fn layers(&self) -> Element<Message> {
+        if self.layers.is_empty() {
+            return widget::text::body(fl!("no-layers")).into();
+        }
+        let mut section = widget::settings::section();
+        for (i, layer) in self.layers.iter().enumerate() {
+            let name = if layer.name.is_empty() {
+                fl!("unnamed-layer")
+            } else {
+                layer.name.clone()
+            };
+            section = section.add(widget::settings::item(
+                name,
+                widget::toggler(layer.visible).on_toggle(move |visible| Message::Layer(i, visible)),
+            ));
+        }
+        widget::settings::view_column(vec![section.into()]).into()
+    }
+
+    fn signatures(&self) -> Element<Message> {
+        if self.signatures.is_empty() {
+            return widget::text::body(fl!("no-signatures")).into();
+        }
+        let mut sections = Vec::new();
+        for signature in self.signatures.iter() {
+            let mut section = widget::settings::section().title(
+                signature
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| fl!("unknown-signer")),
+            );
+            if let Some(time) = signature.time {
+                section = section.add(widget::settings::item(
+                    fl!("signed-at"),
+                    widget::text::body(time.format("%Y-%m-%d %H:%M %:z").to_string()),
+                ));
+            }
+            if let Some(reason) = &signature.reason {
+                section = section.add(widget::settings::item(
+                    fl!("signature-reason"),
+                    widget::text::body(reason),
+                ));
+            }
+            if let Some(location) = &signature.location {
+                section = section.add(widget::settings::item(
+                    fl!("signature-location"),
+                    widget::text::body(location),
+                ));
+            }
+            section = section.add(widget::settings::item(
+                fl!("signature-status"),
+                widget::text::body(signature.status.name()),
+            ));
+            sections.push(section.into());
+        }
+        sections.push(widget::text::caption(fl!("signatures-not-verified")).into());
+        widget::settings::view_column(sections).into()
+    }
+
+    fn settings(&self) -> Element<Message> {
+        let mut section = widget::settings::section().title(fl!("keyboard-shortcuts"));
+        for action in Action::ALL {
+            let label = if self.key_bind_capture == Some(action) {
+                fl!("press-a-key")
+            } else {
+                match self.flags.config.key_binds.get(&action) {
+                    Some(key_bind) => key_bind.to_string(),
+                    None => fl!("disabled"),
+                }
+            };
+            section = section.add(widget::settings::item(
+                action.name(),
+                widget::button::standard(label).on_press(Message::KeyBindCapture(Some(action))),
+            ));
+        }
+        let default_view = self.flags.config.view;
+        let default_zoom = match default_view.fit {
+            pdf::Fit::Page => Some(0),
+            pdf::Fit::Width => Some(1),
+            pdf::Fit::ActualSize => self
+                .flags
+                .config
+                .zoom_presets
+                .iter()
+                .position(|&percent| f32::from(percent) == (default_view.scale * 100.0).round())
+                .map(|i| i + 2),
+        };
+        let default_layout = Layout::ALL
+            .iter()
+            .position(|layout| *layout == default_view.layout);
+        let direction = Direction::ALL
+            .iter()
+            .position(|direction| *direction == self.flags.config.direction);
+        let page_animation = PageAnimation::ALL
+            .iter()
+            .position(|animation| *animation == self.flags.config.page_animation);
+        let zoom_step = ZOOM_STEPS
+            .iter()
+            .position(|step| *step == self.flags.config.zoom_step);
+        let resume = Resume::ALL
+            .iter()
+            .position(|resume| *resume == self.flags.config.resume);
+        let reading_speed = READING_SPEEDS
+            .iter()
+            .position(|speed| *speed == self.flags.config.reading_speed);
+        let grid_spacing = GRID_SPACINGS
+            .iter()
+            .position(|spacing| *spacing == self.flags.config.grid_spacing);
+        let page_gap = PAGE_GAPS
+            .iter()
+            .position(|gap| *gap == self.flags.config.page_gap);
+        let page_edge = PageEdge::ALL
+            .iter()
+            .position(|edge| *edge == self.flags.config.page_edge);
+        let view_section = widget::settings::section()
+            .title(fl!("view"))
+            .add(widget::settings::item(
+                fl!("default-zoom"),
+                widget::dropdown(&self.default_zoom_names, default_zoom, Message::DefaultZoom),
+            ))
+            .add(widget::settings::item(
+                fl!("default-layout"),
+                widget::dropdown(&self.layout_names, default_layout, |i| {
+                    Message::DefaultLayout(Layout::ALL[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("screen-dpi"),
+                widget::text_input(fl!("automatic"), &self.screen_dpi_input)
+                    .on_input(Message::ScreenDpiInput),
+            ))
+            .add(widget::settings::item(
+                fl!("paper-tint"),
+                widget::toggler(self.flags.config.paper_tint).on_toggle(Message::PaperTint),
+            ))
+            .add(widget::settings::item(
+                fl!("paper-color"),
+                widget::text_input("#f4ecd8", &self.paper_color_input)
+                    .on_input(Message::PaperColorInput),
+            ))
+            .add(widget::settings::item(
+                fl!("night-mode"),
+                widget::toggler(self.flags.config.night_mode).on_toggle(|_| Message::NightMode),
+            ))
+            .add(widget::settings::item(
+                fl!("page-order"),
+                widget::dropdown(&self.direction_names, direction, |i| {
+                    Message::PageOrder(Direction::ALL[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("page-gap"),
+                widget::dropdown(&self.page_gap_names, page_gap, |i| {
+                    Message::PageGap(PAGE_GAPS[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("page-edge"),
+                widget::dropdown(&self.page_edge_names, page_edge, |i| {
+                    Message::PageEdge(PageEdge::ALL[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("save-power"),
+                widget::toggler(self.flags.config.save_power).on_toggle(Message::SavePower),
+            ))
+            .add(widget::settings::item(
+                fl!("power-source"),
+                widget::text::body(if self.on_battery {
+                    fl!("battery")
+                } else {
+                    fl!("plugged-in")
+                }),
+            ))
+            .add(
+                widget::settings::item::builder(fl!("smooth-rendering"))
+                    .description(fl!("smooth-rendering-restart"))
+                    .toggler(self.flags.config.antialiasing, Message::Antialiasing),
+            )
+            .add(widget::settings::item(
+                fl!("sharp-pixels"),
+                widget::toggler(self.flags.config.sharp_pixels).on_toggle(Message::SharpPixels),
+            ))
+            .add(widget::settings::item(
+                fl!("darken-text"),
+                widget::toggler(self.flags.config.darken_text).on_toggle(Message::DarkenText),
+            ))
+            .add(widget::settings::item(
+                fl!("page-animation"),
+                widget::dropdown(&self.page_animation_names, page_animation, |i| {
+                    Message::PageAnimation(PageAnimation::ALL[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("arrow-pages"),
+                widget::toggler(self.flags.config.arrow_pages).on_toggle(Message::ArrowPages),
+            ))
+            .add(widget::settings::item(
+                fl!("reduce-motion"),
+                widget::toggler(self.flags.config.reduce_motion).on_toggle(Message::ReduceMotion),
+            ))
+            .add(widget::settings::item(
+                fl!("resume"),
+                widget::dropdown(&self.resume_names, resume, |i| {
+                    Message::Resume(Resume::ALL[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("reading-speed"),
+                widget::dropdown(&self.reading_speed_names, reading_speed, |i| {
+                    Message::ReadingSpeed(READING_SPEEDS[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("grid-spacing"),
+                widget::dropdown(&self.grid_spacing_names, grid_spacing, |i| {
+                    Message::GridSpacing(GRID_SPACINGS[i])
+                }),
+            ))
+            .add(widget::settings::item(
+                fl!("zoom-presets"),
+                widget::text_input("", &self.zoom_presets_input)
+                    .on_input(Message::ZoomPresetsInput),
+            ))
+            .add(widget::settings::item(
+                fl!("zoom-step"),
+                widget::dropdown(&self.zoom_step_names, zoom_step, |i| {
+                    Message::ZoomStep(ZOOM_STEPS[i])
+                }),
+            ));
+        let reflow = self.flags.config.reflow;
+        let reflow_section = widget::settings::section()
+            .title(fl!("books"))
+            .add(widget::settings::item(
+                fl!("book-font-size"),
+                widget::dropdown(
+                    &self.reflow_font_size_names,
+                    REFLOW_FONT_SIZES
+                        .iter()
+                        .position(|size| *size == reflow.font_size),
+                    move |i| {
+                        Message::Reflow(Reflow {
+                            font_size: REFLOW_FONT_SIZES[i],
+                            ..reflow
+                        })
+                    },
+                ),
+            ))
+            .add(widget::settings::item(
+                fl!("book-margin"),
+                widget::dropdown(
+                    &self.reflow_margin_names,
+                    REFLOW_MARGINS
+                        .iter()
+                        .position(|margin| *margin == reflow.margin),
+                    move |i| {
+                        Message::Reflow(Reflow {
+                            margin: REFLOW_MARGINS[i],
+                            ..reflow
+                        })
+                    },
+                ),
+            ))
+            .add(widget::settings::item(
+                fl!("book-width"),
+                widget::dropdown(
+                    &self.reflow_width_names,
+                    REFLOW_WIDTHS
+                        .iter()
+                        .position(|width| *width == reflow.width),
+                    move |i| {
+                        Message::Reflow(Reflow {
+                            width: REFLOW_WIDTHS[i],
+                            ..reflow
+                        })
+                    },
+                ),
+            ));
+        let annotation_section =
+            widget::settings::section()
+                .title(fl!("annotations"))
+                .add(widget::settings::item(
+                    fl!("author"),
+                    widget::text_input("", &self.flags.config.author)
+                        .on_input(Message::AuthorInput),
+                ));
+        let mut color_section = widget::settings::section().title(fl!("colors"));
+        for setting in ColorSetting::ALL {
+            let input = self
+                .color_inputs
+                .get(&setting)
+                .map_or(self.flags.config.color(setting), String::as_str);
+            color_section = color_section.add(widget::settings::item(
+                setting.name(),
+                widget::text_input(fl!("default"), input)
+                    .on_input(move |input| Message::ColorInput(setting, input)),
+            ));
+        }
+        let mut mouse_section = widget::settings::section().title(fl!("mouse"));
+        for mouse_bind in MouseBind::ALL {
+            // The first choice is no action
+            let selected = match self.flags.config.mouse_binds.get(&mouse_bind) {
+                Some(action) => Action::ALL.iter().position(|x| x == action).map(|i| i + 1),
+                None => Some(0),
+            };
+            mouse_section = mouse_section.add(widget::settings::item(
+                mouse_bind.name(),
+                widget::dropdown(&self.action_names, selected, move |i| {
+                    Message::MouseBindSet(mouse_bind, i.checked_sub(1).map(|i| Action::ALL[i]))
+                }),
+            ));
+        }
+        let wheel = Wheel::ALL
+            .iter()
+            .position(|wheel| *wheel == self.flags.config.wheel);
+        mouse_section = mouse_section.add(widget::settings::item(
+            fl!("wheel"),
+            widget::dropdown(&self.wheel_names, wheel, |i| Message::Wheel(Wheel::ALL[i])),
+        ));
+        let mut sections = Vec::new();
+        // Without xdg-mime the default application is unknown and cannot be set
+        if let Some(is_default) = self.default_handler {
+            let control: Element<_> = if is_default {
+                widget::text::body(fl!("is-default")).into()
+            } else {
+                widget::button::standard(fl!("set-default"))
+                    .on_press(Message::DefaultHandlerSet)
+                    .into()
+            };
+            sections.push(
+                widget::settings::section()
+                    .title(fl!("default-viewer"))
+                    .add(widget::settings::item(fl!("open-pdf-documents"), control))
+                    .into(),
+            );
+        }
+        sections.extend([
+            view_section.into(),
+            color_section.into(),
+            reflow_section.into(),
+            annotation_section.into(),
+            mouse_section.into(),
+            section.into(),
+            widget::button::standard(fl!("reset-shortcuts"))
+                .on_press(Message::KeyBindsReset)
+                .into(),
+        ]);
+        widget::settings::view_column(sections).into()
+    }
+
+    /// Draw a page with the frame origin at the center of the page, in PDF orientation
+    fn draw_page(
+        &self,
+        frame: &mut canvas::Frame,
+        ops: Option<&Arc<Vec<pdf::PageOp>>>,
+        page_box: Option<pdf::PageBox>,
+        background: Color,
+        scale: f32,
+    ) {
+        // Darkening is a fixed width on screen, so it matters most for small text
+        let darken = self
+            .flags
+            .config
+            .darken_text
+            .then(|| TEXT_DARKENING / scale);
+        if let Some(page_box) = page_box {
+            // Rotate clockwise, the frame is flipped so the angle is negated
+            frame.rotate(-(page_box.rotate as f32).to_radians());
+            // Move center of visible area to origin
+            let rect = page_box.rect;
+            frame.translate(Vector::new(
+                -(rect.x + rect.width / 2.0),
+                -(rect.y + rect.height / 2.0),
+            ));
+            // Fill background
+            //TODO: clip page contents to the crop box
+            frame.fill_rectangle(rect.position(), rect.size(), background);
+        }
+
+        // Images are left as they are in night mode, as photos would look like negatives
+        let night = self.flags.config.night_mode;
+        // Backgrounds of the whole page would be drawn past the edges of trimmed pages
+        let trim = self.flags.config.trim_margins;
+        if let Some(ops) = ops {
+            for op in ops.iter().filter(|op| !(trim && op.blank())) {
+                if let Some(path) = &op.path {
+                    if let Some(fill) = &op.fill {
+                        let mut fill = fill.clone();
+                        if night {
+                            fill.style = night::style(&fill.style);
+                        }
+                        frame.fill(path, fill);
+                        if let (true, Some(width)) = (op.glyph, darken) {
+                            if let canvas::Style::Solid(color) = fill.style {
+                                frame.stroke(
+                                    path,
+                                    canvas::Stroke::default()
+                                        .with_color(color)
+                                        .with_width(width),
+                                );
+                            }
+                        }
+                    }
+                    if let Some(stroke) = &op.stroke {
+                        let mut stroke = stroke.clone();
+                        if night {
+                            stroke.style = night::style(&stroke.style);
+                        }
+                        frame.stroke(path, stroke);
+                    }
+                }
+                if let Some(image) = &op.image {
+                    frame.draw_image(image.rect, &image.handle);
+                }
+            }
+        }
+    }
+
+    /// Border or shadow behind a page, with the frame origin at the center of the page
+    fn draw_page_edge(&self, frame: &mut canvas::Frame, size: Size, scale: f32, dark: bool) {
+        let (offset, outset, alpha) = match self.flags.config.page_edge {
+            PageEdge::None => return,
+            PageEdge::Border => (0.0, PAGE_EDGE_WIDTH / scale, 0.25),
+            PageEdge::Shadow => (PAGE_SHADOW_OFFSET / scale, 0.0, 0.3),
+        };
+        // Light edges stand out against dark canvases, dark edges against light ones
+        let color = if dark {
+            Color::from_rgba(1.0, 1.0, 1.0, alpha)
+        } else {
+            Color::from_rgba(0.0, 0.0, 0.0, alpha)
+        };
+        // The frame is flipped, so the shadow falls down by moving up in PDF units
+        frame.fill_rectangle(
+            Point::new(
+                -size.width / 2.0 - outset + offset,
+                -size.height / 2.0 - outset - offset,
+            ),
+            Size::new(size.width + 2.0 * outset, size.height + 2.0 * outset),
+            color,
+        );
+    }
+
+    /// Grid from the top left of a page, with the frame origin at the center of the page
+    fn draw_grid(&self, frame: &mut canvas::Frame, size: Size, scale: f32) {
+        let spacing = f32::from(self.flags.config.grid_spacing.max(1));
+        // Lines are a pixel wide at any zoom
+        let stroke = canvas::Stroke::default()
+            .with_color(GRID_COLOR)
+            .with_width(1.0 / scale);
+        let (left, top) = (-size.width / 2.0, size.height / 2.0);
+        let path = canvas::Path::new(|builder| {
+            let mut x = spacing;
+            while x < size.width {
+                builder.move_to(Point::new(left + x, top));
+                builder.line_to(Point::new(left + x, top - size.height));
+                x += spacing;
+            }
+            let mut y = spacing;
+            while y < size.height {
+                builder.move_to(Point::new(left, top - y));
+                builder.line_to(Point::new(left + size.width, top - y));
+                y += spacing;
+            }
+        });
+        frame.stroke(&path, stroke);
+    }
+
+    /// Area of each page shown on the canvas, in canvas pixels
+    fn page_rects(&self, bounds: Size) -> Vec<(u16, Rectangle)> {
+        let scale = self.page_scale(bounds);
+        self.layout()
+            .place(
+                &self.page_sizes,
+                self.position(),
+                self.page_gap(),
+                self.cover_page(),
+            )
+            .into_iter()
+            .filter_map(|(position, center)| {
+                let size = match self.page_box(position) {
+                    Some(page_box) => page_box.size(),
+                    None => *self.page_sizes.get(usize::from(position))?,
+                };
+                let center = if self.right_to_left() {
+                    Vector::new(-center.x, center.y)
+                } else {
+                    center
+                };
+                let center = match self.compare_offsets(position, size) {
+                    Some((offset, _)) => center + Vector::new(offset, 0.0),
+                    None => center,
+                };
+                let x = bounds.width / 2.0 + (self.view.translate.x + center.x) * scale;
+                let y = bounds.height / 2.0 - (self.view.translate.y + center.y) * scale;
+                let size = Size::new(size.width * scale, size.height * scale);
+                Some((
+                    position,
+                    Rectangle::new(
+                        Point::new(x - size.width / 2.0, y - size.height / 2.0),
+                        size,
+                    ),
+                ))
+            })
+            .collect()
+    }
+
+    /// Area of the page compared with the active page, in canvas pixels
+    fn compare_rect(&self, bounds: Size) -> Option<Rectangle> {
+        let position = self.position();
+        let (_, rect) = self
+            .page_rects(bounds)
+            .into_iter()
+            .find(|(x, _)| *x == position)?;
+        let scale = self.page_scale(bounds);
+        let size = self.compare_size(position)?;
+        let size = Size::new(size.width * scale, size.height * scale);
+        Some(Rectangle::new(
+            Point::new(
+                rect.x + rect.width + self.page_gap() * scale,
+                rect.center_y() - size.height / 2.0,
+            ),
+            size,
+        ))
+    }
+
+    /// Media of an annotation under a position in canvas pixels, by page and index
+    fn media_at(&self, bounds: Size, point: Point) -> Option<(ObjectId, usize)> {
+        let (page_id, point) = self.page_point(bounds, point)?;
+        let index = self
+            .page_media
+            .get(&page_id)?
+            .iter()
+            .position(|media| media.rect.contains(point))?;
+        Some((page_id, index))
+    }
+
+    /// Link under a position in canvas pixels
+    fn link_at(&self, bounds: Size, point: Point) -> Option<&pdf::Link> {
+        let (page_id, point) = self.page_point(bounds, point)?;
+        self.page_links
+            .get(&page_id)?
+            .iter()
+            .find(|link| link.rect.contains(point))
+    }
+
+    /// Note under a position in canvas pixels, by page and index
+    fn note_at(&self, bounds: Size, point: Point) -> Option<(ObjectId, usize)> {
+        let (page_id, point) = self.page_point(bounds, point)?;
+        let index = self
+            .notes
+            .get(&page_id)?
+            .iter()
+            .position(|note| note.rect.contains(point))?;
+        Some((page_id, index))
+    }
+
+    /// Page under a position in canvas pixels, and the position in PDF units of the page
+    fn page_point(&self, bounds: Size, point: Point) -> Option<(ObjectId, Point)> {
+        let scale = self.page_scale(bounds);
+        let (position, rect) = self
+            .page_rects(bounds)
+            .into_iter()
+            .find(|(_, rect)| rect.contains(point))?;
+        let page_id = *self.page_ids.get(usize::from(position))?;
+        let page_box = self.page_box(position)?;
+        // From the bottom left of the page as shown to PDF units of the page before rotation
+        let x = (point.x - rect.x) / scale;
+        let y = (rect.y + rect.height - point.y) / scale;
+        let (width, height) = (page_box.rect.width, page_box.rect.height);
+        let (x, y) = match page_box.rotate {
+            90 => (width - y, x),
+            180 => (width - x, height - y),
+            270 => (y, height - x),
+            _ => (x, y),
+        };
+        Some((
+            page_id,
+            Point::new(page_box.rect.x + x, page_box.rect.y + y),
+        ))
+    }
+
+    /// Rulers in PDF units from the top left of the page under the cursor, or the active page
+    fn draw_rulers(&self, frame: &mut canvas::Frame, cursor: Option<Point>, theme: &Theme) {
+        let page_rects = self.page_rects(frame.size());
+        let position = self.position();
+        let Some(page_rect) = page_rects
+            .iter()
+            .find(|(_, rect)| cursor.is_some_and(|cursor| rect.contains(cursor)))
+            .or_else(|| page_rects.iter().find(|(x, _)| *x == position))
+            .map(|(_, rect)| *rect)
+        else {
+            return;
+        };
+        let scale = self.page_scale(frame.size());
+        let step = RULER_STEPS
+            .into_iter()
+            .find(|step| step * scale >= RULER_TICK_MIN)
+            .unwrap_or(RULER_STEPS[RULER_STEPS.len() - 1]);
+        let background = Color::from_rgba(0.0, 0.0, 0.0, 0.7);
+        let (width, height) = (frame.width(), frame.height());
+        frame.fill_rectangle(Point::ORIGIN, Size::new(width, RULER_SIZE), background);
+        frame.fill_rectangle(
+            Point::new(0.0, RULER_SIZE),
+            Size::new(RULER_SIZE, height - RULER_SIZE),
+            background,
+        );
+
+        let stroke = canvas::Stroke::default().with_color(Color::WHITE);
+        // Ticks along one ruler, giving the offset of each from the page origin in pixels
+        let mut ruler = |origin: f32, length: f32, horizontal: bool| {
+            let first = ((RULER_SIZE - origin) / scale / step).floor() as i64;
+            let last = ((length - origin) / scale / step).ceil() as i64;
+            for i in first..=last {
+                let offset = origin + i as f32 * step * scale;
+                if offset < RULER_SIZE {
+                    continue;
+                }
+                let tick = if i % 10 == 0 {
+                    RULER_SIZE
+                } else if i % 5 == 0 {
+                    RULER_SIZE / 2.0
+                } else {
+                    RULER_SIZE / 4.0
+                };
+                let (from, to) = if horizontal {
+                    (
+                        Point::new(offset, RULER_SIZE - tick),
+                        Point::new(offset, RULER_SIZE),
+                    )
+                } else {
+                    (
+                        Point::new(RULER_SIZE - tick, offset),
+                        Point::new(RULER_SIZE, offset),
+                    )
+                };
+                frame.stroke(&canvas::Path::line(from, to), stroke);
+                if i % 10 == 0 {
+                    frame.fill_text(canvas::Text {
+                        content: format!("{}", i as f32 * step),
+                        position: if horizontal {
+                            Point::new(offset + 2.0, 0.0)
+                        } else {
+                            Point::new(0.0, offset + 2.0)
+                        },
+                        color: Color::WHITE,
+                        size: Pixels(RULER_SIZE / 2.0),
+                        ..Default::default()
+                    });
+                }
+            }
+        };
+        ruler(page_rect.x, width, true);
+        ruler(page_rect.y, height, false);
+
+        // Mark the cursor on both rulers
+        if let Some(cursor) = cursor {
+            let stroke = canvas::Stroke::default()
+                .with_color(theme.cosmic().accent_color().into())
+                .with_width(FOCUS_WIDTH);
+            frame.stroke(
+                &canvas::Path::line(Point::new(cursor.x, 0.0), Point::new(cursor.x, RULER_SIZE)),
+                stroke,
+            );
+            frame.stroke(
+                &canvas::Path::line(Point::new(0.0, cursor.y), Point::new(RULER_SIZE, cursor.y)),
+                stroke,
+            );
+        }
+    }
+
+    fn draw_overview(&self, frame: &mut canvas::Frame, selected: u16, dark: bool) {
+        let layout = OverviewLayout::new(frame.size(), self.nav_model.len(), usize::from(selected));
+        frame.fill_rectangle(Point::ORIGIN, frame.size(), Color::BLACK);
+        let pages = self
+            .nav_model
+            .iter()
+            .enumerate()
+            .skip(layout.first)
+            .take(layout.last - layout.first);
+        for (page, entity) in pages {
+            let cell = layout.cell_rect(page);
+            if page == usize::from(selected) {
+                frame.fill_rectangle(cell.position(), cell.size(), Color::from_rgb(0.2, 0.5, 0.9));
+            }
+            frame.fill_text(canvas::Text {
+                content: format!("{}", page + 1),
+                position: Point::new(
+                    cell.center_x(),
+                    cell.y + cell.height - OVERVIEW_PADDING / 2.0,
+                ),
+                color: Color::WHITE,
+                size: Pixels(OVERVIEW_PADDING * 0.75),
+                horizontal_alignment: Horizontal::Center,
+                vertical_alignment: Vertical::Center,
+                ..Default::default()
+            });
+
+            let Some(&page_id) = self.nav_model.data::<ObjectId>(entity) else {
+                continue;
+            };
+            let Some(page_box) = self.page_box(page as u16) else {
+                continue;
+            };
+            let size = page_box.size();
+            let scale = ((cell.width - OVERVIEW_PADDING * 2.0) / size.width)
+                .min((cell.height - OVERVIEW_PADDING * 2.0) / size.height);
+            frame.with_save(|frame| {
+                let center = cell.center();
+                frame.translate(Vector::new(center.x, center.y));
+                frame.scale_nonuniform(Vector::new(1.0, -1.0));
+                frame.scale(scale);
+                self.draw_page(
+                    frame,
+                    self.page_cache.get(&page_id),
+                    Some(page_box),
+                    self.page_color(dark),
+                    scale,
+                );
+            });
+        }
+    }
+
+    /// Short status such as the page number being typed, shown at the bottom of the view,
+    /// with a swatch before the text for colors
+    fn draw_status(&self, frame: &mut canvas::Frame, content: String, swatch: Option<Color>) {
+        //TODO: measure text instead of estimating its width
+        let text_width = content.chars().count() as f32 * STATUS_TEXT_SIZE * 0.6;
+        let swatch_width = if swatch.is_some() {
+            STATUS_TEXT_SIZE + OVERVIEW_PADDING
+        } else {
+            0.0
+        };
+        let size = Size::new(
+            swatch_width + text_width + OVERVIEW_PADDING * 2.0,
+            STATUS_TEXT_SIZE + OVERVIEW_PADDING * 2.0,
+        );
+        let top_left = Point::new(
+            (frame.width() - size.width) / 2.0,
+            frame.height() - OVERVIEW_PADDING - size.height,
+        );
+        frame.fill_rectangle(top_left, size, Color::from_rgba(0.0, 0.0, 0.0, 0.7));
+        if let Some(color) = swatch {
+            frame.fill_rectangle(
+                Point::new(top_left.x + OVERVIEW_PADDING, top_left.y + OVERVIEW_PADDING),
+                Size::new(STATUS_TEXT_SIZE, STATUS_TEXT_SIZE),
+                color,
+            );
+        }
+        frame.fill_text(canvas::Text {
+            content,
+            position: Point::new(
+                top_left.x + OVERVIEW_PADDING + swatch_width + text_width / 2.0,
+                top_left.y + size.height / 2.0,
+            ),
+            color: Color::WHITE,
+            size: Pixels(STATUS_TEXT_SIZE),
+            horizontal_alignment: Horizontal::Center,
+            vertical_alignment: Vertical::Center,
+            ..Default::default()
+        });
+    }
+
+    /// Presentations run fullscreen without the header or nav bar
+    fn set_presentation(&mut self, presentation: Option<Presentation>) -> Task<Message> {
+        let presenting = presentation.is_some();
+        if presenting && self.presentation.is_none() {
+            self.presentation_sidebar = self.core.nav_bar_active();
+        }
+        self.presentation = presentation;
+        if let Some(&page_id) = self.nav_model.active_data::<ObjectId>() {
+            let position = self.position();
+            if let Some(presentation) = &mut self.presentation {
+                // No transition to the page the presentation starts on
+                presentation.page_changed(
+                    position,
+                    pdf::page_duration(&self.flags.doc, page_id),
+                    None,
+                );
+            }
+        }
+        self.core.window.show_headerbar = !presenting;
+        self.core
+            .nav_bar_set_toggled(!presenting && self.presentation_sidebar);
+        match self.core.main_window_id() {
+            Some(id) => window::change_mode(
+                id,
+                if presenting {
+                    window::Mode::Fullscreen
+                } else {
+                    window::Mode::Windowed
+                },
+            ),
+            None => Task::none(),
+        }
+    }
+}
+
+impl canvas::Program<Message, Theme, Renderer> for App {
+    type State = pdf::CanvasState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (Status, Option<Message>) {
+        match event {
+            canvas::Event::Mouse(
+                mouse::Event::ButtonPressed(_) | mouse::Event::WheelScrolled { .. },
+            )
+            | canvas::Event::Keyboard(keyboard::Event::KeyPressed { .. })
+            | canvas::Event::Touch(touch::Event::FingerPressed { .. }) => {
+                self.last_input.set(Some(Instant::now()));
+            }
+            _ => {}
+        }
+
+        if let Some(action) = self.key_bind_capture {
+            if let canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) =
+                &event
+            {
+                let message = match key {
+                    Key::Named(Named::Escape) => Message::KeyBindCapture(None),
+                    _ => match KeyBind::from_event(key, *modifiers) {
+                        Some(key_bind) => Message::KeyBindSet(action, key_bind),
+                        None => return (Status::Captured, None),
+                    },
+                };
+                return (Status::Captured, Some(message));
+            }
+        }
+
+        if let Some(presentation) = &self.presentation {
+            if presentation.kiosk {
+                // Kiosk mode ignores all input except escape, which exits it
+                return match event {
+                    canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+                        key: Key::Named(Named::Escape),
+                        ..
+                    }) => (Status::Captured, Some(Message::Present(false))),
+                    _ => (Status::Captured, None),
+                };
+            }
+
+            if let Some(selected) = presentation.overview {
+                let last = self.nav_model.len().saturating_sub(1) as u16;
+                let layout =
+                    OverviewLayout::new(bounds.size(), self.nav_model.len(), usize::from(selected));
+                let columns = layout.columns as u16;
+                let message = match &event {
+                    canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) => match key {
+                        Key::Named(Named::Escape | Named::Tab) => {
+                            Message::PresentationOverview(None)
+                        }
+                        Key::Named(Named::Enter) => Message::PageGoto(selected),
+                        Key::Named(Named::ArrowLeft) => {
+                            Message::PresentationOverview(Some(selected.saturating_sub(1)))
+                        }
+                        Key::Named(Named::ArrowRight) => {
+                            Message::PresentationOverview(Some((selected + 1).min(last)))
+                        }
+                        Key::Named(Named::ArrowUp) => {
+                            Message::PresentationOverview(Some(selected.saturating_sub(columns)))
+                        }
+                        Key::Named(Named::ArrowDown) => {
+                            Message::PresentationOverview(Some((selected + columns).min(last)))
+                        }
+                        _ => return (Status::Captured, None),
+                    },
+                    canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                        match cursor.position_in(bounds).and_then(|x| layout.page_at(x)) {
+                            Some(page) => Message::PageGoto(page as u16),
+                            None => return (Status::Captured, None),
+                        }
+                    }
+                    _ => return (Status::Captured, None),
+                };
+                return (Status::Captured, Some(message));
+            }
+
+            if presentation.pen {
+                let ink = match event {
+                    canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                        cursor.position_in(bounds).map(Ink::Begin)
+                    }
+                    canvas::Event::Mouse(mouse::Event::CursorMoved { .. })
+                        if presentation.drawing() =>
+                    {
+                        cursor.position_in(bounds).map(Ink::Point)
+                    }
+                    canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                        if presentation.drawing() =>
+                    {
+                        Some(Ink::End)
+                    }
+                    _ => None,
+                };
+                if let Some(ink) = ink {
+                    return (Status::Captured, Some(Message::PresentationInk(ink)));
+                }
+            }
+
+            if presentation.pointer != Pointer::None {
+                if let canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) = event {
+                    // Redraw the pointer
+                    return (Status::Captured, Some(Message::Tick));
+                }
+            }
+
+            if let canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) =
+                &event
+            {
+                if presentation.blank.is_some() {
+                    // Any key restores the slide
+                    return (Status::Captured, Some(Message::PresentationBlank(None)));
+                }
+                let message = match key {
+                    Key::Named(Named::Escape) => Message::Present(false),
+                    Key::Named(Named::Space) if presentation.auto_advancing() => {
+                        Message::PresentationPause
+                    }
+                    Key::Named(
+                        Named::ArrowDown | Named::ArrowRight | Named::PageDown | Named::Space,
+                    ) => Message::PageNext,
+                    Key::Named(
+                        Named::ArrowLeft | Named::ArrowUp | Named::Backspace | Named::PageUp,
+                    ) => Message::PagePrev,
+                    Key::Named(Named::Tab) => Message::PresentationOverview(Some(self.position())),
+                    Key::Character(c) if c.as_str() == "l" => {
+                        Message::PresentationPointer(presentation.pointer.next())
+                    }
+                    Key::Character(c) if c.as_str() == "t" => Message::PresentationTimer,
+                    Key::Character(c) if c.eq_ignore_ascii_case("z") && modifiers.command() => {
+                        Message::PresentationInk(if modifiers.shift() {
+                            Ink::Redo
+                        } else {
+                            Ink::Undo
+                        })
+                    }
+                    Key::Character(c) if c.as_str() == "p" => Message::PresentationInk(Ink::Pen),
+                    Key::Character(c) if c.as_str() == "e" => Message::PresentationInk(Ink::Erase),
+                    Key::Character(c) if c.as_str() == "k" => Message::PresentationInk(Ink::Pin),
+                    Key::Character(c) if c.as_str() == "[" => {
+                        Message::PresentationInk(Ink::Width(1.0 / 1.5))
+                    }
+                    Key::Character(c) if c.as_str() == "]" => {
+                        Message::PresentationInk(Ink::Width(1.5))
+                    }
+                    Key::Character(c) if matches!(c.as_str(), "b" | ".") => {
+                        Message::PresentationBlank(Some(Color::BLACK))
+                    }
+                    Key::Character(c) if matches!(c.as_str(), "w" | ",") => {
+                        Message::PresentationBlank(Some(Color::WHITE))
+                    }
+                    // Number keys select the pen color
+                    Key::Character(c) => match c.as_str().parse::<usize>() {
+                        Ok(i) if (1..=INK_COLORS.len()).contains(&i) => {
+                            Message::PresentationInk(Ink::Color(INK_COLORS[i - 1]))
+                        }
+                        _ => return (Status::Ignored, None),
+                    },
+                    _ => return (Status::Ignored, None),
+                };
+                return (Status::Captured, Some(message));
+            }
+        }
+
+        // Dragging the edge of the page area next to the sidebar resizes it
+        if let Some(offset) = state.sidebar_resize {
+            match event {
+                canvas::Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                    return (
+                        Status::Captured,
+                        Some(Message::SidebarWidth(position.x + offset)),
+                    );
+                }
+                canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                    state.sidebar_resize = None;
+                    return (Status::Captured, None);
+                }
+                _ => {}
+            }
+        }
+        if let canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+            if let Some(position) = cursor
+                .position()
+                .filter(|position| self.on_sidebar_edge(bounds, *position))
+            {
+                state.sidebar_resize = Some(self.flags.state.sidebar_width - position.x);
+                return (Status::Captured, None);
+            }
+        }
+
+        if let Some(anchor) = state.autoscroll {
+            match event {
+                // Any click or key stops autoscrolling
+                canvas::Event::Mouse(mouse::Event::ButtonPressed(_))
+                | canvas::Event::Keyboard(keyboard::Event::KeyPressed { .. }) => {
+                    state.autoscroll = None;
+                    return (Status::Captured, Some(Message::Autoscroll(None)));
+                }
+                canvas::Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                    // Scroll toward the cursor, the frame is flipped so x is negated instead of y
+                    let velocity = Autoscroll::velocity(position - anchor);
+                    let scale = self.page_scale(bounds.size());
+                    return (
+                        Status::Captured,
+                        Some(Message::Autoscroll(Some(Vector::new(
+                            -velocity.x / scale,
+                            velocity.y / scale,
+                        )))),
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        if self.note_tool {
+            match event {
+                canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                    if let Some((page_id, point)) = cursor
+                        .position_in(bounds)
+                        .and_then(|position| self.page_point(bounds.size(), position))
+                    {
+                        return (Status::Captured, Some(Message::NoteAdd(page_id, point)));
+                    }
+                }
+                canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: Key::Named(Named::Escape),
+                    ..
+                }) => return (Status::Captured, Some(Message::NoteTool(false))),
+                _ => {}
+            }
+        }
+
+        if self.eyedropper {
+            match event {
+                canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                    if let Some(position) = cursor.position_over(bounds) {
+                        return (Status::Captured, Some(Message::PickColor(position)));
+                    }
+                }
+                canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: Key::Named(Named::Escape),
+                    ..
+                }) => return (Status::Captured, Some(Message::Eyedropper(false))),
+                _ => {}
+            }
+        }
+
+        // Arrow keys move through the sidebar while it has the focus
+        if self.pane_focus == Some(Pane::Sidebar) {
+            if let canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+                key: Key::Named(named),
+                modifiers,
+                ..
+            }) = &event
+            {
+                if modifiers.is_empty() {
+                    if let Some(model) = self.nav_model() {
+                        let last = model.len().saturating_sub(1);
+                        let active = model.position(model.active()).map_or(0, usize::from);
+                        let position = match named {
+                            Named::ArrowUp => Some(active.saturating_sub(1)),
+                            Named::ArrowDown => Some((active + 1).min(last)),
+                            Named::Home => Some(0),
+                            Named::End => Some(last),
+                            _ => None,
+                        };
+                        if let Some(position) = position {
+                            return (Status::Captured, Some(Message::SidebarSelect(position)));
+                        }
+                    }
+                    if *named == Named::Escape {
+                        return (Status::Captured, Some(Message::FocusPane));
+                    }
+                }
+            }
+        }
+
+        // Keys typed into the page field or a note are left to them
+        if self.page_input.is_some() || self.note_edit.is_some() {
+            if let canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) =
+                &event
+            {
+                if editing_key(key, *modifiers) || *key == Key::Named(Named::Enter) {
+                    return (Status::Ignored, None);
+                }
+            }
+        }
+
+        // Keys typed into the search field are left to it, apart from Enter going through matches
+        if self.search.is_some() {
+            if let canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) =
+                &event
+            {
+                if *key == Key::Named(Named::Enter) {
+                    return (
+                        Status::Captured,
+                        Some(if modifiers.shift() {
+                            Message::SearchPrev
+                        } else {
+                            Message::SearchNext
+                        }),
+                    );
+                }
+                if editing_key(key, *modifiers) {
+                    return (Status::Ignored, None);
+                }
+            }
+        }
+
+        // Typed page numbers take digits and editing keys that are not bound to actions
+        if let canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = &event
+        {
+            if modifiers.is_empty()
+                && KeyBind::from_event(key, *modifiers)
+                    .and_then(|key_bind| self.flags.config.action(&key_bind))
+                    .is_none()
+            {
+                let typed = self.page_number.as_ref().map(|(typed, _)| typed.as_str());
+                let message = match (key, typed) {
+                    (Key::Character(c), typed) if c.parse::<u16>().is_ok() => Some(
+                        Message::PageNumber(Some(format!("{}{}", typed.unwrap_or(""), c))),
+                    ),
+                    (Key::Named(Named::Backspace), Some(typed)) => {
+                        let mut chars = typed.chars();
+                        chars.next_back();
+                        Some(Message::PageNumber(
+                            Some(chars.as_str().to_string()).filter(|x| !x.is_empty()),
+                        ))
+                    }
+                    (Key::Named(Named::Enter), Some(typed)) => {
+                        // Page numbers past the end go to the last page
+                        match typed.parse::<usize>().ok().and_then(|x| x.checked_sub(1)) {
+                            Some(position) => Some(Message::PageGoto(
+                                position.min(self.nav_model.len().saturating_sub(1)) as u16,
+                            )),
+                            None => Some(Message::PageNumber(None)),
+                        }
+                    }
+                    (Key::Named(Named::Escape), Some(_)) => Some(Message::PageNumber(None)),
+                    _ => None,
+                };
+                if let Some(message) = message {
+                    return (Status::Captured, Some(message));
+                }
+            }
+        }
+
+        match event {
+            // Space pans while held, and runs its action when released without panning
+            canvas::Event::Keyboard(keyboard::Event::KeyPressed {
+                key: Key::Named(Named::Space),
+                modifiers,
+                ..
+            }) if modifiers.is_empty() => {
+                if !state.space {
+                    state.space = true;
+                    state.space_panned = false;
+                }
+                (Status::Captured, None)
+            }
+            canvas::Event::Keyboard(keyboard::Event::KeyReleased {
+                key: Key::Named(Named::Space),
+                ..
+            }) => {
+                if !std::mem::take(&mut state.space) || state.space_panned {
+                    return (Status::Ignored, None);
+                }
+                match self.flags.config.action(&KeyBind::new(&[], "Space")) {
+                    Some(action) => (
+                        Status::Captured,
+                        Some(self.action_message(action, bounds.size())),
+                    ),
+                    None => (Status::Ignored, None),
+                }
+            }
+            canvas::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                let Some(action) = KeyBind::from_event(&key, modifiers)
+                    .and_then(|key_bind| self.flags.config.action(&key_bind))
+                else {
+                    return (Status::Ignored, None);
+                };
+                (
+                    Status::Captured,
+                    Some(self.action_message(action, bounds.size())),
+                )
+            }
+            canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = modifiers;
+                (Status::Captured, None)
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(button)) => {
+                let Some(position) = cursor.position_in(bounds) else {
+                    return (Status::Ignored, None);
+                };
+                // Middle button or space with left button drags the page
+                if button == mouse::Button::Middle || (button == mouse::Button::Left && state.space)
+                {
+                    state.space_panned |= state.space;
+                    state.drag = Some(pdf::Drag {
+                        button,
+                        position,
+                        moved: false,
+                    });
+                    return (Status::Captured, None);
+                }
+                if button == mouse::Button::Left {
+                    if let Some(note) = self.note_at(bounds.size(), position) {
+                        return (Status::Captured, Some(Message::NoteEdit(Some(note))));
+                    }
+                    if let Some((page_id, index)) = self.media_at(bounds.size(), position) {
+                        return (Status::Captured, Some(Message::PlayMedia(page_id, index)));
+                    }
+                    if let Some(link) = self.link_at(bounds.size(), position) {
+                        return (
+                            Status::Captured,
+                            Some(Message::FollowLink(link.target.clone())),
+                        );
+                    }
+                }
+                let mouse_bind = match button {
+                    mouse::Button::Left => {
+                        let click = Click::new(position, button, state.last_click);
+                        state.last_click = Some(click);
+                        match click.kind() {
+                            click::Kind::Double => MouseBind::DoubleClick,
+                            _ => return (Status::Ignored, None),
+                        }
+                    }
+                    mouse::Button::Back => MouseBind::Back,
+                    mouse::Button::Forward => MouseBind::Forward,
+                    _ => return (Status::Ignored, None),
+                };
+                match self.flags.config.mouse_binds.get(&mouse_bind) {
+                    Some(action) => (
+                        Status::Captured,
+                        Some(self.action_message(*action, bounds.size())),
+                    ),
+                    None => (Status::Ignored, None),
+                }
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                let Some(drag) = &mut state.drag else {
+                    return (Status::Ignored, None);
+                };
+                let delta = position - drag.position;
+                drag.position = position;
+                drag.moved = true;
+                // Keep the page under the cursor, the frame is flipped so y is negated
+                let scale = self.page_scale(bounds.size());
+                (
+                    Status::Captured,
+                    Some(Message::Pan(Vector::new(delta.x / scale, -delta.y / scale))),
+                )
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(button)) => {
+                match state.drag.take() {
+                    Some(drag) if drag.button == button => {
+                        // A middle-click that did not drag runs its action, or autoscrolls if it has none
+                        if button == mouse::Button::Middle && !drag.moved {
+                            if let Some(action) =
+                                self.flags.config.mouse_binds.get(&MouseBind::MiddleClick)
+                            {
+                                return (
+                                    Status::Captured,
+                                    Some(self.action_message(*action, bounds.size())),
+                                );
+                            }
+                            state.autoscroll = cursor.position();
+                            return (
+                                Status::Captured,
+                                Some(Message::Autoscroll(Some(Vector::new(0.0, 0.0)))),
+                            );
+                        }
+                        (Status::Captured, None)
+                    }
+                    drag => {
+                        state.drag = drag;
+                        (Status::Ignored, None)
+                    }
+                }
+            }
+            canvas::Event::Touch(touch::Event::FingerPressed { id, position }) => {
+                if state.two_finger.is_some() || !bounds.contains(position) {
+                    return (Status::Ignored, None);
+                }
+                if let Some(touch) = state.touch.take() {
+                    state.two_finger =
+                        Some(TwoFinger::new((touch.id, touch.position), (id, position)));
+                    // Put back a page being swiped
+                    let message = touch.swipe.then_some(Message::SwipeEnd(0.0, bounds.width));
+                    return (Status::Captured, message);
+                }
+                state.touch = Some(Touch::new(id, position, self.fits_width(bounds.size())));
+                // Touching the page stops it from moving
+                (Status::Captured, Some(Message::Fling(None)))
+            }
+            canvas::Event::Touch(touch::Event::FingerMoved { id, position })
+                if state.two_finger.is_some() =>
+            {
+                match &mut state.two_finger {
+                    Some(two_finger) if two_finger.contains(id) => {
+                        let (factor, delta) = two_finger.pinched(id, position);
+                        let scale = self.page_scale(bounds.size());
+                        let center = two_finger.center() - bounds.position();
+                        let pan = Vector::new(delta.x / scale, -delta.y / scale)
+                            + self.zoom_anchor(bounds.size(), Point::ORIGIN + center, factor);
+                        (Status::Captured, Some(Message::ZoomPan(factor, pan)))
+                    }
+                    _ => (Status::Ignored, None),
+                }
+            }
+            canvas::Event::Touch(
+                touch::Event::FingerLifted { id, .. } | touch::Event::FingerLost { id, .. },
+            ) if state
+                .two_finger
+                .as_ref()
+                .is_some_and(|two_finger| two_finger.contains(id)) =>
+            {
+                // Lifting either finger ends the gesture, rotating by the nearest quarter turn
+                let rotation = state
+                    .two_finger
+                    .take()
+                    .map_or(0, |two_finger| two_finger.snapped_rotation());
+                let message = (rotation != 0).then_some(Message::Rotate(rotation));
+                (Status::Captured, message)
+            }
+            canvas::Event::Touch(touch::Event::FingerMoved { id, position }) => {
+                let Some(touch) = state.touch.as_mut().filter(|touch| touch.id == id) else {
+                    return (Status::Ignored, None);
+                };
+                let delta = touch.moved(position);
+                let message = if touch.swipe {
+                    Message::SwipeDrag(position.x - touch.start.x)
+                } else {
+                    let scale = self.page_scale(bounds.size());
+                    Message::Pan(Vector::new(delta.x / scale, -delta.y / scale))
+                };
+                (Status::Captured, Some(message))
+            }
+            canvas::Event::Touch(
+                touch::Event::FingerLifted { id, position }
+                | touch::Event::FingerLost { id, position },
+            ) => match state.touch.take() {
+                Some(touch) if touch.id == id => {
+                    let message = if touch.swipe {
+                        Some(Message::SwipeEnd(position.x - touch.start.x, bounds.width))
+                    } else if touch.moved.elapsed() < gesture::FLING_REST {
+                        // Keep panning with the velocity of the finger
+                        let scale = self.page_scale(bounds.size());
+                        Some(Message::Fling(Some(Vector::new(
+                            touch.velocity.x / scale,
+                            -touch.velocity.y / scale,
+                        ))))
+                    } else {
+                        None
+                    };
+                    (Status::Captured, message)
+                }
+                touch => {
+                    state.touch = touch;
+                    (Status::Ignored, None)
+                }
+            },
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                if let Some(pos) = cursor.position_in(bounds) {
+                    let (x, y) = match delta {
+                        mouse::ScrollDelta::Lines { x, y } => {
+                            //TODO: best value to translate line scroll to pixels
+                            (x * 16.0, y * 16.0)
+                        }
+                        mouse::ScrollDelta::Pixels { x, y } => (x, y),
+                    };
+                    if state.modifiers.contains(keyboard::Modifiers::SHIFT) {
+                        // Vertical scrolling pans sideways
+                        return (
+                            Status::Captured,
+                            Some(Message::Pan(Vector::new(x + y, 0.0))),
+                        );
+                    }
+                    let wheel = match (
+                        self.flags.config.wheel,
+                        state.modifiers.contains(keyboard::Modifiers::CTRL),
+                    ) {
+                        (Wheel::Zoom, true) => Wheel::Scroll,
+                        (_, true) => Wheel::Zoom,
+                        (wheel, false) => wheel,
+                    };
+                    let message = match wheel {
+                        Wheel::Scroll => Message::Pan(Vector::new(x, -y)),
+                        Wheel::Pages => {
+                            // Each line turns a page, while pixels add up to turn one
+                            state.wheel_pages += match delta {
+                                mouse::ScrollDelta::Lines { y, .. } => y.signum() * WHEEL_PAGE,
+                                mouse::ScrollDelta::Pixels { y, .. } => y,
+                            };
+                            if state.wheel_pages <= -WHEEL_PAGE {
+                                state.wheel_pages = 0.0;
+                                Message::PageNext
+                            } else if state.wheel_pages >= WHEEL_PAGE {
+                                state.wheel_pages = 0.0;
+                                Message::PagePrev
+                            } else {
+                                return (Status::Captured, None);
+                            }
+                        }
+                        Wheel::Zoom => {
+                            let step = 1.0 + f32::from(self.flags.config.zoom_step) / 100.0;
+                            let factor = step.powf(y / 16.0);
+                            Message::ZoomPan(factor, self.zoom_anchor(bounds.size(), pos, factor))
+                        }
+                    };
+                    (Status::Captured, Some(message))
+                } else {
+                    (Status::Ignored, None)
+                }
+            }
+            _ => (Status::Ignored, None),
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> mouse::Interaction {
+        let over_link = cursor.position_in(bounds).is_some_and(|point| {
+            self.link_at(bounds.size(), point).is_some()
+                || self.note_at(bounds.size(), point).is_some()
+        });
+        let on_sidebar_edge = cursor
+            .position()
+            .is_some_and(|position| self.on_sidebar_edge(bounds, position));
+        if state.sidebar_resize.is_some() || on_sidebar_edge {
+            return mouse::Interaction::ResizingHorizontally;
+        }
+        match &self.presentation {
+            Some(presentation) if presentation.pen => mouse::Interaction::Crosshair,
+            Some(_) => mouse::Interaction::default(),
+            None if self.eyedropper || self.note_tool => mouse::Interaction::Crosshair,
+            None if state.autoscroll.is_some() => mouse::Interaction::Move,
+            None if state.drag.is_some() => mouse::Interaction::Grabbing,
+            None if state.space => mouse::Interaction::Grab,
+            None if over_link => mouse::Interaction::Pointer,
+            None => mouse::Interaction::default(),
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> Vec<widget::canvas::Geometry> {
+        let dark = theme.cosmic().is_dark;
+        if state.dark.replace(Some(dark)) != Some(dark) {
+            self.canvas_cache.clear();
+        }
+        self.canvas_size.set(bounds.size());
+        let geo = self.canvas_cache.draw(renderer, bounds.size(), |frame| {
+            if let Some(selected) = self.presentation.as_ref().and_then(|x| x.overview) {
+                self.draw_overview(frame, selected, dark);
+                return;
+            }
+            if let Some(color) = self.flags.config.canvas_color.get(dark) {
+                frame.fill_rectangle(Point::ORIGIN, frame.size(), color);
+            }
+            let background = self.page_color(dark);
+
+            let scale = self.page_scale(frame.size())
+                * self
+                    .zoom_ease
+                    .as_ref()
+                    .map_or(1.0, |ease| ease.value().exp());
+            // Presentations show the page centered, as the pan may be for another layout
+            let translate = if self.presentation.is_some() {
+                Vector::new(0.0, 0.0)
+            } else {
+                self.view.translate
+            };
+            // Slide the pages with a swipe
+            if let Some(swipe) = &self.swipe {
+                frame.translate(Vector::new(swipe.offset(), 0.0));
+            }
+
+            // PDF's origin is the bottom left while the canvas origin is the top right, so flip it
+            {
+                frame.translate(Vector::new(0.0, frame.size().height));
+                frame.scale_nonuniform(Vector::new(1.0, -1.0));
+            }
+
+            // Apply zoom and pan
+            //TODO: can user's pan and zoom be applied without having to regenerate entire frame?
+            {
+                // Move to center
+                frame.translate(Vector::new(
+                    frame.size().width / 2.0,
+                    frame.size().height / 2.0,
+                ));
+                // Zoom
+                frame.scale(scale);
+                // Apply pan
+                frame.translate(translate);
+            }
+
+            for (position, center) in self.visible_pages(frame.size(), scale) {
+                let (Some(&page_id), Some(size)) = (
+                    self.page_ids.get(usize::from(position)),
+                    self.page_sizes.get(usize::from(position)),
+                ) else {
+                    continue;
+                };
+                // Facing pages go from right to left
+                let center = if self.right_to_left() {
+                    Vector::new(-center.x, center.y)
+                } else {
+                    center
+                };
+                let page_box = self.page_box(position);
+                let size = page_box.map_or(*size, |page_box| page_box.size());
+                // Compared pages go to the right, with words that differ highlighted on both
+                let compare = self
+                    .compare_offsets(position, size)
+                    .filter(|_| self.presentation.is_none());
+                let differences = compare.and_then(|_| self.text_differences(position));
+                let center = match compare {
+                    Some((offset, compare_offset)) => {
+                        let compare_id =
+                            self.compare_ids[self.compare_index(position).unwrap_or_default()];
+                        let compare_box = self
+                            .compare_doc()
+                            .and_then(|compare| pdf::page_box(compare, compare_id));
+                        let compare_center = center + Vector::new(compare_offset, 0.0);
+                        frame.with_save(|frame| {
+                            frame.translate(compare_center);
+                            frame.scale(self.compare_zoom);
+                            self.draw_page(
+                                frame,
+                                self.compare_cache.get(&compare_id),
+                                compare_box,
+                                background,
+                                scale * self.compare_zoom,
+                            );
+                            if let Some(differences) = &differences {
+                                for rect in differences[1].iter() {
+                                    frame.fill_rectangle(
+                                        rect.position(),
+                                        rect.size(),
+                                        DIFFERENCE_HIGHLIGHT,
+                                    );
+                                }
+                            }
+                        });
+                        center + Vector::new(offset, 0.0)
+                    }
+                    None => center,
+                };
+                let center = if self.flags.config.sharp_pixels {
+                    self.snap_to_pixels(center, size, translate, scale, frame.size())
+                } else {
+                    center
+                };
+                frame.with_save(|frame| {
+                    frame.translate(center);
+                    if self.presentation.is_none() {
+                        self.draw_page_edge(frame, size, scale, dark);
+                    }
+                    self.draw_page(
+                        frame,
+                        self.page_cache.get(&page_id),
+                        page_box,
+                        background,
+                        scale,
+                    );
+                    if let Some(media) = self.page_media.get(&page_id) {
+                        draw_media(frame, media, scale);
+                    }
+                    if let Some(notes) = self.notes.get(&page_id) {
+                        draw_notes(frame, notes);
+                    }
+                    if let Some(differences) = &differences {
+                        for rect in differences[0].iter() {
+                            frame.fill_rectangle(
+                                rect.position(),
+                                rect.size(),
+                                DIFFERENCE_HIGHLIGHT,
+                            );
+                        }
+                    }
+                    let matches = self.search_matches(page_id);
+                    let selected = self.selected_match(position, matches.len());
+                    for (i, rect) in matches.into_iter().enumerate() {
+                        let color = if selected == Some(i) {
+                            SEARCH_SELECTED
+                        } else {
+                            SEARCH_HIGHLIGHT
+                        };
+                        frame.fill_rectangle(rect.position(), rect.size(), color);
+                    }
+                });
+                // Preview cropping while exporting
+                if let Some(crop) = self.export_crop() {
+                    if crop.applies_to(page_id) {
+                        let [top, right, bottom, left] = crop.margins;
+                        let (x, y) = (-size.width / 2.0, -size.height / 2.0);
+                        frame.with_save(|frame| {
+                            frame.translate(center);
+                            frame.fill_rectangle(
+                                Point::new(x, y + size.height - top),
+                                Size::new(size.width, top),
+                                CROP_SHADE,
+                            );
+                            frame.fill_rectangle(
+                                Point::new(x, y),
+                                Size::new(size.width, bottom),
+                                CROP_SHADE,
+                            );
+                            let middle = (size.height - top - bottom).max(0.0);
+                            frame.fill_rectangle(
+                                Point::new(x, y + bottom),
+                                Size::new(left, middle),
+                                CROP_SHADE,
+                            );
+                            frame.fill_rectangle(
+                                Point::new(x + size.width - right, y + bottom),
+                                Size::new(right, middle),
+                                CROP_SHADE,
+                            );
+                        });
+                    }
+                }
+                if self.flags.config.grid && self.presentation.is_none() {
+                    frame.with_save(|frame| {
+                        frame.translate(center);
+                        self.draw_grid(frame, size, scale);
+                    });
+                }
+            }
+        });
+        let mut geometry = vec![geo];
+        if let Some(fade) = &self.fade {
+            // Fade new pages in from the background
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            let mut color = self
+                .flags
+                .config
+                .canvas_color
+                .get(dark)
+                .unwrap_or_else(|| theme.cosmic().bg_color().into());
+            color.a = fade.value();
+            frame.fill_rectangle(Point::ORIGIN, bounds.size(), color);
+            geometry.push(frame.into_geometry());
+        }
+        if self.flags.config.rulers && self.presentation.is_none() {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            self.draw_rulers(&mut frame, cursor.position_in(bounds), theme);
+            geometry.push(frame.into_geometry());
+        }
+        // Show that the page has the keyboard focus after moving it with F6
+        let focus_rect = match self.pane_focus {
+            Some(Pane::Page) => Some(Rectangle::new(Point::ORIGIN, bounds.size())),
+            Some(Pane::Compare) => self.compare_rect(bounds.size()),
+            _ => None,
+        };
+        if let Some(rect) = focus_rect {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            frame.stroke(
+                &canvas::Path::rectangle(
+                    Point::new(rect.x + FOCUS_WIDTH / 2.0, rect.y + FOCUS_WIDTH / 2.0),
+                    Size::new(rect.width - FOCUS_WIDTH, rect.height - FOCUS_WIDTH),
+                ),
+                canvas::Stroke::default()
+                    .with_color(theme.cosmic().accent_color().into())
+                    .with_width(FOCUS_WIDTH),
+            );
+            geometry.push(frame.into_geometry());
+        }
+        if let Some(anchor) = state.autoscroll {
+            // Mark where autoscrolling started
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            let center = anchor - bounds.position();
+            let center = Point::new(center.x, center.y);
+            frame.fill(
+                &canvas::Path::circle(center, AUTOSCROLL_MARKER_RADIUS),
+                Color::from_rgba(0.0, 0.0, 0.0, 0.5),
+            );
+            frame.stroke(
+                &canvas::Path::circle(center, AUTOSCROLL_MARKER_RADIUS),
+                canvas::Stroke::default().with_color(Color::WHITE),
+            );
+            geometry.push(frame.into_geometry());
+        }
+        if let Some((typed, _)) = &self.page_number {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            let content = fl!(
+                "go-to-page",
+                page = typed.as_str(),
+                pages = self.nav_model.len()
+            );
+            self.draw_status(&mut frame, content, None);
+            geometry.push(frame.into_geometry());
+        } else if let Some(link) = cursor
+            .position_in(bounds)
+            .filter(|_| self.presentation.is_none())
+            .and_then(|point| self.link_at(bounds.size(), point))
+        {
+            // Show where a link goes while hovering it
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            let content = match &link.target {
+                pdf::LinkTarget::Page(page_id, _) => {
+                    let page = self
+                        .page_ids
+                        .iter()
+                        .position(|x| x == page_id)
+                        .map_or(0, |position| position + 1);
+                    fl!("link-page", page = page)
+                }
+                pdf::LinkTarget::Uri(uri) => uri.clone(),
+            };
+            self.draw_status(&mut frame, content, None);
+            geometry.push(frame.into_geometry());
+        } else if let Some((color, _)) = self.picked_color {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            let [red, green, blue, _] = color.into_rgba8();
+            let content = fl!(
+                "picked-color",
+                hex = format_color(color),
+                red = red,
+                green = green,
+                blue = blue
+            );
+            self.draw_status(&mut frame, content, Some(color));
+            geometry.push(frame.into_geometry());
+        } else if let Some((content, _)) = &self.status {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            self.draw_status(&mut frame, content.clone(), None);
+            geometry.push(frame.into_geometry());
+        }
+        if let Some(presentation) = self.presentation.as_ref().filter(|x| x.overview.is_none()) {
+            let mut frame = canvas::Frame::new(renderer, bounds.size());
+            presentation.draw_overlay(&mut frame, cursor.position_in(bounds));
+            geometry.push(frame.into_geometry());
+        }
+        geometry
+    }
+}
+
+impl Application for App {
+    type Executor = executor::Default;
+    type Flags = Flags;
+    type Message = Message;
+    const APP_ID: &'static str = "com.system76.CosmicReader";
+
+    fn core(&self) -> &Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut Core {
+        &mut self.core
+    }
+
+    fn init(core: Core, flags: Self::Flags) -> (Self, Task<Message>) {
+        let mut nav_model = Model::default();
+        for (i, page_id) in flags.doc.page_iter().enumerate() {
+            nav_model
+                .insert()
+                .text(fl!("page-number", number = i + 1))
+                .data::<ObjectId>(page_id);
+        }
+        nav_model.activate_position(0);
+
+        let page_ids: Vec<ObjectId> = flags.doc.page_iter().collect();
+        let compare_ids: Vec<ObjectId> = flags
+            .compare
+            .as_ref()
+            .map(|compare| compare.page_iter().collect())
+            .unwrap_or_default();
+        let page_sizes = page_ids
+            .iter()
+            .map(|&page_id| match pdf::page_box(&flags.doc, page_id) {
+                Some(page_box) => page_box.size(),
+                // US Letter is used when a page has no size
+                None => Size::new(612.0, 792.0),
+            })
+            .collect();
+
+        let mut outline_model = Model::default();
+        let mut sections = Vec::new();
+        match flags.doc.get_toc() {
+            Ok(toc) => {
+                for entry in toc.toc {
+                    // Outline page numbers start at 1
+                    let Some(position) = entry.page.checked_sub(1) else {
+                        continue;
+                    };
+                    outline_model
+                        .insert()
+                        .text(format!(
+                            "{}{}",
+                            "  ".repeat(entry.level.saturating_sub(1)),
+                            entry.title
+                        ))
+                        .data::<u16>(position as u16);
+                    sections.push((entry.level, entry.title, position as u16));
+                }
+            }
+            Err(err) => {
+                log::info!("failed to load outline: {}", err);
+            }
+        }
+        // Tagged documents can be navigated by their structure, which names the kind of each
+        // element so screen readers announce it, and by their headings if they have no outline
+        let mut structure_model = Model::default();
+        let headings_outline = outline_model.len() == 0;
+        let mut level = 0;
+        for item in structure::structure(&flags.doc) {
+            let Some(position) = item
+                .page_id
+                .and_then(|page_id| page_ids.iter().position(|x| *x == page_id))
+            else {
+                continue;
+            };
+            let title = item.text.trim();
+            // Lists and tables go under the heading before them
+            let (indent, text) = match item.kind {
+                StructKind::Heading(heading) => {
+                    level = usize::from(heading);
+                    if headings_outline {
+                        sections.push((level, title.to_string(), position as u16));
+                        outline_model
+                            .insert()
+                            .text(format!("{}{}", "  ".repeat(level - 1), title))
+                            .data::<u16>(position as u16);
+                    }
+                    (level - 1, fl!("heading", level = heading, title = title))
+                }
+                StructKind::List => (level, fl!("list", title = title)),
+                StructKind::Table => (level, fl!("table", title = title)),
+            };
+            structure_model
+                .insert()
+                .text(format!("{}{}", "  ".repeat(indent), text.trim()))
+                .data::<u16>(position as u16);
+        }
+
+        let document_view = flags
+            .state
+            .documents
+            .get(&flags.key)
+            .copied()
+            .unwrap_or_else(|| {
+                // Images may be far larger than the window, so they start fitted to it
+                if picture::is_picture(&flags.path) {
+                    config::DocumentView {
+                        fit: pdf::Fit::Page,
+                        ..flags.config.view
+                    }
+                } else {
+                    flags.config.view
+                }
+            });
+
+        let zoom_presets_input = flags
+            .config
+            .zoom_presets
+            .iter()
+            .map(|percent| percent.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let paper_color_input = flags.config.paper_color.clone();
+        let document_rtl = pdf::right_to_left(&flags.doc);
+        let file_len = fs::metadata(&flags.path).map_or(0, |metadata| metadata.len());
+        let signatures = signature::signatures(&flags.doc, file_len);
+        let layers = layer::layers(&flags.doc);
+        let screen_dpi_input = flags
+            .config
+            .screen_dpi
+            .map(|dpi| dpi.to_string())
+            .unwrap_or_default();
+
+        let mut app = Self {
+            core,
+            flags,
+            canvas_cache: canvas::Cache::new(),
+            nav_model,
+            outline_model,
+            structure_model,
+            bookmarks_model: Model::default(),
+            annotations_model: Model::default(),
+            sections,
+            page_ids,
+            page_sizes,
+            rotations: HashMap::new(),
+            swipe: None,
+            fling: None,
+            fade: None,
+            zoom_ease: None,
+            autoscroll: None,
+            reading_scroll: None,
+            view_lock: None,
+            last_input: Cell::new(None),
+            warming: None,
+            canvas_size: Cell::new(Size::ZERO),
+            pages_loading: RefCell::new(HashSet::new()),
+            on_battery: power::on_battery(),
+            page_number: None,
+            page_input: None,
+            eyedropper: false,
+            notes: HashMap::new(),
+            notes_changed: HashSet::new(),
+            history: History::default(),
+            quit_prompt: None,
+            quit_after_save: None,
+            note_tool: false,
+            note_edit: None,
+            picked_color: None,
+            status: None,
+            state_changed: None,
+            search: None,
+            search_id: widget::text_input::Id::unique(),
+            search_index: None,
+            search_results: Vec::new(),
+            search_match: None,
+            page_cache: HashMap::new(),
+            page_media: HashMap::new(),
+            page_links: HashMap::new(),
+            page_content: HashMap::new(),
+            split_view: !compare_ids.is_empty(),
+            compare_ids,
+            compare_offset: 0,
+            compare_sync: true,
+            compare_position: 0,
+            compare_cache: HashMap::new(),
+            compare_loading: RefCell::new(HashSet::new()),
+            compare_generation: 0,
+            pane_zoom_lock: true,
+            compare_zoom: 1.0,
+            compare_differences: RefCell::new(HashMap::new()),
+            presentation: None,
+            presentation_sidebar: false,
+            view: pdf::PageView::new(document_view.layout, document_view.fit, document_view.scale),
+            context_page: ContextPage::Settings,
+            statistics: None,
+            conformance: None,
+            signatures,
+            hidden_layers: Arc::new(layer::hidden(&layers)),
+            layers,
+            stamp: export::Stamp::default(),
+            stamp_image_input: String::new(),
+            stamp_position_names: StampPosition::ALL
+                .iter()
+                .map(|position| position.name())
+                .collect(),
+            export_status: None,
+            image_format: raster::Format::default(),
+            image_format_names: raster::Format::ALL
+                .iter()
+                .map(|format| format.name())
+                .collect(),
+            image_dpi_input: raster::DPI.to_string(),
+            image_dpi: raster::DPI,
+            image_pages_input: String::new(),
+            crop_inputs: Default::default(),
+            crop_margins: [0.0; 4],
+            crop_current_page: false,
+            action_names: [fl!("none")]
+                .into_iter()
+                .chain(Action::ALL.iter().map(|action| action.name()))
+                .collect(),
+            default_zoom_names: Vec::new(),
+            layout_names: Layout::ALL.iter().map(|layout| layout.name()).collect(),
+            wheel_names: Wheel::ALL.iter().map(|wheel| wheel.name()).collect(),
+            page_animation_names: PageAnimation::ALL
+                .iter()
+                .map(|animation| animation.name())
+                .collect(),
+            page_edge_names: PageEdge::ALL.iter().map(|edge| edge.name()).collect(),
+            page_gap_names: PAGE_GAPS
+                .iter()
+                .map(|&points| fl!("page-gap-points", points = points))
+                .collect(),
+            reflow_font_size_names: REFLOW_FONT_SIZES
+                .iter()
+                .map(|&points| fl!("reflow-points", points = points))
+                .collect(),
+            reflow_margin_names: REFLOW_MARGINS
+                .iter()
+                .map(|&points| fl!("reflow-points", points = points))
+                .collect(),
+            reflow_width_names: REFLOW_WIDTHS
+                .iter()
+                .map(|&points| fl!("reflow-points", points = points))
+                .collect(),
+            direction_names: Direction::ALL
+                .iter()
+                .map(|direction| direction.name())
+                .collect(),
+            document_rtl,
+            zoom_step_names: ZOOM_STEPS
+                .iter()
+                .map(|&percent| fl!("zoom-percent", percent = percent))
+                .collect(),
+            reading_speed_names: READING_SPEEDS
+                .iter()
+                .map(|&points| fl!("reading-speed-points", points = points))
+                .collect(),
+            grid_spacing_names: GRID_SPACINGS
+                .iter()
+                .map(|&points| fl!("grid-spacing-points", points = points))
+                .collect(),
+            zoom_presets_input,
+            key_bind_capture: None,
+            screen_dpi_input,
+            paper_color_input,
+            color_inputs: HashMap::new(),
+            scale_factor: 1.0,
+            pane_focus: None,
+            default_handler: None,
+            default_handler_dialog: false,
+            resume_prompt: None,
+            resume_names: Resume::ALL.iter().map(|resume| resume.name()).collect(),
+            print_fallback: None,
+        };
+        app.core.nav_bar_set_toggled(app.flags.state.show_sidebar);
+        app.update_bookmarks_model();
+        app.update_annotations_model();
+        app.update_default_zoom_names();
+        app.add_recent();
+        if let Some(page) = app.flags.page {
+            let last = app.nav_model.len().saturating_sub(1) as u16;
+            app.nav_model
+                .activate_position(page.saturating_sub(1).min(last));
+        } else if let Some(last_read) = app.last_read() {
+            match app.flags.config.resume {
+                Resume::Always => app.resume(last_read),
+                Resume::Ask => app.resume_prompt = Some(last_read),
+                Resume::Never => {}
+            }
+        }
+        let mut tasks = vec![app.page_changed()];
+        if let Some(id) = app.core.main_window_id() {
+            tasks.push(
+                window::get_scale_factor(id).map(|scale_factor| {
+                    cosmic::app::Message::App(Message::ScaleFactor(scale_factor))
+                }),
+            );
+        }
+        if !app.flags.kiosk {
+            tasks.push(Task::perform(
+                // xdg-mime is a shell script that may take a while, so it runs off the executor
+                async move {
+                    tokio::task::spawn_blocking(|| mime::is_default(&App::desktop_id()))
+                        .await
+                        .ok()
+                        .flatten()
+                },
+                |is_default| cosmic::app::Message::App(Message::DefaultHandler(is_default)),
+            ));
+        }
+        if app.flags.kiosk {
+            let presentation = Presentation::new(
+                true,
+                app.flags.duration,
+                // Kiosks always advance, every 10 seconds by default
+                Some(app.flags.interval.unwrap_or(Duration::from_secs(10))),
+                app.transitions(),
+            );
+            tasks.push(app.set_presentation(Some(presentation)));
+        }
+        (app, Task::batch(tasks))
+    }
+
+    fn context_drawer(&self) -> Option<Element<Message>> {
+        if !self.core.window.show_context {
+            return None;
+        }
+        Some(match self.context_page {
+            ContextPage::About => self.about(),
+            ContextPage::Export => self.export(),
+            ContextPage::ExportImage => self.export_image(),
+            ContextPage::KeyboardShortcuts => self.keyboard_shortcuts(),
+            ContextPage::Layers => self.layers(),
+            ContextPage::Settings => self.settings(),
+            ContextPage::Signatures => self.signatures(),
+            ContextPage::Statistics => self.statistics(),
+        })
+    }
+
+    fn dialog(&self) -> Option<Element<Message>> {
+        if let Some(note) = self
+            .note_edit
+            .and_then(|(page_id, index)| self.notes.get(&page_id)?.get(index))
+        {
+            return Some(
+                widget::dialog()
+                    .title(fl!("note"))
+                    .body(note.author.as_str())
+                    .control(
+                        widget::text_input(fl!("note-placeholder"), note.contents.as_str())
+                            .on_input(Message::NoteInput)
+                            .on_submit(Message::NoteEdit(None)),
+                    )
+                    .primary_action(
+                        widget::button::suggested(fl!("done")).on_press(Message::NoteEdit(None)),
+                    )
+                    .secondary_action(
+                        widget::button::destructive(fl!("delete")).on_press(Message::NoteDelete),
+                    )
+                    .into(),
+            );
+        }
+        if self.quit_prompt.is_some() {
+            return Some(
+                widget::dialog()
+                    .title(fl!("save-notes"))
+                    .body(fl!("save-notes-body"))
+                    .primary_action(
+                        widget::button::suggested(fl!("save"))
+                            .on_press(Message::QuitUnsaved(Some(true))),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("cancel"))
+                            .on_press(Message::QuitUnsaved(None)),
+                    )
+                    .tertiary_action(
+                        widget::button::destructive(fl!("quit-without-saving"))
+                            .on_press(Message::QuitUnsaved(Some(false))),
+                    )
+                    .into(),
+            );
+        }
+        if let Some(last_read) = self.resume_prompt {
+            return Some(
+                widget::dialog()
+                    .title(fl!("continue-reading"))
+                    .body(fl!("continue-reading-body", page = last_read.page + 1))
+                    .primary_action(
+                        widget::button::suggested(fl!("continue"))
+                            .on_press(Message::ResumeReading(true)),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("start-here"))
+                            .on_press(Message::ResumeReading(false)),
+                    )
+                    .into(),
+            );
+        }
+        if let Some(err) = &self.print_fallback {
+            return Some(
+                widget::dialog()
+                    .title(fl!("print-with-cups"))
+                    .body(fl!("print-with-cups-body", error = err.as_str()))
+                    .primary_action(
+                        widget::button::suggested(fl!("print"))
+                            .on_press(Message::PrintFallback(true)),
+                    )
+                    .secondary_action(
+                        widget::button::standard(fl!("cancel"))
+                            .on_press(Message::PrintFallback(false)),
+                    )
+                    .into(),
+            );
+        }
+        if !self.default_handler_dialog {
+            return None;
+        }
+        Some(
+            widget::dialog()
+                .title(fl!("default-viewer"))
+                .body(fl!("default-viewer-body"))
+                .primary_action(
+                    widget::button::suggested(fl!("set-default"))
+                        .on_press(Message::DefaultHandlerSet),
+                )
+                .secondary_action(
+                    widget::button::standard(fl!("not-now"))
+                        .on_press(Message::DefaultHandlerDismiss),
+                )
+                .into(),
+        )
+    }
+
+    fn header_start(&self) -> Vec<Element<Message>> {
+        let key_binds = self
+            .flags
+            .config
+            .key_binds
+            .iter()
+            .filter_map(|(action, key_bind)| Some((key_bind.to_menu()?, *action)))
+            .collect();
+        vec![menu::menu_bar(
+            &key_binds,
+            &self.flags.config.zoom_presets,
+            &self.flags.state.recent,
+            self.view.layout,
+            self.view.fit,
+            self.remembers_view(),
+            self.view_lock.is_some(),
+            self.flags.config.trim_margins,
+            self.flags.config.rulers,
+            self.flags.config.grid,
+            self.flags.config.night_mode,
+            self.reading_scroll.is_some(),
+            self.right_to_left(),
+            self.cover_page(),
+            self.flags
+                .state
+                .bookmarks
+                .get(&self.flags.key)
+                .is_some_and(|bookmarks| bookmarks.contains(&self.position())),
+            if self.core.nav_bar_active() {
+                Some(self.sidebar())
+            } else {
+                None
+            },
+            self.split_view,
+            self.compare_sync,
+            self.pane_zoom_lock,
+        )]
+    }
+
+    fn header_end(&self) -> Vec<Element<Message>> {
+        let mut elements = Vec::new();
+        if let Some(search) = &self.search {
+            elements.push(self.search_field(search));
+        }
+        let page = self
+            .page_input
+            .clone()
+            .unwrap_or_else(|| (self.position() + 1).to_string());
+        elements.push(
+            widget::row::with_children(vec![
+                widget::text_input("", page)
+                    .on_input(Message::PageInput)
+                    .on_submit(Message::PageInputSubmit)
+                    .on_focus(Message::PageInputFocus(true))
+                    .on_unfocus(Message::PageInputFocus(false))
+                    .width(Length::Fixed(56.0))
+                    .into(),
+                widget::text::body(fl!("page-total", pages = self.nav_model.len())).into(),
+            ])
+            .spacing(4)
+            .align_y(Vertical::Center)
+            .into(),
+        );
+        elements.push(
+            widget::button::icon(widget::icon::from_name("object-rotate-left-symbolic"))
+                .on_press(Message::Rotate(-90))
+                .into(),
+        );
+        elements.push(
+            widget::button::icon(widget::icon::from_name("object-rotate-right-symbolic"))
+                .on_press(Message::Rotate(90))
+                .into(),
+        );
+        elements.push(
+            widget::button::icon(widget::icon::from_name("weather-clear-night-symbolic"))
+                .on_press(Message::NightMode)
+                .into(),
+        );
+        elements
+    }
+
+    fn on_close_requested(&self, _id: window::Id) -> Option<Message> {
+        if self.state_changed.is_some() {
+            self.save_state();
+        }
+        // Closing the window asks about unsaved notes as quitting does
+        (!self.notes_changed.is_empty()).then_some(Message::Quit)
+    }
+
+    fn on_escape(&mut self) -> Task<Message> {
+        if self.search.take().is_some() {
+            self.search_results.clear();
+            self.canvas_cache.clear();
+        }
+        Task::none()
+    }
+
+    fn header_center(&self) -> Vec<Element<Message>> {
+        let breadcrumb = self.breadcrumb();
+        if breadcrumb.is_empty() {
+            return Vec::new();
+        }
+        vec![widget::text::body(breadcrumb.join(" › ")).into()]
+    }
+
+    fn nav_model(&self) -> Option<&Model> {
+        match self.sidebar() {
+            Sidebar::Pages => Some(&self.nav_model),
+            Sidebar::Outline => Some(&self.outline_model),
+            Sidebar::Structure => Some(&self.structure_model),
+            Sidebar::Bookmarks => Some(&self.bookmarks_model),
+            Sidebar::Annotations => Some(&self.annotations_model),
+        }
+    }
+
+    fn nav_bar(&self) -> Option<Element<cosmic::app::Message<Message>>> {
+        if !self.core.nav_bar_active() {
+            return None;
+        }
+        let nav_model = self.nav_model()?;
+        // The default sidebar, with the width it was resized to
+        let nav = widget::nav_bar(nav_model, |id| {
+            cosmic::app::Message::Cosmic(cosmic::app::cosmic::Message::NavBar(id))
+        })
+        .into_container()
+        .height(Length::Shrink);
+        let width = if self.core.is_condensed() {
+            Length::Shrink
+        } else {
+            Length::Fixed(self.flags.state.sidebar_width)
+        };
+        Some(nav.width(width).into())
+    }
+
+    fn on_nav_select(&mut self, id: widget::nav_bar::Id) -> Task<Message> {
+        let position = match self.sidebar() {
+            Sidebar::Pages => {
+                self.nav_model.activate(id);
+                return self.page_changed();
+            }
+            Sidebar::Outline => Self::select_position(&mut self.outline_model, id),
+            Sidebar::Structure => Self::select_position(&mut self.structure_model, id),
+            Sidebar::Bookmarks => Self::select_position(&mut self.bookmarks_model, id),
+            Sidebar::Annotations => Self::select_position(&mut self.annotations_model, id),
+        };
+        match position {
+            Some(position) => self.activate_position(position),
+            None => Task::none(),
+        }
+    }
+
+    fn update(&mut self, message: Message) -> Task<Message> {
+        match message {
+            Message::CanvasClearCache => {
+                self.canvas_cache.clear();
+            }
+            Message::ColorInput(setting, input) => {
+                // Empty colors go back to the default
+                if input.trim().is_empty() || parse_color(&input).is_some() {
+                    *self.flags.config.color_mut(setting) = input.trim().to_string();
+                    self.save_config();
+                    self.canvas_cache.clear();
+                    self.color_inputs.remove(&setting);
+                } else {
+                    self.color_inputs.insert(setting, input);
+                }
+            }
+            Message::DefaultHandler(is_default) => {
+                self.default_handler = is_default;
+                // Offer once, the settings can change it later
+                if is_default == Some(false) && !self.flags.state.default_handler_offered {
+                    self.default_handler_dialog = true;
+                    self.flags.state.default_handler_offered = true;
+                    self.save_state();
+                }
+            }
+            Message::DefaultHandlerDismiss => {
+                self.default_handler_dialog = false;
+            }
+            Message::DefaultHandlerSet => {
+                self.default_handler_dialog = false;
+                return Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(|| mime::set_default(&App::desktop_id()))
+                            .await
+                            .map_err(|err| err.to_string())
+                            .and_then(|result| result)
+                    },
+                    |result| {
+                        if let Err(err) = &result {
+                            log::error!("failed to set default application: {}", err);
+                        }
+                        cosmic::app::Message::App(Message::DefaultHandler(Some(result.is_ok())))
+                    },
+                );
+            }
+            Message::DefaultLayout(layout) => {
+                self.flags.config.view.layout = layout;
+                self.save_config();
+                if !self.remembers_view() {
+                    return self.set_view(self.flags.config.view);
+                }
+            }
+            Message::DefaultZoom(i) => {
+                let (fit, scale) = match i {
+                    0 => (pdf::Fit::Page, 1.0),
+                    1 => (pdf::Fit::Width, 1.0),
+                    _ => match self.flags.config.zoom_presets.get(i - 2) {
+                        Some(&percent) => (pdf::Fit::ActualSize, f32::from(percent) / 100.0),
+                        None => return Task::none(),
+                    },
+                };
+                self.flags.config.view.fit = fit;
+                self.flags.config.view.scale = scale;
+                self.save_config();
+                if !self.remembers_view() {
+                    return self.set_view(self.flags.config.view);
+                }
+            }
+            Message::Autoscroll(velocity) => match (&mut self.autoscroll, velocity) {
+                (Some(autoscroll), Some(velocity)) => autoscroll.velocity = velocity,
+                (_, velocity) => self.autoscroll = velocity.map(Autoscroll::new),
+            },
+            Message::AutoscrollTick(now) => {
+                if let Some(autoscroll) = &mut self.autoscroll {
+                    let offset = autoscroll.step(now);
+                    return self.pan(offset);
+                }
+            }
+            Message::FocusPane => {
+                // The sidebar can only be focused while it is shown
+                let sidebar_shown = self.core.nav_bar_active() && self.presentation.is_none();
+                let compare_shown =
+                    self.compare_size(self.position()).is_some() && self.presentation.is_none();
+                self.pane_focus = match self.pane_focus {
+                    Some(Pane::Page) if compare_shown => Some(Pane::Compare),
+                    Some(Pane::Page | Pane::Compare) | None if sidebar_shown => Some(Pane::Sidebar),
+                    _ => Some(Pane::Page),
+                };
+            }
+            Message::Find => {
+                if self.search.take().is_some() {
+                    self.search_results.clear();
+                    self.canvas_cache.clear();
+                    return Task::none();
+                }
+                self.search = Some(String::new());
+                let mut tasks = vec![widget::text_input::focus(self.search_id.clone())];
+                if self.search_index.is_none() {
+                    let doc = self.flags.doc.clone();
+                    let path = self.flags.path.clone();
+                    tasks.push(Task::perform(
+                        async move {
+                            tokio::task::spawn_blocking(move || search::Index::load(&doc, &path))
+                                .await
+                                .unwrap_or_else(|err| {
+                                    log::error!("failed to index document: {err}");
+                                    search::Index::default()
+                                })
+                        },
+                        |index| cosmic::app::Message::App(Message::SearchIndex(Arc::new(index))),
+                    ));
+                }
+                return Task::batch(tasks);
+            }
+            Message::Eyedropper(eyedropper) => {
+                self.eyedropper = eyedropper;
+            }
+            Message::NoteTool(note_tool) => {
+                self.note_tool = note_tool;
+            }
+            Message::NoteAdd(page_id, point) => {
+                self.note_tool = false;
+                self.history
+                    .push(EditKind::NoteAdd, self.notes_edit(page_id));
+                let notes = self.notes.entry(page_id).or_default();
+                notes.push(note::Note::new(point, self.flags.config.author.clone()));
+                self.note_edit = Some((page_id, notes.len() - 1));
+                self.notes_changed.insert(page_id);
+                self.update_annotations_model();
+                self.canvas_cache.clear();
+            }
+            Message::NoteEdit(note_edit) => {
+                self.note_edit = note_edit;
+            }
+            Message::NoteInput(contents) => {
+                if let Some((page_id, index)) = self.note_edit {
+                    self.history
+                        .push(EditKind::NoteText(page_id, index), self.notes_edit(page_id));
+                    if let Some(note) = self
+                        .notes
+                        .get_mut(&page_id)
+                        .and_then(|notes| notes.get_mut(index))
+                    {
+                        note.contents = contents;
+                        self.notes_changed.insert(page_id);
+                        self.update_annotations_model();
+                    }
+                }
+            }
+            Message::NoteDelete => {
+                if let Some((page_id, index)) = self.note_edit.take() {
+                    self.history
+                        .push(EditKind::NoteDelete, self.notes_edit(page_id));
+                    if let Some(notes) = self.notes.get_mut(&page_id) {
+                        if index < notes.len() {
+                            notes.remove(index);
+                            self.notes_changed.insert(page_id);
+                        }
+                    }
+                    self.update_annotations_model();
+                    self.canvas_cache.clear();
+                }
+            }
+            Message::Save => {
+                if self.notes_changed.is_empty() {
+                    return Task::none();
+                }
+                // New notes are given the annotations they are saved as now, so notes edited
+                // while saving are saved to the same annotations next time
+                let mut next_id = self
+                    .notes
+                    .values()
+                    .flatten()
+                    .filter_map(|note| Some(note.id?.0))
+                    .fold(self.flags.doc.max_id, u32::max);
+                for page_id in self.notes_changed.iter() {
+                    for note in self.notes.get_mut(page_id).into_iter().flatten() {
+                        if note.id.is_none() {
+                            next_id += 1;
+                            note.id = Some((next_id, 0));
+                        }
+                    }
+                }
+                let notes: Arc<HashMap<ObjectId, Vec<note::Note>>> = Arc::new(
+                    self.notes_changed
+                        .iter()
+                        .filter_map(|page_id| Some((*page_id, self.notes.get(page_id)?.clone())))
+                        .collect(),
+                );
+                let doc = self.flags.doc.clone();
+                let path = self.flags.path.clone();
+                return Task::perform(
+                    async move {
+                        let saving = notes.clone();
+                        let result =
+                            tokio::task::spawn_blocking(move || note::save(&doc, &saving, &path))
+                                .await
+                                .map_err(|err| err.to_string())
+                                .and_then(|result| result.map_err(|err| err.to_string()))
+                                .map(Arc::new);
+                        (result, notes)
+                    },
+                    |(result, notes)| cosmic::app::Message::App(Message::Saved(result, notes)),
+                );
+            }
+            Message::Saved(result, saved) => {
+                let content = match result {
+                    Ok(doc) => {
+                        // Pages edited again while saving are still changed
+                        for (page_id, notes) in saved.iter() {
+                            if self.notes.get(page_id) == Some(notes) {
+                                self.notes_changed.remove(page_id);
+                            }
+                        }
+                        self.flags.doc = doc;
+                        if let Some(leave) = self.quit_after_save.take() {
+                            return self.leave(leave);
+                        }
+                        fl!("saved")
+                    }
+                    Err(err) => {
+                        log::error!("failed to save: {}", err);
+                        self.quit_after_save = None;
+                        fl!("save-failed", error = err)
+                    }
+                };
+                self.status = Some((content, Instant::now()));
+                self.canvas_cache.clear();
+            }
+            Message::PickColor(position) => {
+                self.eyedropper = false;
+                if let Some(id) = self.core.main_window_id() {
+                    let scale_factor = self.scale_factor;
+                    return window::screenshot(id).map(move |screenshot| {
+                        cosmic::app::Message::App(Message::PickedColor(screenshot_color(
+                            &screenshot,
+                            position,
+                            scale_factor,
+                        )))
+                    });
+                }
+            }
+            Message::PickedColor(color) => {
+                if let Some(color) = color {
+                    self.picked_color = Some((color, Instant::now()));
+                    return clipboard::write(format_color(color));
+                }
+            }
+            Message::Fling(velocity) => {
+                self.fling = velocity
+                    .filter(|_| !self.reduce_motion())
+                    .and_then(Fling::new);
+            }
+            Message::FlingTick(now) => {
+                if let Some(fling) = &mut self.fling {
+                    match fling.step(now) {
+                        Some(offset) => return self.pan(offset),
+                        None => self.fling = None,
+                    }
+                }
+            }
+            Message::CropMargin(i, input) => {
+                self.history
+                    .push(EditKind::CropMargin(i), self.export_edit());
+                // Margins are only used once they are valid
+                let margin = if input.trim().is_empty() {
+                    Some(0.0)
+                } else {
+                    input.trim().parse::<f32>().ok().filter(|x| *x >= 0.0)
+                };
+                if let Some(margin) = margin {
+                    self.crop_margins[i] = margin;
+                    self.canvas_cache.clear();
+                }
+                self.crop_inputs[i] = input;
+            }
+            Message::CropCurrentPage(current_page) => {
+                self.history
+                    .push(EditKind::CropCurrentPage, self.export_edit());
+                self.crop_current_page = current_page;
+                self.canvas_cache.clear();
+            }
+            Message::Export => {
+                let doc = self.flags.doc.clone();
+                let options = self.export_options();
+                let file_name = export::export_file_name(&self.flags.path);
+                self.export_status = Some(fl!("exporting"));
+                return Task::perform(
+                    async move {
+                        let response = file_chooser::save::Dialog::new()
+                            .title(fl!("export"))
+                            .file_name(file_name)
+                            .save_file()
+                            .await;
+                        let path = match response {
+                            Ok(response) => response
+                                .url()
+                                .and_then(|url| url.to_file_path().ok())
+                                .ok_or_else(|| fl!("not-a-local-file"))?,
+                            Err(file_chooser::Error::Cancelled) => return Ok(None),
+                            Err(err) => return Err(err.to_string()),
+                        };
+                        tokio::task::spawn_blocking(move || {
+                            export::export(&doc, &options, &path).map(|()| Some(path))
+                        })
+                        .await
+                        .map_err(|err| err.to_string())?
+                        .map_err(|err| err.to_string())
+                    },
+                    |result| cosmic::app::Message::App(Message::Exported(result)),
+                );
+            }
+            Message::Exported(result) => {
+                self.export_status = match result {
+                    Ok(None) => None,
+                    Ok(Some(path)) => Some(fl!("exported", path = path.display().to_string())),
+                    Err(err) => {
+                        log::error!("failed to export: {}", err);
+                        Some(fl!("export-failed", error = err))
+                    }
+                };
+            }
+            Message::ExportImage => {
+                let positions = if self.image_pages_input.trim().is_empty() {
+                    vec![self.position()]
+                } else {
+                    match raster::parse_pages(&self.image_pages_input, self.page_ids.len()) {
+                        Ok(positions) => positions,
+                        Err(err) => {
+                            self.export_status = Some(err);
+                            return Task::none();
+                        }
+                    }
+                };
+                let pages: Vec<_> = positions
+                    .into_iter()
+                    .filter_map(|position| {
+                        let page_id = *self.page_ids.get(usize::from(position))?;
+                        Some((position, page_id, self.page_box(position)?))
+                    })
+                    .collect();
+                let doc = self.flags.doc.clone();
+                let hidden = self.hidden_layers.clone();
+                let (dpi, format) = (self.image_dpi, self.image_format);
+                let stem = self
+                    .flags
+                    .path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let file_name = match &pages[..] {
+                    [(position, ..)] => {
+                        format!("{stem}-page-{}.{}", position + 1, format.extension())
+                    }
+                    _ => format!("{stem}.{}", format.extension()),
+                };
+                self.export_status = Some(fl!("exporting"));
+                return Task::perform(
+                    async move {
+                        let response = file_chooser::save::Dialog::new()
+                            .title(fl!("export-image"))
+                            .file_name(file_name)
+                            .save_file()
+                            .await;
+                        let path = match response {
+                            Ok(response) => response
+                                .url()
+                                .and_then(|url| url.to_file_path().ok())
+                                .ok_or_else(|| fl!("not-a-local-file"))?,
+                            Err(file_chooser::Error::Cancelled) => return Ok(None),
+                            Err(err) => return Err(err.to_string()),
+                        };
+                        tokio::task::spawn_blocking(move || {
+                            // Several pages are numbered after the name chosen
+                            let several = pages.len() > 1;
+                            let mut first = None;
+                            for (position, page_id, page_box) in pages {
+                                let path = if several {
+                                    raster::page_path(&path, position + 1)
+                                } else {
+                                    path.clone()
+                                };
+                                raster::export(
+                                    &doc, page_id, page_box, &hidden, dpi, format, &path,
+                                )?;
+                                first.get_or_insert(path);
+                            }
+                            Ok(first)
+                        })
+                        .await
+                        .map_err(|err| err.to_string())?
+                    },
+                    |result| cosmic::app::Message::App(Message::ExportedImage(result)),
+                );
+            }
+            Message::ExportedImage(result) => {
+                self.export_status = match result {
+                    Ok(None) => None,
+                    Ok(Some(path)) => Some(fl!("exported", path = path.display().to_string())),
+                    Err(err) => {
+                        log::error!("failed to export image: {}", err);
+                        Some(fl!("export-failed", error = err))
+                    }
+                };
+            }
+            Message::ImagePagesInput(input) => {
+                self.image_pages_input = input;
+            }
+            Message::ImageDpiInput(input) => {
+                if let Ok(dpi @ 1..) = input.trim().parse::<u16>() {
+                    self.image_dpi = dpi;
+                }
+                self.image_dpi_input = input;
+            }
+            Message::ImageFormat(format) => {
+                self.image_format = format;
+            }
+            Message::ExportSvg => {
+                let position = self.position();
+                let (Some(&page_id), Some(page_box)) = (
+                    self.page_ids.get(usize::from(position)),
+                    self.page_box(position),
+                ) else {
+                    return Task::none();
+                };
+                let doc = self.flags.doc.clone();
+                let hidden = self.hidden_layers.clone();
+                let file_name = svg::svg_file_name(&self.flags.path, position + 1);
+                return Task::perform(
+                    async move {
+                        let response = file_chooser::save::Dialog::new()
+                            .title(fl!("export-svg"))
+                            .file_name(file_name)
+                            .save_file()
+                            .await;
+                        let path = match response {
+                            Ok(response) => response
+                                .url()
+                                .and_then(|url| url.to_file_path().ok())
+                                .ok_or_else(|| fl!("not-a-local-file"))?,
+                            Err(file_chooser::Error::Cancelled) => return Ok(None),
+                            Err(err) => return Err(err.to_string()),
+                        };
+                        tokio::task::spawn_blocking(move || {
+                            svg::export(&doc, page_id, page_box, &hidden, &path)
+                                .map(|()| Some(path))
+                        })
+                        .await
+                        .map_err(|err| err.to_string())?
+                    },
+                    |result| cosmic::app::Message::App(Message::ExportedSvg(result)),
+                );
+            }
+            Message::ExportedSvg(result) => {
+                let content = match result {
+                    Ok(None) => return Task::none(),
+                    Ok(Some(path)) => fl!("exported", path = path.display().to_string()),
+                    Err(err) => {
+                        log::error!("failed to export SVG: {}", err);
+                        fl!("export-failed", error = err)
+                    }
+                };
+                self.status = Some((content, Instant::now()));
+                self.canvas_cache.clear();
+            }
+            Message::WarmCache => {
+                let idle = self
+                    .last_input
+                    .get()
+                    .map_or(true, |input| input.elapsed() >= WARM_IDLE);
+                if idle && self.warming.is_none() {
+                    if let Some(page_id) = self.warm_page() {
+                        self.warming = Some(page_id);
+                        return self.load_page(page_id);
+                    }
+                }
+            }
+            Message::Forwarded(page) => {
+                let mut tasks = Vec::new();
+                if let Some(id) = self.core.main_window_id() {
+                    tasks.push(window::gain_focus(id));
+                }
+                if let Some(page) = page {
+                    let last = self.nav_model.len().saturating_sub(1) as u16;
+                    tasks.push(self.activate_position(page.saturating_sub(1).min(last)));
+                }
+                return Task::batch(tasks);
+            }
+            Message::PowerCheck => {
+                self.on_battery = power::on_battery();
+                let transitions = self.transitions();
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.transitions = transitions;
+                }
+            }
+            Message::PlayMedia(page_id, index) => {
+                let Some(media) = self
+                    .page_media
+                    .get(&page_id)
+                    .and_then(|media| media.get(index))
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+                let doc = self.flags.doc.clone();
+                let path = self.flags.path.clone();
+                return Task::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || media::play(&doc, &media, &path))
+                            .await
+                            .map_err(|err| err.to_string())?
+                    },
+                    |result| cosmic::app::Message::App(Message::MediaPlayed(result)),
+                );
+            }
+            Message::FollowLink(target) => match target {
+                pdf::LinkTarget::Page(page_id, top) => {
+                    let Some(position) = self.page_ids.iter().position(|x| *x == page_id) else {
+                        return Task::none();
+                    };
+                    let position = position as u16;
+                    let task = self.activate_position(position);
+                    // Center the top of the destination, where the layout pans to pages
+                    //TODO: also for zoomed pages of other layouts and rotated pages
+                    if let (Some(top), Layout::Continuous, Some(page_box)) =
+                        (top, self.layout(), self.page_box(position))
+                    {
+                        if page_box.rotate == 0 {
+                            self.view.translate.y -=
+                                top - (page_box.rect.y + page_box.rect.height / 2.0);
+                        }
+                    }
+                    return task;
+                }
+                pdf::LinkTarget::Uri(uri) => {
+                    if let Err(err) = link::open(&uri) {
+                        log::error!("failed to open link: {}", err);
+                        self.status = Some((fl!("open-link-failed", error = err), Instant::now()));
+                        self.canvas_cache.clear();
+                    }
+                }
+            },
+            Message::MediaPlayed(result) => {
+                if let Err(err) = result {
+                    log::error!("failed to play media: {}", err);
+                    self.status = Some((fl!("play-media-failed", error = err), Instant::now()));
+                    self.canvas_cache.clear();
+                }
+            }
+            Message::Print => {
+                let doc = self.flags.doc.clone();
+                let title = self.print_title();
+                self.status = Some((fl!("printing"), Instant::now()));
+                self.canvas_cache.clear();
+                return Task::perform(
+                    async move {
+                        let data = App::print_data(doc).await?;
+                        print::portal(data, title).await.map(|()| None)
+                    },
+                    |result| cosmic::app::Message::App(Message::Printed(result)),
+                );
+            }
+            Message::PrintFallback(confirmed) => {
+                self.print_fallback = None;
+                if confirmed {
+                    let doc = self.flags.doc.clone();
+                    let title = self.print_title();
+                    self.status = Some((fl!("printing"), Instant::now()));
+                    self.canvas_cache.clear();
+                    return Task::perform(
+                        async move {
+                            let data = App::print_data(doc).await?;
+                            tokio::task::spawn_blocking(move || print::lp(&data, &title))
+                                .await
+                                .map_err(|err| print::Error::Failed(err.to_string()))?
+                                .map(Some)
+                        },
+                        |result| cosmic::app::Message::App(Message::Printed(result)),
+                    );
+                }
+            }
+            Message::Printed(result) => {
+                let content = match result {
+                    Ok(request) => {
+                        log::info!("printed: {:?}", request);
+                        Some(fl!("printed"))
+                    }
+                    Err(print::Error::Unavailable(err)) => {
+                        log::warn!("print portal unavailable: {}", err);
+                        self.print_fallback = Some(err);
+                        None
+                    }
+                    Err(print::Error::Cancelled) => None,
+                    Err(print::Error::Failed(err)) => {
+                        log::error!("failed to print: {}", err);
+                        Some(fl!("print-failed", error = err))
+                    }
+                };
+                self.status = content.map(|content| (content, Instant::now()));
+                self.canvas_cache.clear();
+            }
+            Message::Share => {
+                let path = self.flags.path.clone();
+                let subject = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                return Task::perform(share::email(path, subject), |result| {
+                    cosmic::app::Message::App(Message::Shared(result))
+                });
+            }
+            Message::Shared(result) => {
+                if let Err(err) = result {
+                    log::error!("failed to share: {}", err);
+                    self.status = Some((fl!("share-failed", error = err), Instant::now()));
+                    self.canvas_cache.clear();
+                }
+            }
+            Message::Fit(fit) => {
+                return self.set_view(DocumentView {
+                    fit,
+                    scale: 1.0,
+                    ..self.document_view()
+                });
+            }
+            Message::KeyBindCapture(action) => {
+                self.key_bind_capture = action;
+            }
+            Message::KeyBindSet(action, key_bind) => {
+                // A key bind can only trigger one action
+                self.flags
+                    .config
+                    .key_binds
+                    .retain(|other, x| *other == action || *x != key_bind);
+                self.flags.config.key_binds.insert(action, key_bind);
+                self.key_bind_capture = None;
+                self.save_config();
+            }
+            Message::KeyBindsReset => {
+                self.flags.config.key_binds = key_bind::key_binds();
+                self.key_bind_capture = None;
+                self.save_config();
+            }
+            Message::MouseBindSet(mouse_bind, action) => {
+                match action {
+                    Some(action) => self.flags.config.mouse_binds.insert(mouse_bind, action),
+                    None => self.flags.config.mouse_binds.remove(&mouse_bind),
+                };
+                self.save_config();
+            }
+            Message::Layer(index, visible) => {
+                if let Some(layer) = self.layers.get_mut(index) {
+                    layer.visible = visible;
+                }
+                self.hidden_layers = Arc::new(layer::hidden(&self.layers));
+                // Pages are loaded again without the hidden layers
+                self.page_cache.clear();
+                self.pages_loading.get_mut().clear();
+                self.page_content.clear();
+                self.canvas_cache.clear();
+                return self.load_layout_pages();
+            }
+            Message::Layout(layout) => {
+                return self.set_view(DocumentView {
+                    layout,
+                    ..self.document_view()
+                });
+            }
+            Message::PageInput(input) => {
+                self.page_input = Some(input);
+            }
+            Message::PageInputFocus(focused) => {
+                self.page_input = focused.then(|| (self.position() + 1).to_string());
+            }
+            Message::PageInputSubmit => {
+                // Page numbers past the end go to the last page
+                let last = self.nav_model.len().saturating_sub(1);
+                let position = self
+                    .page_input
+                    .as_deref()
+                    .and_then(|input| input.trim().parse::<usize>().ok())
+                    .and_then(|page| page.checked_sub(1))
+                    .map(|position| position.min(last) as u16);
+                let Some(position) = position else {
+                    self.page_input = Some((self.position() + 1).to_string());
+                    return Task::none();
+                };
+                self.page_input = Some((position + 1).to_string());
+                return self.activate_position(position);
+            }
+            Message::PageGoto(position) => {
+                self.page_number = None;
+                if self.pane_focus == Some(Pane::Compare) {
+                    return self.compare_goto(i32::from(position));
+                }
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.set_overview(None);
+                }
+                return self.activate_position(position);
+            }
+            Message::PageLast => {
+                if self.pane_focus == Some(Pane::Compare) {
+                    return self.compare_goto(self.compare_ids.len() as i32 - 1);
+                }
+                if let Some(position) = self.nav_model.len().checked_sub(1) {
+                    return self.activate_position(position as u16);
+                }
+            }
+            Message::PageNext => {
+                if let Some(index) = self
+                    .compare_index(self.position())
+                    .filter(|_| self.pane_focus == Some(Pane::Compare))
+                {
+                    return self.compare_goto(index as i32 + 1);
+                }
+                if let Some(position) = self.next_position() {
+                    return self.activate_position(position);
+                }
+            }
+            Message::PageAnimation(animation) => {
+                self.flags.config.page_animation = animation;
+                self.save_config();
+            }
+            Message::PageEdge(edge) => {
+                self.flags.config.page_edge = edge;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::PageGap(gap) => {
+                self.flags.config.page_gap = gap;
+                self.save_config();
+                self.scroll_to_page();
+                self.canvas_cache.clear();
+            }
+            Message::PageOrder(direction) => {
+                self.flags.config.direction = direction;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::PageNumber(typed) => {
+                self.page_number = typed.map(|typed| (typed, Instant::now()));
+            }
+            Message::Antialiasing(antialiasing) => {
+                // The renderer is chosen when the window is created
+                self.flags.config.antialiasing = antialiasing;
+                self.save_config();
+            }
+            Message::AuthorInput(author) => {
+                self.flags.config.author = author;
+                self.save_config();
+            }
+            Message::PaperColorInput(input) => {
+                // Colors are only saved once they are valid
+                if parse_color(&input).is_some() {
+                    self.flags.config.paper_color = input.trim().to_string();
+                    self.save_config();
+                    self.canvas_cache.clear();
+                }
+                self.paper_color_input = input;
+            }
+            Message::PaperTint(paper_tint) => {
+                self.flags.config.paper_tint = paper_tint;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::CompareOps(generation, page_id, ops) => {
+                if generation == self.compare_generation {
+                    self.compare_loading.get_mut().remove(&page_id);
+                    self.compare_differences
+                        .get_mut()
+                        .retain(|(_, compare_id), _| *compare_id != page_id);
+                    self.compare_cache.insert(page_id, ops);
+                    self.canvas_cache.clear();
+                }
+            }
+            Message::CompareOpened(result) => match result {
+                Ok(Some(compare)) => return self.set_compare_doc(Some(compare)),
+                Ok(None) => {}
+                Err(err) => {
+                    log::error!("failed to open document for the second pane: {}", err);
+                    self.status = Some((fl!("open-failed", error = err), Instant::now()));
+                    self.canvas_cache.clear();
+                }
+            },
+            Message::PageOps(page_id, hidden, ops) => {
+                if self.warming == Some(page_id) {
+                    self.warming = None;
+                }
+                // Pages loaded before layers were shown or hidden are being loaded again
+                if !Arc::ptr_eq(&hidden, &self.hidden_layers) {
+                    return Task::none();
+                }
+                self.pages_loading.get_mut().remove(&page_id);
+                if let Some(content) = pdf::content_bounds(&ops) {
+                    self.page_content.insert(page_id, content);
+                }
+                self.compare_differences
+                    .get_mut()
+                    .retain(|(x, _), _| *x != page_id);
+                self.page_cache.insert(page_id, ops);
+                // Notes are kept as edited when pages are loaded again
+                self.notes
+                    .entry(page_id)
+                    .or_insert_with(|| note::page_notes(&self.flags.doc, page_id));
+                self.page_media
+                    .insert(page_id, pdf::page_media(&self.flags.doc, page_id));
+                self.page_links
+                    .insert(page_id, pdf::page_links(&self.flags.doc, page_id));
+                if self.flags.config.trim_margins {
+                    if let Some(position) = self.page_ids.iter().position(|x| *x == page_id) {
+                        self.update_page_size(position as u16);
+                    }
+                }
+                self.canvas_cache.clear();
+            }
+            Message::PagePrev => {
+                if let Some(index) = self
+                    .compare_index(self.position())
+                    .filter(|_| self.pane_focus == Some(Pane::Compare))
+                {
+                    return self.compare_goto(index as i32 - 1);
+                }
+                if let Some(position) = self.prev_position() {
+                    return self.activate_position(position);
+                }
+            }
+            Message::Pan(offset) => {
+                return self.pan(offset);
+            }
+            Message::Present(present) => {
+                let presentation = if present {
+                    Some(Presentation::new(
+                        false,
+                        self.flags.duration,
+                        self.flags.interval,
+                        self.transitions(),
+                    ))
+                } else {
+                    None
+                };
+                return self.set_presentation(presentation);
+            }
+            Message::PresentationBlank(blank) => {
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.blank = blank;
+                }
+            }
+            Message::PresentationInk(ink) => {
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.ink_update(ink);
+                }
+            }
+            Message::PresentationOverview(overview) => {
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.set_overview(overview);
+                    self.canvas_cache.clear();
+                    // Only the slides on the screen of the overview with the selection are shown
+                    if let Some(selected) = overview {
+                        let layout = OverviewLayout::new(
+                            self.canvas_size.get(),
+                            self.page_ids.len(),
+                            usize::from(selected),
+                        );
+                        return Task::batch(
+                            self.page_ids[layout.first..layout.last]
+                                .iter()
+                                .map(|&page_id| self.load_page(page_id)),
+                        );
+                    }
+                }
+            }
+            Message::PresentationPause => {
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.set_paused(!presentation.paused);
+                }
+            }
+            Message::PresentationPointer(pointer) => {
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.pointer = pointer;
+                }
+            }
+            Message::PresentationTick => {
+                if let Some(presentation) = &self.presentation {
+                    if presentation.advance_due() {
+                        let position = self.position();
+                        if usize::from(position) + 1 < self.nav_model.len() {
+                            return self.activate_position(position + 1);
+                        } else if presentation.kiosk {
+                            // Kiosks loop back to the first page after the last one
+                            return self.activate_position(0);
+                        }
+                    }
+                }
+            }
+            Message::PresentationTimer => {
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.show_timer = !presentation.show_timer;
+                }
+            }
+            Message::ToggleContextPage(context_page) => {
+                if self.context_page == context_page {
+                    self.core.window.show_context = !self.core.window.show_context;
+                } else {
+                    self.context_page = context_page;
+                    self.core.window.show_context = true;
+                }
+                self.set_context_title(context_page.title());
+                if context_page == ContextPage::Statistics && self.statistics.is_none() {
+                    let doc = self.flags.doc.clone();
+                    let conformance_doc = doc.clone();
+                    return Task::batch([
+                        Task::perform(
+                            async move {
+                                tokio::task::spawn_blocking(move || pdf::statistics(&doc))
+                                    .await
+                                    .unwrap_or_else(|err| {
+                                        log::error!("failed to count statistics: {err}");
+                                        pdf::Statistics::default()
+                                    })
+                            },
+                            |statistics| cosmic::app::Message::App(Message::Statistics(statistics)),
+                        ),
+                        Task::perform(
+                            async move {
+                                tokio::task::spawn_blocking(move || {
+                                    conformance::conformance(&conformance_doc)
+                                })
+                                .await
+                                .unwrap_or_else(|err| {
+                                    log::error!("failed to check conformance: {err}");
+                                    conformance::Conformance::default()
+                                })
+                            },
+                            |conformance| {
+                                cosmic::app::Message::App(Message::Conformance(conformance))
+                            },
+                        ),
+                    ]);
+                }
+            }
+            Message::ToggleGrid => {
+                self.flags.config.grid = !self.flags.config.grid;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::NightMode => {
+                self.flags.config.night_mode = !self.flags.config.night_mode;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::ToggleRulers => {
+                self.flags.config.rulers = !self.flags.config.rulers;
+                self.save_config();
+            }
+            Message::ToggleSidebar => {
+                if self.presentation.is_none() {
+                    self.core.nav_bar_toggle();
+                    self.flags.state.show_sidebar = self.core.nav_bar_active();
+                    self.save_state();
+                }
+            }
+            Message::Zoom(factor) => {
+                if self.zooms_compare_alone() {
+                    self.compare_zoom *= factor;
+                    self.canvas_cache.clear();
+                    return Task::none();
+                }
+                self.view.scale *= factor;
+                if self.animates_zoom() {
+                    // Start from the scale shown, so zooming again during the animation is smooth
+                    let shown = self.zoom_ease.as_ref().map_or(0.0, Ease::value);
+                    self.zoom_ease = Some(Ease::new(shown - factor.ln()));
+                }
+                self.view_changed();
+                // Zooming out shows more pages
+                return self.load_layout_pages();
+            }
+            Message::ZoomPreset(percent) => {
+                return self.set_view(DocumentView {
+                    fit: pdf::Fit::ActualSize,
+                    scale: f32::from(percent) / 100.0,
+                    ..self.document_view()
+                });
+            }
+            Message::ZoomPresetsInput(input) => {
+                // Presets are only saved when the whole list is valid
+                let presets: Result<Vec<u16>, _> = input
+                    .split(',')
+                    .map(|x| x.trim().trim_end_matches('%').parse::<u16>())
+                    .collect();
+                if let Ok(presets) = presets {
+                    if !presets.is_empty() && !presets.contains(&0) {
+                        self.flags.config.zoom_presets = presets;
+                        self.save_config();
+                        self.update_default_zoom_names();
+                    }
+                }
+                self.zoom_presets_input = input;
+            }
+            Message::ZoomPan(factor, pan) => {
+                // Following the cursor or fingers directly, animating would lag behind them
+                self.view.scale *= factor;
+                self.view_changed();
+                return self.pan(pan);
+            }
+            Message::ZoomReset => {
+                if self.zooms_compare_alone() {
+                    self.compare_zoom = 1.0;
+                    self.canvas_cache.clear();
+                    return Task::none();
+                }
+                return self.set_view(DocumentView {
+                    scale: 1.0,
+                    ..self.document_view()
+                });
+            }
+            Message::GridSpacing(spacing) => {
+                self.flags.config.grid_spacing = spacing;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::ZoomStep(percent) => {
+                self.flags.config.zoom_step = percent;
+                self.save_config();
+            }
+            Message::DocumentNext => {
+                return self.open_sibling(true);
+            }
+            Message::DocumentPrev => {
+                return self.open_sibling(false);
+            }
+            Message::NewInstance => {
+                // Another process shows the document in its own window, with its own state
+                //TODO: open windows in this process with window::open, which needs the state of
+                // each document moved out of App into state kept by window
+                let page = self.position() + 1;
+                if let Err(err) = env::current_exe().and_then(|exe| {
+                    process::Command::new(exe)
+                        .arg("--new-instance")
+                        .arg("--page")
+                        .arg(page.to_string())
+                        .arg(&self.flags.path)
+                        .spawn()
+                }) {
+                    log::error!("failed to start new instance: {}", err);
+                    self.status = Some((
+                        fl!("new-instance-failed", error = err.to_string()),
+                        Instant::now(),
+                    ));
+                    self.canvas_cache.clear();
+                }
+            }
+            Message::Open => {
+                return Task::perform(
+                    async move {
+                        let response = file_chooser::open::Dialog::new()
+                            .title(fl!("open"))
+                            .open_file()
+                            .await;
+                        match response {
+                            Ok(response) => response
+                                .url()
+                                .to_file_path()
+                                .map(Some)
+                                .map_err(|()| fl!("not-a-local-file")),
+                            Err(file_chooser::Error::Cancelled) => Ok(None),
+                            Err(err) => Err(err.to_string()),
+                        }
+                    },
+                    |result| cosmic::app::Message::App(Message::OpenChosen(result)),
+                );
+            }
+            Message::OpenChosen(result) => match result {
+                Ok(Some(path)) => return self.leave(Leave::Open(path)),
+                Ok(None) => {}
+                Err(err) => {
+                    self.status = Some((fl!("open-failed", error = err), Instant::now()));
+                    self.canvas_cache.clear();
+                }
+            },
+            Message::OpenRecent(index) => {
+                if let Some(path) = self.flags.state.recent.get(index).cloned() {
+                    return self.leave(Leave::Open(path));
+                }
+            }
+            Message::Quit => {
+                self.quit_after_save = None;
+                return self.leave(Leave::Quit);
+            }
+            Message::QuitUnsaved(answer) => {
+                let leave = self.quit_prompt.take();
+                match (answer, leave) {
+                    (Some(true), Some(leave)) => {
+                        self.quit_after_save = Some(leave);
+                        return self.update(Message::Save);
+                    }
+                    (Some(false), Some(leave)) => {
+                        self.notes_changed.clear();
+                        return self.leave(leave);
+                    }
+                    _ => {}
+                }
+            }
+            Message::ReadingScroll => {
+                if self.reading_scroll.take().is_none() {
+                    self.reading_scroll = Some(Autoscroll::new(Vector::new(0.0, 0.0)));
+                    self.last_input.set(None);
+                    // Only the continuous layout can scroll through the document
+                    if self.view.layout != Layout::Continuous {
+                        return self.set_view(DocumentView {
+                            layout: Layout::Continuous,
+                            ..self.document_view()
+                        });
+                    }
+                }
+            }
+            Message::ReadingScrollTick(now) => {
+                let paused = self.presentation.is_some()
+                    || self.layout() != Layout::Continuous
+                    || self
+                        .last_input
+                        .get()
+                        .is_some_and(|input| input.elapsed() < READING_SCROLL_PAUSE);
+                if let Some(reading_scroll) = &mut self.reading_scroll {
+                    reading_scroll.velocity =
+                        Vector::new(0.0, f32::from(self.flags.config.reading_speed));
+                    // Steps while paused are dropped so scrolling continues smoothly
+                    let offset = reading_scroll.step(now);
+                    if !paused {
+                        return self.pan(offset);
+                    }
+                }
+            }
+            Message::Resume(resume) => {
+                self.flags.config.resume = resume;
+                self.save_config();
+            }
+            Message::ResumeReading(resume) => {
+                if let Some(last_read) = self.resume_prompt.take() {
+                    if resume {
+                        self.resume(last_read);
+                    }
+                }
+                return self.page_changed();
+            }
+            Message::Redo => {
+                let current = self.history.next_redo().map(|edit| self.edit_state(edit));
+                if let Some(edit) = current.and_then(|current| self.history.redo(current)) {
+                    self.restore_edit(edit);
+                }
+            }
+            Message::Undo => {
+                let current = self.history.next_undo().map(|edit| self.edit_state(edit));
+                if let Some(edit) = current.and_then(|current| self.history.undo(current)) {
+                    self.restore_edit(edit);
+                }
+            }
+            Message::ReadingSpeed(speed) => {
+                self.flags.config.reading_speed = speed;
+                self.save_config();
+            }
+            Message::Reflow(reflow) => {
+                self.flags.config.reflow = reflow;
+                self.save_config();
+                // Books are laid out when loaded, so an open book is loaded again
+                if format::is_epub(&self.flags.path) {
+                    return self.leave(Leave::Reopen);
+                }
+            }
+            Message::ArrowPages(arrow_pages) => {
+                self.flags.config.arrow_pages = arrow_pages;
+                self.save_config();
+            }
+            Message::ReduceMotion(reduce_motion) => {
+                self.flags.config.reduce_motion = reduce_motion;
+                self.save_config();
+                let transitions = self.transitions();
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.transitions = transitions;
+                }
+            }
+            Message::RememberView => {
+                if self.remembers_view() {
+                    self.flags.state.documents.remove(&self.flags.key);
+                    self.save_state();
+                } else {
+                    // Once the document is in the state, view_changed saves its view
+                    self.flags
+                        .state
+                        .documents
+                        .insert(self.flags.key.clone(), DocumentView::default());
+                    self.view_changed();
+                }
+            }
+            Message::TrimMargins => {
+                self.flags.config.trim_margins = !self.flags.config.trim_margins;
+                self.save_config();
+                for position in 0..self.page_ids.len() as u16 {
+                    self.update_page_size(position);
+                }
+                self.scroll_to_page();
+                self.canvas_cache.clear();
+            }
+            Message::SplitView => {
+                // The compared document is kept to show again when the view is split again
+                if self.split_view {
+                    self.split_view = false;
+                    if self.pane_focus == Some(Pane::Compare) {
+                        self.pane_focus = Some(Pane::Page);
+                    }
+                    self.canvas_cache.clear();
+                    return self.page_changed();
+                }
+                if self.compare_ids.is_empty() {
+                    return self.set_compare_doc(self.flags.compare.clone());
+                }
+                self.split_view = true;
+                self.compare_position = self.position();
+                return self.page_changed();
+            }
+            Message::LockPaneZoom => {
+                self.pane_zoom_lock = !self.pane_zoom_lock;
+                if self.pane_zoom_lock {
+                    self.compare_zoom = 1.0;
+                    self.canvas_cache.clear();
+                }
+            }
+            Message::OpenSecondPane => {
+                let reflow = self.flags.config.reflow;
+                return Task::perform(
+                    async move {
+                        let response = file_chooser::open::Dialog::new()
+                            .title(fl!("open-second-pane"))
+                            .open_file()
+                            .await;
+                        let path = match response {
+                            Ok(response) => response
+                                .url()
+                                .to_file_path()
+                                .map_err(|()| fl!("not-a-local-file"))?,
+                            Err(file_chooser::Error::Cancelled) => return Ok(None),
+                            Err(err) => return Err(err.to_string()),
+                        };
+                        tokio::task::spawn_blocking(move || format::load(&path, reflow))
+                            .await
+                            .map_err(|err| err.to_string())?
+                            .map(|doc| Some(Arc::new(doc)))
+                    },
+                    |result| cosmic::app::Message::App(Message::CompareOpened(result)),
+                );
+            }
+            Message::SyncPanes => {
+                self.compare_sync = !self.compare_sync;
+                self.compare_position = self.position();
+            }
+            Message::ViewLock => {
+                self.view_lock = match self.view_lock {
+                    Some(_) => None,
+                    None => self.view_size(),
+                };
+                self.canvas_cache.clear();
+            }
+            Message::Conformance(conformance) => {
+                self.conformance = Some(conformance);
+            }
+            Message::CopyLink => {
+                self.status = Some((fl!("link-copied"), Instant::now()));
+                self.canvas_cache.clear();
+                return clipboard::write(self.page_link());
+            }
+            Message::Bookmark => {
+                let position = self.position();
+                let bookmarks = &mut self.flags.state.bookmarks;
+                let document = bookmarks.entry(self.flags.key.clone()).or_default();
+                if !document.remove(&position) {
+                    document.insert(position);
+                }
+                if document.is_empty() {
+                    bookmarks.remove(&self.flags.key);
+                }
+                self.save_state();
+                self.update_bookmarks_model();
+            }
+            Message::CoverPage => {
+                let cover_pages = &mut self.flags.state.cover_pages;
+                if !cover_pages.remove(&self.flags.key) {
+                    cover_pages.insert(self.flags.key.clone());
+                }
+                self.save_state();
+                self.canvas_cache.clear();
+                // Pairs of pages change, so others may be shown
+                return self.load_layout_pages();
+            }
+            Message::RightToLeft => {
+                let right_to_left = !self.right_to_left();
+                self.flags
+                    .state
+                    .right_to_left
+                    .insert(self.flags.key.clone(), right_to_left);
+                self.save_state();
+                self.canvas_cache.clear();
+            }
+            Message::Rotate(degrees) => {
+                self.rotate(self.position(), degrees);
+                self.canvas_cache.clear();
+            }
+            Message::RotateAll(degrees) => {
+                for position in 0..self.page_ids.len() as u16 {
+                    self.rotate(position, degrees);
+                }
+                self.canvas_cache.clear();
+            }
+            Message::DarkenText(darken_text) => {
+                self.flags.config.darken_text = darken_text;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::SavePower(save_power) => {
+                self.flags.config.save_power = save_power;
+                self.save_config();
+                self.on_battery = power::on_battery();
+                let transitions = self.transitions();
+                if let Some(presentation) = &mut self.presentation {
+                    presentation.transitions = transitions;
+                }
+            }
+            Message::SharpPixels(sharp_pixels) => {
+                self.flags.config.sharp_pixels = sharp_pixels;
+                self.save_config();
+                self.canvas_cache.clear();
+            }
+            Message::ScaleFactor(scale_factor) => {
+                self.scale_factor = scale_factor;
+                self.canvas_cache.clear();
+            }
+            Message::ScreenDpiInput(input) => {
+                // Clearing the DPI goes back to the automatic value
+                let screen_dpi = match input.trim() {
+                    "" => Some(None),
+                    dpi => dpi.parse::<u16>().ok().filter(|&dpi| dpi > 0).map(Some),
+                };
+                if let Some(screen_dpi) = screen_dpi {
+                    self.flags.config.screen_dpi = screen_dpi;
+                    self.save_config();
+                    self.canvas_cache.clear();
+                }
+                self.screen_dpi_input = input;
+            }
+            Message::SearchIndex(index) => {
+                if let Some(search) = &self.search {
+                    self.search_results = index.search(search);
+                }
+                self.search_index = Some(index);
+            }
+            Message::SearchInput(input) => {
+                self.search_results = match &self.search_index {
+                    Some(index) => index.search(&input),
+                    None => Vec::new(),
+                };
+                self.search = Some(input);
+                self.search_match = None;
+                self.canvas_cache.clear();
+            }
+            Message::SearchNext => {
+                let position = self.position();
+                let matches = self.page_search_matches(position);
+                let next = match self.selected_match(position, matches) {
+                    Some(index) => index + 1,
+                    None => 0,
+                };
+                if next < matches {
+                    self.search_match = Some((position, next));
+                    self.canvas_cache.clear();
+                } else if let Some(&page) = self
+                    .search_results
+                    .iter()
+                    .find(|&&result| result > position)
+                    .or_else(|| self.search_results.first())
+                {
+                    // Wrap around to the first result after the last one
+                    self.search_match = Some((page, 0));
+                    return self.activate_position(page);
+                }
+            }
+            Message::SearchPrev => {
+                let position = self.position();
+                let matches = self.page_search_matches(position);
+                let prev = match self.selected_match(position, matches) {
+                    Some(index) => index.checked_sub(1),
+                    None => matches.checked_sub(1),
+                };
+                if let Some(prev) = prev {
+                    self.search_match = Some((position, prev));
+                    self.canvas_cache.clear();
+                } else if let Some(&page) = self
+                    .search_results
+                    .iter()
+                    .rev()
+                    .find(|&&result| result < position)
+                    .or_else(|| self.search_results.last())
+                {
+                    // The last match, as the page may not be loaded to count them yet
+                    self.search_match = Some((page, usize::MAX));
+                    return self.activate_position(page);
+                }
+            }
+            Message::ScrollScreen(screen) => {
+                return self.scroll_screen(screen);
+            }
+            Message::ScrollStep(step, screen) => {
+                return self.scroll_step(step, screen);
+            }
+            Message::StampImage(input) => {
+                self.history.push(EditKind::StampImage, self.export_edit());
+                let path = input.trim();
+                self.stamp.image = (!path.is_empty()).then(|| PathBuf::from(path));
+                self.stamp_image_input = input;
+            }
+            Message::StampOpacity(opacity) => {
+                self.history
+                    .push(EditKind::StampOpacity, self.export_edit());
+                self.stamp.opacity = opacity;
+            }
+            Message::StampPosition(position) => {
+                self.history
+                    .push(EditKind::StampPosition, self.export_edit());
+                self.stamp.position = position;
+            }
+            Message::StampRotation(rotation) => {
+                self.history
+                    .push(EditKind::StampRotation, self.export_edit());
+                self.stamp.rotation = rotation;
+            }
+            Message::StampText(text) => {
+                self.history.push(EditKind::StampText, self.export_edit());
+                self.stamp.text = text;
+            }
+            Message::Statistics(statistics) => {
+                self.statistics = Some(statistics);
+            }
+            Message::StateTimeout => {
+                if self
+                    .state_changed
+                    .is_some_and(|changed_at| changed_at.elapsed() >= STATE_SAVE_DELAY)
+                {
+                    self.state_changed = None;
+                    self.save_state();
+                }
+            }
+            Message::StatusTimeout => {
+                if self
+                    .page_number
+                    .as_ref()
+                    .is_some_and(|(_, typed_at)| typed_at.elapsed() >= STATUS_TIMEOUT)
+                {
+                    self.page_number = None;
+                }
+                if self
+                    .picked_color
+                    .is_some_and(|(_, picked_at)| picked_at.elapsed() >= STATUS_TIMEOUT)
+                {
+                    self.picked_color = None;
+                }
+                if self
+                    .status
+                    .as_ref()
+                    .is_some_and(|(_, shown_at)| shown_at.elapsed() >= STATUS_TIMEOUT)
+                {
+                    self.status = None;
+                }
+            }
+            Message::SidebarSelect(position) => {
+                let id = self
+                    .nav_model()
+                    .and_then(|model| model.iter().nth(position));
+                if let Some(id) = id {
+                    return self.on_nav_select(id);
+                }
+            }
+            Message::SidebarWidth(width) => {
+                self.flags.state.sidebar_width = width.clamp(SIDEBAR_WIDTH_MIN, SIDEBAR_WIDTH_MAX);
+                self.state_changed = Some(Instant::now());
+            }
+            Message::Sidebar(sidebar) => {
+                self.flags.state.sidebar = sidebar;
+                self.flags.state.show_sidebar = true;
+                if self.presentation.is_none() {
+                    self.core.nav_bar_set_toggled(true);
+                }
+                self.save_state();
+            }
+            Message::SwipeDrag(offset) => {
+                // Resist swiping past the first or last page
+                let edge = if (offset > 0.0) != self.right_to_left() {
+                    self.prev_position().is_none()
+                } else {
+                    self.next_position().is_none()
+                };
+                self.swipe = Some(Swipe::dragging(if edge {
+                    offset * gesture::EDGE_RESISTANCE
+                } else {
+                    offset
+                }));
+                self.canvas_cache.clear();
+            }
+            Message::SwipeEnd(offset, width) => {
+                self.canvas_cache.clear();
+                let position = if offset.abs() > width * gesture::SWIPE_THRESHOLD {
+                    // Right to left documents go forward by swiping right
+                    if (offset < 0.0) != self.right_to_left() {
+                        self.next_position()
+                    } else {
+                        self.prev_position()
+                    }
+                } else {
+                    None
+                };
+                if self.reduce_motion() {
+                    self.swipe = None;
+                    return match position {
+                        Some(position) => self.activate_position(position),
+                        None => Task::none(),
+                    };
+                }
+                match position {
+                    Some(position) => {
+                        // The new page slides in from the side the finger moved away from
+                        let offset = if offset < 0.0 {
+                            offset + width
+                        } else {
+                            offset - width
+                        };
+                        self.swipe = Some(Swipe::released(offset));
+                        return self.activate_position(position);
+                    }
+                    None => {
+                        let offset = self.swipe.as_ref().map_or(0.0, |swipe| swipe.offset());
+                        self.swipe = Some(Swipe::released(offset));
+                    }
+                }
+            }
+            Message::SwipeTick => {
+                self.canvas_cache.clear();
+                if self.swipe.as_ref().is_some_and(|swipe| swipe.finished()) {
+                    self.swipe = None;
+                }
+            }
+            Message::Tick => {}
+            Message::ViewAnimationTick => {
+                self.canvas_cache.clear();
+                if self.fade.as_ref().is_some_and(Ease::finished) {
+                    self.fade = None;
+                }
+                if self.zoom_ease.as_ref().is_some_and(Ease::finished) {
+                    self.zoom_ease = None;
+                }
+            }
+            Message::Wheel(wheel) => {
+                self.flags.config.wheel = wheel;
+                self.save_config();
+            }
+        }
+        Task::none()
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        let mut subscriptions = Vec::new();
+        if self.page_number.is_some() || self.picked_color.is_some() || self.status.is_some() {
+            subscriptions
+                .push(time::every(Duration::from_millis(250)).map(|_| Message::StatusTimeout));
+        }
+        if self.state_changed.is_some() {
+            subscriptions
+                .push(time::every(Duration::from_millis(250)).map(|_| Message::StateTimeout));
+        }
+        if self.autoscroll.is_some() {
+            subscriptions.push(self.frames().map(Message::AutoscrollTick));
+        }
+        if self.reading_scroll.is_some() {
+            subscriptions.push(self.frames().map(Message::ReadingScrollTick));
+        }
+        if self.fling.is_some() {
+            subscriptions.push(self.frames().map(Message::FlingTick));
+        }
+        if self.warming.is_none() && !self.save_power() && self.warm_page().is_some() {
+            subscriptions.push(time::every(WARM_IDLE / 4).map(|_| Message::WarmCache));
+        }
+        if self.fade.is_some() || self.zoom_ease.is_some() {
+            subscriptions.push(self.frames().map(|_| Message::ViewAnimationTick));
+        }
+        if self.swipe.as_ref().is_some_and(|swipe| swipe.animating()) {
+            subscriptions.push(self.frames().map(|_| Message::SwipeTick));
+        }
+        if let Some(presentation) = &self.presentation {
+            if presentation.auto_advancing() && !presentation.advance_waiting() {
+                subscriptions.push(
+                    time::every(Duration::from_millis(250)).map(|_| Message::PresentationTick),
+                );
+            }
+            if presentation.transitioning() {
+                subscriptions.push(self.frames().map(|_| Message::Tick));
+            }
+            if presentation.show_timer {
+                // Redraw the timer overlay every second
+                subscriptions.push(time::every(Duration::from_secs(1)).map(|_| Message::Tick));
+            }
+        }
+        if self.flags.config.save_power {
+            subscriptions.push(time::every(POWER_POLL).map(|_| Message::PowerCheck));
+        }
+        if let Some(listener) = &self.flags.listener {
+            subscriptions.push(instance::subscription(listener.clone()).map(Message::Forwarded));
+        }
+        Subscription::batch(subscriptions)
+    }
+
+    fn view(&self) -> Element<Message> {
+        let canvas = canvas::Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Fill);
+        if self.signatures.is_empty() || self.presentation.is_some() {
+            return canvas.into();
+        }
+        // Signed documents say so above the pages
+        let banner = widget::row::with_children(vec![
+            widget::text::body(fl!("signed-banner", signatures = self.signatures.len())).into(),
+            widget::horizontal_space().into(),
+            widget::button::text(fl!("details"))
+                .on_press(Message::ToggleContextPage(ContextPage::Signatures))
+                .into(),
+        ])
+        .align_y(Vertical::Center)
+        .padding(8);
+        widget::column::with_children(vec![banner.into(), canvas.into()]).into()
     }
 }
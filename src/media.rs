@@ -0,0 +1,43 @@
+use lopdf::Document;
+use std::{io::Write, path::Path, process::Command};
+
+use crate::{pdf, runtime};
+
+/// Schemes of linked media played from the web, others such as file: could run programs
+const SCHEMES: [&str; 2] = ["http", "https"];
+
+/// Play media with the default application for its type, embedded files are copied out first
+//TODO: play media on the page with an embedded player
+pub fn play(doc: &Document, media: &pdf::Media, doc_path: &Path) -> Result<(), String> {
+    let target = match media.embedded {
+        Some(id) => {
+            let data = pdf::embedded_file(doc, id).map_err(|err| err.to_string())?;
+            // Only the file name is used, so names cannot write outside of the directory, and
+            // a number is added when media of another document has the same name
+            let name = Path::new(&media.name)
+                .file_name()
+                .map_or_else(|| "media".into(), |name| name.to_string_lossy());
+            let (path, mut file) = runtime::create(&name).map_err(|err| err.to_string())?;
+            file.write_all(&data).map_err(|err| err.to_string())?;
+            path.into_os_string()
+        }
+        // Linked media may be on the web
+        None if media.name.contains("://") => {
+            let scheme = media.name.split_once(':').map_or("", |(scheme, _)| scheme);
+            if !SCHEMES.iter().any(|x| x.eq_ignore_ascii_case(scheme)) {
+                return Err(format!("unsupported media link {:?}", media.name));
+            }
+            media.name.clone().into()
+        }
+        None => doc_path
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(&media.name)
+            .into_os_string(),
+    };
+    Command::new("xdg-open")
+        .arg(target)
+        .spawn()
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
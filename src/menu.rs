@@ -0,0 +1,234 @@
+use cosmic::{
+    widget::menu::{self, key_bind::KeyBind, ItemHeight, ItemWidth},
+    Element,
+};
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::{config::Sidebar, fl, key_bind::Action, layout::Layout, pdf::Fit, Message};
+
+pub fn menu_bar<'a>(
+    key_binds: &HashMap<KeyBind, Action>,
+    zoom_presets: &[u16],
+    recent: &[PathBuf],
+    layout: Layout,
+    fit: Fit,
+    remember_view: bool,
+    view_lock: bool,
+    trim_margins: bool,
+    rulers: bool,
+    grid: bool,
+    night_mode: bool,
+    reading_scroll: bool,
+    right_to_left: bool,
+    cover_page: bool,
+    bookmarked: bool,
+    sidebar: Option<Sidebar>,
+    split_view: bool,
+    sync_panes: bool,
+    pane_zoom_lock: bool,
+) -> Element<'a, Message> {
+    menu::bar(vec![
+        menu::Tree::with_children(
+            menu::root(fl!("file")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Button(fl!("new-instance"), Action::NewInstance),
+                    menu::Item::Button(fl!("open-menu"), Action::Open),
+                    // The first recent document is this one
+                    menu::Item::Folder(
+                        fl!("open-recent"),
+                        recent
+                            .iter()
+                            .enumerate()
+                            .skip(1)
+                            .map(|(index, path)| {
+                                let name = path.file_name().unwrap_or(path.as_os_str());
+                                menu::Item::Button(
+                                    name.to_string_lossy().into_owned(),
+                                    Action::OpenRecent(index),
+                                )
+                            })
+                            .collect(),
+                    ),
+                    menu::Item::Button(fl!("compare-with-menu"), Action::CompareWith),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("save"), Action::Save),
+                    menu::Item::Button(fl!("export-menu"), Action::Export),
+                    menu::Item::Button(fl!("export-image-menu"), Action::ExportImage),
+                    menu::Item::Button(fl!("export-svg-menu"), Action::ExportSvg),
+                    menu::Item::Button(fl!("print-menu"), Action::Print),
+                    menu::Item::Button(fl!("share-menu"), Action::Share),
+                    menu::Item::Button(fl!("statistics-menu"), Action::Statistics),
+                    menu::Item::Button(fl!("signatures-menu"), Action::Signatures),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("quit"), Action::Quit),
+                ],
+            ),
+        ),
+        menu::Tree::with_children(
+            menu::root(fl!("edit")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Button(fl!("undo"), Action::Undo),
+                    menu::Item::Button(fl!("redo"), Action::Redo),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("find-menu"), Action::Find),
+                    menu::Item::Button(fl!("find-next"), Action::FindNext),
+                    menu::Item::Button(fl!("find-previous"), Action::FindPrev),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("copy-link"), Action::CopyLink),
+                    menu::Item::Button(fl!("add-note"), Action::AddNote),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("settings-menu"), Action::Settings),
+                ],
+            ),
+        ),
+        menu::Tree::with_children(
+            menu::root(fl!("view")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Button(fl!("zoom-in"), Action::ZoomIn),
+                    menu::Item::Button(fl!("zoom-out"), Action::ZoomOut),
+                    menu::Item::Button(fl!("reset-zoom"), Action::ZoomReset),
+                    menu::Item::Folder(
+                        fl!("zoom"),
+                        zoom_presets
+                            .iter()
+                            .map(|&percent| {
+                                let action = Action::ZoomPreset(percent);
+                                menu::Item::Button(action.name(), action)
+                            })
+                            .collect(),
+                    ),
+                    menu::Item::Divider,
+                    menu::Item::CheckBox(
+                        Fit::ActualSize.name(),
+                        fit == Fit::ActualSize,
+                        Action::ActualSize,
+                    ),
+                    menu::Item::CheckBox(Fit::Page.name(), fit == Fit::Page, Action::FitPage),
+                    menu::Item::CheckBox(Fit::Width.name(), fit == Fit::Width, Action::FitWidth),
+                    menu::Item::Divider,
+                    menu::Item::CheckBox(
+                        Layout::Single.name(),
+                        layout == Layout::Single,
+                        Action::LayoutSingle,
+                    ),
+                    menu::Item::CheckBox(
+                        Layout::Continuous.name(),
+                        layout == Layout::Continuous,
+                        Action::LayoutContinuous,
+                    ),
+                    menu::Item::CheckBox(
+                        Layout::Facing.name(),
+                        layout == Layout::Facing,
+                        Action::LayoutFacing,
+                    ),
+                    menu::Item::CheckBox(
+                        fl!("right-to-left-pages"),
+                        right_to_left,
+                        Action::RightToLeft,
+                    ),
+                    menu::Item::CheckBox(fl!("cover-page"), cover_page, Action::CoverPage),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("rotate-left"), Action::RotateLeft),
+                    menu::Item::Button(fl!("rotate-right"), Action::RotateRight),
+                    menu::Item::Button(fl!("rotate-all-left"), Action::RotateAllLeft),
+                    menu::Item::Button(fl!("rotate-all-right"), Action::RotateAllRight),
+                    menu::Item::Divider,
+                    menu::Item::CheckBox(
+                        fl!("remember-for-document"),
+                        remember_view,
+                        Action::RememberView,
+                    ),
+                    menu::Item::CheckBox(fl!("lock-view"), view_lock, Action::ViewLock),
+                    menu::Item::CheckBox(fl!("trim-margins"), trim_margins, Action::TrimMargins),
+                    menu::Item::Divider,
+                    menu::Item::CheckBox(fl!("rulers"), rulers, Action::ToggleRulers),
+                    menu::Item::CheckBox(fl!("grid"), grid, Action::ToggleGrid),
+                    menu::Item::CheckBox(fl!("night-mode"), night_mode, Action::NightMode),
+                    menu::Item::Divider,
+                    menu::Item::CheckBox(fl!("sidebar"), sidebar.is_some(), Action::ToggleSidebar),
+                    menu::Item::CheckBox(
+                        fl!("pages"),
+                        sidebar == Some(Sidebar::Pages),
+                        Action::SidebarPages,
+                    ),
+                    menu::Item::CheckBox(
+                        fl!("outline"),
+                        sidebar == Some(Sidebar::Outline),
+                        Action::SidebarOutline,
+                    ),
+                    menu::Item::CheckBox(
+                        fl!("structure"),
+                        sidebar == Some(Sidebar::Structure),
+                        Action::SidebarStructure,
+                    ),
+                    menu::Item::CheckBox(
+                        fl!("bookmarks"),
+                        sidebar == Some(Sidebar::Bookmarks),
+                        Action::SidebarBookmarks,
+                    ),
+                    menu::Item::CheckBox(
+                        fl!("annotations"),
+                        sidebar == Some(Sidebar::Annotations),
+                        Action::SidebarAnnotations,
+                    ),
+                    menu::Item::Button(fl!("layers-menu"), Action::Layers),
+                    menu::Item::Divider,
+                    menu::Item::CheckBox(fl!("split-view"), split_view, Action::SplitView),
+                    menu::Item::CheckBox(fl!("sync-panes"), sync_panes, Action::SyncPanes),
+                    menu::Item::CheckBox(
+                        fl!("lock-pane-zoom"),
+                        pane_zoom_lock,
+                        Action::LockPaneZoom,
+                    ),
+                    menu::Item::Button(fl!("open-second-pane-menu"), Action::OpenSecondPane),
+                    menu::Item::Divider,
+                    menu::Item::CheckBox(
+                        fl!("reading-scroll"),
+                        reading_scroll,
+                        Action::ReadingScroll,
+                    ),
+                    menu::Item::Button(fl!("pick-color"), Action::PickColor),
+                    menu::Item::Button(fl!("present"), Action::Present),
+                ],
+            ),
+        ),
+        menu::Tree::with_children(
+            menu::root(fl!("go")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Button(fl!("previous-page"), Action::PagePrev),
+                    menu::Item::Button(fl!("next-page"), Action::PageNext),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("first-page"), Action::PageFirst),
+                    menu::Item::Button(fl!("last-page"), Action::PageLast),
+                    menu::Item::Button(fl!("go-to-page-menu"), Action::GoToPage),
+                    menu::Item::CheckBox(fl!("bookmark-page"), bookmarked, Action::Bookmark),
+                    menu::Item::Divider,
+                    menu::Item::Button(fl!("previous-document"), Action::DocumentPrev),
+                    menu::Item::Button(fl!("next-document"), Action::DocumentNext),
+                ],
+            ),
+        ),
+        menu::Tree::with_children(
+            menu::root(fl!("help")),
+            menu::items(
+                key_binds,
+                vec![
+                    menu::Item::Button(fl!("keyboard-shortcuts-menu"), Action::KeyboardShortcuts),
+                    menu::Item::Button(fl!("about"), Action::About),
+                ],
+            ),
+        ),
+    ])
+    .item_height(ItemHeight::Dynamic(40))
+    .item_width(ItemWidth::Uniform(240))
+    .spacing(4.0)
+    .into()
+}
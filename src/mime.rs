@@ -0,0 +1,61 @@
+use std::{env, path::PathBuf, process::Command};
+
+/// Types of documents opened by the reader, PDF first
+pub const MIME_TYPES: [&str; 5] = [
+    "application/pdf",
+    "application/x-pdf",
+    "application/vnd.comicbook+zip",
+    "application/x-cbz",
+    "application/vnd.comicbook-rar",
+];
+
+/// Whether the desktop entry is installed in one of the XDG data directories, as making a
+/// missing entry the default would leave documents opening with nothing
+pub fn is_installed(desktop_id: &str) -> bool {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")));
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from))
+        .any(|dir| dir.join("applications").join(desktop_id).is_file())
+}
+
+/// Whether the desktop entry opens PDF documents by default, none if xdg-mime is unavailable
+/// or the entry is not installed, so it cannot be made the default
+pub fn is_default(desktop_id: &str) -> Option<bool> {
+    if !is_installed(desktop_id) {
+        return None;
+    }
+    let output = Command::new("xdg-mime")
+        .args(["query", "default", MIME_TYPES[0]])
+        .output()
+        .map_err(|err| log::warn!("failed to query default application: {}", err))
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim() == desktop_id)
+}
+
+/// Make the desktop entry the default for opening PDF documents
+pub fn set_default(desktop_id: &str) -> Result<(), String> {
+    if !is_installed(desktop_id) {
+        return Err(format!("{desktop_id} is not installed"));
+    }
+    let output = Command::new("xdg-mime")
+        .arg("default")
+        .arg(desktop_id)
+        .args(MIME_TYPES)
+        .output()
+        .map_err(|err| err.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
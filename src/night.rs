@@ -0,0 +1,31 @@
+use cosmic::iced::{widget::canvas, Color};
+
+/// Color with its lightness inverted, so white becomes black and black becomes white while hue
+/// and saturation stay the same, unlike inverting each channel which turns red into cyan
+pub fn color(color: Color) -> Color {
+    let max = color.r.max(color.g).max(color.b);
+    let min = color.r.min(color.g).min(color.b);
+    // Shifting every channel by the same amount keeps their differences, which set hue and
+    // saturation, and moves lightness, the mean of max and min, to one minus itself
+    let shift = 1.0 - max - min;
+    Color {
+        r: color.r + shift,
+        g: color.g + shift,
+        b: color.b + shift,
+        a: color.a,
+    }
+}
+
+/// Fill or stroke style with its colors inverted like [`color`]
+pub fn style(style: &canvas::Style) -> canvas::Style {
+    match style {
+        canvas::Style::Solid(solid) => canvas::Style::Solid(color(*solid)),
+        canvas::Style::Gradient(canvas::Gradient::Linear(linear)) => {
+            let mut linear = *linear;
+            for stop in linear.stops.iter_mut().flatten() {
+                stop.color = color(stop.color);
+            }
+            canvas::Style::Gradient(canvas::Gradient::Linear(linear))
+        }
+    }
+}
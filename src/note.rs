@@ -0,0 +1,189 @@
+use cosmic::iced::{Point, Rectangle, Size};
+use lopdf::{
+    decode_text_string, dictionary, Document, IncrementalDocument, Object, ObjectId, StringFormat,
+};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::pdf;
+
+/// Size of the icon of a new note, in PDF units, as other readers place them
+pub const SIZE: f32 = 20.0;
+
+/// Text annotation, shown as an icon on the page and opened to read or edit
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    /// Annotation the note was read from or will be saved as, none for new notes until they are
+    /// given one when saving
+    pub id: Option<ObjectId>,
+    /// Area of the icon, in PDF units
+    pub rect: Rectangle,
+    pub contents: String,
+    pub author: String,
+}
+
+impl Note {
+    /// New note with the top left corner of its icon at a point in PDF units
+    pub fn new(point: Point, author: String) -> Self {
+        Self {
+            id: None,
+            rect: Rectangle::new(Point::new(point.x, point.y - SIZE), Size::new(SIZE, SIZE)),
+            contents: String::new(),
+            author,
+        }
+    }
+}
+
+/// Text annotations of a page
+pub fn page_notes(doc: &Document, page_id: ObjectId) -> Vec<Note> {
+    let mut notes = Vec::new();
+    for object in annotations(doc, page_id) {
+        let Ok(id) = object.as_reference() else {
+            continue;
+        };
+        let Ok(annotation) = doc.get_dictionary(id) else {
+            continue;
+        };
+        if annotation.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Text") {
+            continue;
+        }
+        let Some(rect) = annotation.get(b"Rect").ok().and_then(|x| pdf::rect(doc, x)) else {
+            continue;
+        };
+        let text = |key: &[u8]| {
+            annotation
+                .get_deref(key, doc)
+                .ok()
+                .and_then(|x| decode_text_string(x).ok())
+                .unwrap_or_default()
+        };
+        notes.push(Note {
+            id: Some(id),
+            rect,
+            contents: text(b"Contents"),
+            author: text(b"T"),
+        });
+    }
+    notes
+}
+
+/// Copy of a document with the notes of the given pages in place of their text annotations,
+/// appended to the document as an incremental update, so signatures of the bytes before it stay
+/// intact
+pub fn save(
+    doc: &Document,
+    notes: &HashMap<ObjectId, Vec<Note>>,
+    path: &Path,
+) -> Result<Document, lopdf::Error> {
+    let mut doc = doc.clone();
+    // Objects of the update, the changed pages and their notes
+    let mut changed = Vec::new();
+    for (&page_id, notes) in notes.iter() {
+        let mut kept = Vec::new();
+        let mut removed = Vec::new();
+        for object in annotations(&doc, page_id) {
+            let subtype = object
+                .as_reference()
+                .and_then(|id| doc.get_dictionary(id))
+                .and_then(|annotation| annotation.get(b"Subtype"))
+                .and_then(Object::as_name)
+                .ok();
+            let id = object.as_reference().ok();
+            let noted = id.is_some_and(|id| notes.iter().any(|note| note.id == Some(id)));
+            if subtype == Some(b"Text") && !noted {
+                removed.extend(id);
+            } else {
+                kept.push(object);
+            }
+        }
+        // Popups of removed notes would be left showing nothing
+        kept.retain(|object| {
+            let parent = object
+                .as_reference()
+                .and_then(|id| doc.get_dictionary(id))
+                .and_then(|annotation| annotation.get(b"Parent"))
+                .and_then(Object::as_reference);
+            !parent.is_ok_and(|parent| removed.contains(&parent))
+        });
+
+        for note in notes.iter() {
+            let rect = note.rect;
+            let rect = vec![
+                Object::Real(rect.x),
+                Object::Real(rect.y),
+                Object::Real(rect.x + rect.width),
+                Object::Real(rect.y + rect.height),
+            ];
+            let annotation = dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Text",
+                "Rect" => rect.clone(),
+                "Contents" => text_string(&note.contents),
+                "T" => text_string(&note.author),
+                "Name" => "Comment",
+                "P" => page_id,
+            };
+            let id = match note.id {
+                Some(id) => match doc.get_object_mut(id).and_then(Object::as_dict_mut) {
+                    Ok(annotation) => {
+                        annotation.set("Rect", rect);
+                        annotation.set("Contents", text_string(&note.contents));
+                        id
+                    }
+                    // Notes are given the ids they will be saved as before their annotation exists
+                    Err(_) => {
+                        doc.set_object(id, annotation);
+                        doc.max_id = doc.max_id.max(id.0);
+                        kept.push(Object::Reference(id));
+                        id
+                    }
+                },
+                None => {
+                    let id = doc.add_object(annotation);
+                    kept.push(Object::Reference(id));
+                    id
+                }
+            };
+            changed.push(id);
+        }
+        doc.get_object_mut(page_id)
+            .and_then(Object::as_dict_mut)?
+            .set("Annots", kept);
+        changed.push(page_id);
+    }
+    let mut update = IncrementalDocument::load(path)?;
+    for id in changed {
+        update
+            .new_document
+            .set_object(id, doc.get_object(id)?.clone());
+    }
+    update.new_document.max_id = doc.max_id;
+    // Write next to the document and rename, so a failed save leaves the document as it was
+    let partial = path.with_extension("partial");
+    update.save(&partial)?;
+    // The document keeps its permissions rather than those of a new file
+    fs::set_permissions(&partial, fs::metadata(path)?.permissions())?;
+    fs::rename(&partial, path)?;
+    Ok(doc)
+}
+
+/// References to the annotations of a page, which may be given directly or by reference
+fn annotations(doc: &Document, page_id: ObjectId) -> Vec<Object> {
+    doc.get_dictionary(page_id)
+        .and_then(|page| page.get_deref(b"Annots", doc))
+        .and_then(Object::as_array)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// PDF text string, in UTF-16 if it is not ASCII
+fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        Object::string_literal(text)
+    } else {
+        let mut bytes = vec![0xfe, 0xff];
+        for unit in text.encode_utf16() {
+            bytes.extend(unit.to_be_bytes());
+        }
+        Object::String(bytes, StringFormat::Hexadecimal)
+    }
+}
@@ -5,28 +5,35 @@ use cosmic::{
             cosmic_text::{self, fontdb, Attrs, AttrsOwned, FamilyOwned, Stretch, Style, Weight},
         },
         alignment::{Horizontal, Vertical},
-        keyboard,
+        keyboard, mouse,
         widget::{
             canvas::{
                 self,
-                path::lyon_path::geom::euclid::{Point2D, Transform2D, UnknownUnit, Vector2D},
+                path::lyon_path::{
+                    geom::euclid::{Point2D, Transform2D, UnknownUnit, Vector2D},
+                    Event,
+                },
             },
             image,
             text::{LineHeight, Shaping},
         },
         Color, Font, Pixels, Point, Rectangle, Size, Vector,
     },
+    iced_core::mouse::Click,
     iced_renderer::geometry::Frame,
 };
-use lopdf::{Dictionary, Document, Encoding, Object, ObjectId};
+use lopdf::{decode_text_string, Dictionary, Document, Encoding, Object, ObjectId};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::{BTreeMap, HashMap},
+    cell::Cell,
+    collections::{BTreeMap, HashMap, HashSet},
     error::Error,
     mem, str,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use crate::text::Text;
+use crate::{fl, layer, layout::Layout, text::Text};
 
 type Transform = Transform2D<f32, UnknownUnit, UnknownUnit>;
 
@@ -59,12 +66,21 @@ impl<'a> Default for GraphicsState<'a> {
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct Image {
     pub name: String,
     pub rect: Rectangle,
     pub handle: image::Handle,
 }
 
+/// Text shown by one text operator, for finding it on the page
+#[derive(Clone, Debug)]
+pub struct TextRun {
+    pub content: String,
+    /// Box around the text from its descent to its ascent, in PDF units with y up
+    pub rect: Rectangle,
+}
+
 #[derive(Clone, Debug)]
 struct TextState {
     cursor_tf: Transform,
@@ -87,21 +103,93 @@ impl Default for TextState {
     }
 }
 
+#[derive(Default)]
 pub struct CanvasState {
+    pub modifiers: keyboard::Modifiers,
+    /// Space is held to pan by dragging with the left button
+    pub space: bool,
+    /// The page was dragged while space was held, so releasing it does not run its action
+    pub space_panned: bool,
+    pub drag: Option<Drag>,
+    pub last_click: Option<Click>,
+    pub touch: Option<crate::gesture::Touch>,
+    /// A second finger turns the touch into a two finger gesture
+    pub two_finger: Option<crate::gesture::TwoFinger>,
+    /// Pixels scrolled toward changing pages, for scroll wheel page turning with touchpads
+    pub wheel_pages: f32,
+    /// Whether the theme was dark when last drawn, to redraw pages when it changes
+    pub dark: Cell<Option<bool>>,
+    /// Window position of the middle-click that started autoscrolling
+    pub autoscroll: Option<Point>,
+    /// Width of the sidebar less the window x of the cursor dragging its edge, while resizing it
+    pub sidebar_resize: Option<f32>,
+}
+
+/// Panning by dragging the page with the mouse
+pub struct Drag {
+    pub button: mouse::Button,
+    pub position: Point,
+    pub moved: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Fit {
+    #[default]
+    ActualSize,
+    Page,
+    Width,
+}
+
+impl Fit {
+    pub const ALL: [Self; 3] = [Self::ActualSize, Self::Page, Self::Width];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::ActualSize => fl!("actual-size"),
+            Self::Page => fl!("fit-page"),
+            Self::Width => fl!("fit-width"),
+        }
+    }
+}
+
+/// Layout, zoom, and pan of the pages
+pub struct PageView {
+    pub layout: Layout,
+    pub fit: Fit,
+    /// Zoom relative to the fit
     pub scale: f32,
     pub translate: Vector,
-    pub modifiers: keyboard::Modifiers,
 }
 
-impl Default for CanvasState {
-    fn default() -> Self {
+impl PageView {
+    pub fn new(layout: Layout, fit: Fit, scale: f32) -> Self {
         Self {
-            // Default PDF DPI is 72, default screen DPI is 96
-            scale: 96.0 / 72.0,
+            layout,
+            fit,
+            scale,
             translate: Vector::new(0.0, 0.0),
-            modifiers: keyboard::Modifiers::empty(),
         }
     }
+
+    /// Scale from PDF units to pixels for a page of the given size, on a screen with the given
+    /// logical pixels per inch
+    pub fn page_scale(&self, bounds: Size, page: Option<Size>, ppi: f32) -> f32 {
+        // Pages without area are shown at actual size, fitting them would divide by zero
+        let page = page.filter(|page| page.width > 0.0 && page.height > 0.0);
+        let fit = match (self.fit, page) {
+            (Fit::Page, Some(page)) => (bounds.width / page.width).min(bounds.height / page.height),
+            (Fit::Width, Some(page)) => bounds.width / page.width,
+            // PDF units are 1/72 inch
+            _ => ppi / 72.0,
+        };
+        fit * self.scale
+    }
+}
+
+impl Default for PageView {
+    fn default() -> Self {
+        Self::new(Layout::default(), Fit::default(), 1.0)
+    }
 }
 
 //TODO: errors
@@ -149,11 +237,63 @@ fn finish_path(original: &mut canvas::path::Builder, transform: &Transform) -> c
     builder.build().transform(transform)
 }
 
+#[derive(Clone, Debug)]
 pub struct PageOp {
     pub path: Option<canvas::Path>,
     pub fill: Option<canvas::Fill>,
     pub stroke: Option<canvas::Stroke<'static>>,
     pub image: Option<Image>,
+    /// Outline of a text glyph, which may be darkened to stay legible at small sizes
+    pub glyph: bool,
+    pub text: Option<TextRun>,
+}
+
+impl PageOp {
+    /// Opaque white fill without an outline, such as a background painted over the whole page
+    pub fn blank(&self) -> bool {
+        self.image.is_none()
+            && self.stroke.is_none()
+            && self.fill.is_some_and(
+                |fill| matches!(fill.style, canvas::Style::Solid(color) if color == Color::WHITE),
+            )
+    }
+}
+
+/// Area of a page that is drawn on, in PDF units, ignoring blank backgrounds
+//TODO: curves are bounded by their control points, which may reach past the curve
+pub fn content_bounds(ops: &[PageOp]) -> Option<Rectangle> {
+    let mut points = Vec::new();
+    for op in ops.iter().filter(|op| !op.blank()) {
+        if let Some(path) = &op.path {
+            for event in path.raw().iter() {
+                match event {
+                    Event::Begin { at } => points.push(at),
+                    Event::Line { to, .. } => points.push(to),
+                    Event::Quadratic { ctrl, to, .. } => points.extend([ctrl, to]),
+                    Event::Cubic {
+                        ctrl1, ctrl2, to, ..
+                    } => points.extend([ctrl1, ctrl2, to]),
+                    Event::End { .. } => {}
+                }
+            }
+        }
+        if let Some(image) = &op.image {
+            // Image rectangles are placed by their top left corner, with y up
+            let rect = image.rect;
+            points.push(Point2D::new(rect.x, rect.y));
+            points.push(Point2D::new(rect.x + rect.width, rect.y - rect.height));
+        }
+    }
+    let first = points.first()?;
+    let (mut min, mut max) = (*first, *first);
+    for point in points.iter() {
+        min = min.min(*point);
+        max = max.max(*point);
+    }
+    Some(Rectangle::new(
+        Point::new(min.x, min.y),
+        Size::new(max.x - min.x, max.y - min.y),
+    ))
 }
 
 fn load_fonts(doc: &Document, fonts: &BTreeMap<Vec<u8>, &Dictionary>) {
@@ -294,7 +434,635 @@ fn load_image(
     ))
 }
 
-pub fn page_ops(doc: &Document, page_id: ObjectId) -> Vec<PageOp> {
+/// The visible area of a page and how it is rotated for display
+#[derive(Clone, Copy, Debug)]
+pub struct PageBox {
+    /// Crop box clipped to the media box, in PDF units
+    pub rect: Rectangle,
+    /// Clockwise rotation in degrees, one of 0, 90, 180, or 270
+    pub rotate: i64,
+}
+
+impl PageBox {
+    /// Size of the page as displayed, after rotation
+    pub fn size(&self) -> Size {
+        if self.rotate % 180 == 0 {
+            self.rect.size()
+        } else {
+            Size::new(self.rect.height, self.rect.width)
+        }
+    }
+}
+
+// MediaBox, CropBox, and Rotate can be inherited from parent page tree nodes
+fn page_attr<'a>(doc: &'a Document, page_id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut dict = doc.get_dictionary(page_id).ok()?;
+    // Limit depth in case the page tree has a loop
+    for _ in 0..32 {
+        if let Ok(obj) = dict.get_deref(key, doc) {
+            return Some(obj);
+        }
+        dict = dict
+            .get_deref(b"Parent", doc)
+            .and_then(|x| x.as_dict())
+            .ok()?;
+    }
+    None
+}
+
+fn page_rect(doc: &Document, page_id: ObjectId, key: &[u8]) -> Option<Rectangle> {
+    rect(doc, page_attr(doc, page_id, key)?)
+}
+
+pub fn rect(doc: &Document, obj: &Object) -> Option<Rectangle> {
+    let array = doc.dereference(obj).ok()?.1.as_array().ok()?;
+    let mut values = [0.0; 4];
+    for (value, obj) in values.iter_mut().zip(array.iter()) {
+        *value = doc.dereference(obj).ok()?.1.as_float().ok()?;
+    }
+    if array.len() != values.len() {
+        return None;
+    }
+    // Rectangles may be given by any two opposite corners
+    let [x0, y0, x1, y1] = values;
+    Some(Rectangle::new(
+        Point::new(x0.min(x1), y0.min(y1)),
+        Size::new((x1 - x0).abs(), (y1 - y0).abs()),
+    ))
+}
+
+pub fn page_box(doc: &Document, page_id: ObjectId) -> Option<PageBox> {
+    // Pages without area cannot be scaled to fit, so they are treated as having no size
+    let has_area = |rect: &Rectangle| rect.width > 0.0 && rect.height > 0.0;
+    let media_box = page_rect(doc, page_id, b"MediaBox").filter(has_area)?;
+    let rect = page_rect(doc, page_id, b"CropBox")
+        .and_then(|crop_box| crop_box.intersection(&media_box))
+        .filter(has_area)
+        .unwrap_or(media_box);
+    let rotate = page_attr(doc, page_id, b"Rotate")
+        .and_then(|x| x.as_i64().ok())
+        .unwrap_or(0)
+        .rem_euclid(360)
+        / 90
+        * 90;
+    Some(PageBox { rect, rotate })
+}
+
+/// Languages written right to left, by their primary language subtag
+const RTL_LANGUAGES: [&str; 7] = ["ar", "dv", "fa", "he", "ps", "ur", "yi"];
+
+/// Whether pages are read right to left, from the viewer preferences or the document language
+pub fn right_to_left(doc: &Document) -> bool {
+    let Ok(catalog) = doc.catalog() else {
+        return false;
+    };
+    // Comics and manga set the direction in the viewer preferences
+    if let Ok(direction) = catalog
+        .get_deref(b"ViewerPreferences", doc)
+        .and_then(Object::as_dict)
+        .and_then(|prefs| prefs.get(b"Direction"))
+        .and_then(Object::as_name_str)
+    {
+        return direction == "R2L";
+    }
+    catalog
+        .get_deref(b"Lang", doc)
+        .ok()
+        .and_then(|lang| decode_text_string(lang).ok())
+        .is_some_and(|lang| {
+            let language = lang.split(['-', '_']).next().unwrap_or("");
+            RTL_LANGUAGES.contains(&language.to_ascii_lowercase().as_str())
+        })
+}
+
+/// Name of a destination on the page, from the named destinations of the document
+pub fn named_destination(doc: &Document, page_id: ObjectId) -> Option<String> {
+    let catalog = doc.catalog().ok()?;
+    // PDF 1.1 has a dictionary of destinations by name
+    if let Ok(dests) = catalog.get_deref(b"Dests", doc).and_then(Object::as_dict) {
+        for (name, dest) in dests.iter() {
+            if destination_page(doc, dest) == Some(page_id) {
+                return Some(String::from_utf8_lossy(name).into_owned());
+            }
+        }
+    }
+    let tree = catalog
+        .get_deref(b"Names", doc)
+        .and_then(Object::as_dict)
+        .and_then(|names| names.get_deref(b"Dests", doc))
+        .and_then(Object::as_dict)
+        .ok()?;
+    find_destination(doc, tree, page_id, 0)
+}
+
+/// Name tree nodes deeper than this are ignored, in case of cycles
+const NAME_TREE_DEPTH: usize = 32;
+
+/// Longest transition between pages, in seconds, as longer ones leave the presentation stuck
+const TRANSITION_SECS_MAX: f32 = 10.0;
+
+fn find_destination(
+    doc: &Document,
+    node: &Dictionary,
+    page_id: ObjectId,
+    depth: usize,
+) -> Option<String> {
+    if depth > NAME_TREE_DEPTH {
+        return None;
+    }
+    if let Ok(names) = node.get_deref(b"Names", doc).and_then(Object::as_array) {
+        for pair in names.chunks(2) {
+            if let [name, dest] = pair {
+                if destination_page(doc, dest) == Some(page_id) {
+                    return decode_text_string(name).ok();
+                }
+            }
+        }
+    }
+    node.get_deref(b"Kids", doc)
+        .and_then(Object::as_array)
+        .ok()?
+        .iter()
+        .filter_map(|kid| doc.dereference(kid).ok()?.1.as_dict().ok())
+        .find_map(|kid| find_destination(doc, kid, page_id, depth + 1))
+}
+
+/// Page of an explicit destination, or of a dictionary with one in its /D entry
+fn destination_page(doc: &Document, dest: &Object) -> Option<ObjectId> {
+    let (_, dest) = doc.dereference(dest).ok()?;
+    let dest = match dest {
+        Object::Dictionary(dict) => dict.get_deref(b"D", doc).ok()?,
+        _ => dest,
+    };
+    dest.as_array().ok()?.first()?.as_reference().ok()
+}
+
+/// Where a link goes
+#[derive(Clone, Debug)]
+pub enum LinkTarget {
+    /// Page, with the top of the destination in PDF units if it has one
+    Page(ObjectId, Option<f32>),
+    Uri(String),
+}
+
+/// Link annotation of a page
+#[derive(Clone, Debug)]
+pub struct Link {
+    /// Area of the annotation, in PDF units
+    pub rect: Rectangle,
+    pub target: LinkTarget,
+}
+
+/// Links of a page to its document and to the web
+//TODO: links to other documents with GoToR and Launch actions
+pub fn page_links(doc: &Document, page_id: ObjectId) -> Vec<Link> {
+    let Ok(annotations) = doc.get_page_annotations(page_id) else {
+        return Vec::new();
+    };
+    let mut links = Vec::new();
+    for annotation in annotations {
+        if annotation.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Link") {
+            continue;
+        }
+        let target = match annotation.get(b"Dest") {
+            Ok(dest) => destination_target(doc, dest, 0),
+            Err(_) => {
+                let Ok(action) = annotation.get_deref(b"A", doc).and_then(Object::as_dict) else {
+                    continue;
+                };
+                match action.get(b"S").and_then(Object::as_name) {
+                    Ok(b"URI") => action
+                        .get_deref(b"URI", doc)
+                        .and_then(Object::as_str)
+                        .ok()
+                        .map(|uri| LinkTarget::Uri(String::from_utf8_lossy(uri).into_owned())),
+                    Ok(b"GoTo") => action
+                        .get(b"D")
+                        .ok()
+                        .and_then(|dest| destination_target(doc, dest, 0)),
+                    _ => None,
+                }
+            }
+        };
+        let (Some(target), Some(rect)) = (
+            target,
+            annotation.get(b"Rect").ok().and_then(|x| rect(doc, x)),
+        ) else {
+            continue;
+        };
+        links.push(Link { rect, target });
+    }
+    links
+}
+
+/// Page and top of an explicit or named destination
+fn destination_target(doc: &Document, dest: &Object, depth: usize) -> Option<LinkTarget> {
+    let (_, dest) = doc.dereference(dest).ok()?;
+    let catalog = doc.catalog().ok()?;
+    let dest = match dest {
+        // PDF 1.1 names destinations in a dictionary, later versions in a name tree
+        Object::Name(name) => catalog
+            .get_deref(b"Dests", doc)
+            .and_then(Object::as_dict)
+            .and_then(|dests| dests.get_deref(name, doc))
+            .ok()?,
+        Object::String(name, _) => {
+            let tree = catalog
+                .get_deref(b"Names", doc)
+                .and_then(Object::as_dict)
+                .and_then(|names| names.get_deref(b"Dests", doc))
+                .and_then(Object::as_dict)
+                .ok()?;
+            lookup_name(doc, tree, name, 0)?
+        }
+        _ => dest,
+    };
+    let dest = match dest {
+        Object::Dictionary(dict) => dict.get_deref(b"D", doc).ok()?,
+        _ => dest,
+    };
+    // Named destinations are explicit, but guard against names of names anyway
+    if matches!(dest, Object::Name(_) | Object::String(..)) {
+        return (depth < NAME_TREE_DEPTH)
+            .then(|| destination_target(doc, dest, depth + 1))
+            .flatten();
+    }
+    let array = dest.as_array().ok()?;
+    let page_id = array.first()?.as_reference().ok()?;
+    let top = match array.get(1).and_then(|kind| kind.as_name().ok()) {
+        Some(b"XYZ") => array.get(3),
+        Some(b"FitH" | b"FitBH") => array.get(2),
+        Some(b"FitR") => array.get(5),
+        _ => None,
+    }
+    .and_then(|top| top.as_float().ok());
+    Some(LinkTarget::Page(page_id, top))
+}
+
+/// Value of a key in a name tree
+fn lookup_name<'a>(
+    doc: &'a Document,
+    node: &'a Dictionary,
+    name: &[u8],
+    depth: usize,
+) -> Option<&'a Object> {
+    if depth > NAME_TREE_DEPTH {
+        return None;
+    }
+    if let Ok(names) = node.get_deref(b"Names", doc).and_then(Object::as_array) {
+        for pair in names.chunks(2) {
+            if let [Object::String(key, _), value] = pair {
+                if key == name {
+                    return Some(doc.dereference(value).ok()?.1);
+                }
+            }
+        }
+    }
+    node.get_deref(b"Kids", doc)
+        .and_then(Object::as_array)
+        .ok()?
+        .iter()
+        .filter_map(|kid| doc.dereference(kid).ok()?.1.as_dict().ok())
+        .find_map(|kid| lookup_name(doc, kid, name, depth + 1))
+}
+
+/// Audio or video of a Screen or RichMedia annotation
+#[derive(Clone, Debug)]
+pub struct Media {
+    /// Area of the annotation, in PDF units
+    pub rect: Rectangle,
+    /// File name, relative to the document if it is not embedded
+    pub name: String,
+    /// Stream of the embedded file
+    pub embedded: Option<ObjectId>,
+}
+
+/// Media played by the annotations of a page
+pub fn page_media(doc: &Document, page_id: ObjectId) -> Vec<Media> {
+    let Ok(annotations) = doc.get_page_annotations(page_id) else {
+        return Vec::new();
+    };
+    let mut media = Vec::new();
+    for annotation in annotations {
+        let file_spec = match annotation.get(b"Subtype").and_then(Object::as_name) {
+            // Screen annotations play a rendition of a media clip
+            Ok(b"Screen") => annotation
+                .get_deref(b"A", doc)
+                .and_then(Object::as_dict)
+                .and_then(|action| action.get_deref(b"R", doc))
+                .and_then(Object::as_dict)
+                .and_then(|rendition| rendition.get_deref(b"C", doc))
+                .and_then(Object::as_dict)
+                .and_then(|clip| clip.get_deref(b"D", doc))
+                .ok(),
+            // RichMedia annotations have assets by name, the first is played
+            Ok(b"RichMedia") => annotation
+                .get_deref(b"RichMediaContent", doc)
+                .and_then(Object::as_dict)
+                .and_then(|content| content.get_deref(b"Assets", doc))
+                .and_then(Object::as_dict)
+                .and_then(|assets| assets.get_deref(b"Names", doc))
+                .and_then(Object::as_array)
+                .ok()
+                .and_then(|names| names.get(1))
+                .and_then(|asset| Some(doc.dereference(asset).ok()?.1)),
+            _ => continue,
+        };
+        let (Some(file_spec), Some(rect)) = (
+            file_spec,
+            annotation.get(b"Rect").ok().and_then(|x| rect(doc, x)),
+        ) else {
+            continue;
+        };
+        let (name, embedded) = match file_spec {
+            Object::Dictionary(dict) => (
+                dict.get_deref(b"UF", doc)
+                    .or_else(|_| dict.get_deref(b"F", doc))
+                    .and_then(decode_text_string)
+                    .unwrap_or_default(),
+                dict.get_deref(b"EF", doc)
+                    .and_then(Object::as_dict)
+                    .and_then(|ef| ef.get(b"UF").or_else(|_| ef.get(b"F")))
+                    .and_then(Object::as_reference)
+                    .ok(),
+            ),
+            other => (decode_text_string(other).unwrap_or_default(), None),
+        };
+        media.push(Media {
+            rect,
+            name,
+            embedded,
+        });
+    }
+    media
+}
+
+/// Contents of an embedded file
+pub fn embedded_file(doc: &Document, id: ObjectId) -> Result<Vec<u8>, lopdf::Error> {
+    let stream = doc.get_object(id).and_then(Object::as_stream)?;
+    // Files are often stored without a filter
+    if stream.filters().is_ok() {
+        stream.decompressed_content()
+    } else {
+        Ok(stream.content.clone())
+    }
+}
+
+/// How long the page should be shown in a presentation, from its /Dur entry
+pub fn page_duration(doc: &Document, page_id: ObjectId) -> Option<Duration> {
+    let secs = doc
+        .get_dictionary(page_id)
+        .ok()?
+        .get_deref(b"Dur", doc)
+        .ok()?
+        .as_float()
+        .ok()?;
+    // Durations too long to represent are ignored like missing ones
+    Duration::try_from_secs_f32(secs)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransitionStyle {
+    Split,
+    Blinds,
+    Box,
+    Wipe,
+    /// Used for Fade and for styles that are not supported
+    Fade,
+}
+
+/// Presentation transition used when moving to a page, from its /Trans dictionary
+#[derive(Clone, Copy, Debug)]
+pub struct Transition {
+    pub style: TransitionStyle,
+    pub duration: Duration,
+    /// Split and Blinds lines are horizontal instead of vertical
+    pub horizontal: bool,
+    /// Split and Box move inward from the edges instead of outward from the center
+    pub inward: bool,
+    /// Wipe direction in degrees, counterclockwise from left to right
+    pub direction: i64,
+}
+
+pub fn page_transition(doc: &Document, page_id: ObjectId) -> Option<Transition> {
+    let trans = doc
+        .get_dictionary(page_id)
+        .ok()?
+        .get_deref(b"Trans", doc)
+        .ok()?
+        .as_dict()
+        .ok()?;
+    let style = match trans.get(b"S").and_then(|x| x.as_name()) {
+        Ok(b"Split") => TransitionStyle::Split,
+        Ok(b"Blinds") => TransitionStyle::Blinds,
+        Ok(b"Box") => TransitionStyle::Box,
+        Ok(b"Wipe") => TransitionStyle::Wipe,
+        // R (replace) is the default, and means no transition
+        Ok(b"R") | Err(_) => return None,
+        Ok(style) => {
+            log::info!(
+                "using fade for transition {:?}",
+                String::from_utf8_lossy(style)
+            );
+            TransitionStyle::Fade
+        }
+    };
+    let duration = trans
+        .get_deref(b"D", doc)
+        .ok()
+        .and_then(|x| x.as_float().ok())
+        .and_then(|x| Duration::try_from_secs_f32(x.clamp(0.0, TRANSITION_SECS_MAX)).ok())
+        .unwrap_or(Duration::from_secs(1));
+    Some(Transition {
+        style,
+        duration,
+        horizontal: trans
+            .get(b"Dm")
+            .and_then(|x| x.as_name())
+            .map_or(true, |x| x == b"H"),
+        inward: trans
+            .get(b"M")
+            .and_then(|x| x.as_name())
+            .map_or(true, |x| x == b"I"),
+        direction: trans
+            .get_deref(b"Di", doc)
+            .and_then(|x| x.as_i64())
+            .unwrap_or(0)
+            .rem_euclid(360),
+    })
+}
+
+/// Counts shown in the statistics of a document
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Statistics {
+    pub pages: usize,
+    pub words: usize,
+    pub characters: usize,
+    pub images: usize,
+}
+
+impl Statistics {
+    /// Minutes to read the text at an average reading speed
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(238)
+    }
+}
+
+/// Count the words and characters of the extracted text, and the images of a document
+pub fn statistics(doc: &Document) -> Statistics {
+    let pages = doc.get_pages();
+    let mut statistics = Statistics {
+        pages: pages.len(),
+        ..Default::default()
+    };
+    for &page in pages.keys() {
+        match doc.extract_text(&[page]) {
+            Ok(text) => {
+                statistics.words += text.split_whitespace().count();
+                statistics.characters += text.chars().filter(|c| !c.is_whitespace()).count();
+            }
+            Err(err) => log::warn!("failed to extract text of page {page}: {err}"),
+        }
+    }
+    // Soft masks are stored as images but are part of another image
+    let mut masks = HashSet::new();
+    let mut images = HashSet::new();
+    for (id, object) in doc.objects.iter() {
+        let Ok(stream) = object.as_stream() else {
+            continue;
+        };
+        if stream
+            .dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .is_ok_and(|subtype| subtype == b"Image")
+        {
+            images.insert(*id);
+            if let Ok(mask) = stream.dict.get(b"SMask").and_then(Object::as_reference) {
+                masks.insert(mask);
+            }
+        }
+    }
+    statistics.images = images.difference(&masks).count();
+    statistics
+}
+
+/// Word by word comparisons of pages are given up past this many pairs of words that differ,
+/// as they take time and memory with the product of the numbers of words
+const DIFF_CELLS_MAX: usize = 4_000_000;
+
+/// Word shown on a page, with a box around it in PDF units
+struct Word {
+    text: String,
+    rect: Rectangle,
+}
+
+/// Boxes around the words of two pages that the other page does not have, in PDF units of each
+/// page, from a word by word comparison of their text in the order it is drawn
+pub fn text_run_differences(ops: &[PageOp], other: &[PageOp]) -> [Vec<Rectangle>; 2] {
+    let words = run_words(ops);
+    let other_words = run_words(other);
+    let (common, other_common) = common_words(&words, &other_words);
+    let differing = |words: &[Word], common: &[bool]| {
+        words
+            .iter()
+            .zip(common)
+            .filter(|(_, common)| !**common)
+            .map(|(word, _)| word.rect)
+            .collect()
+    };
+    [
+        differing(&words, &common),
+        differing(&other_words, &other_common),
+    ]
+}
+
+/// Words of the text runs of a page, with characters assumed to be of equal width along a run
+fn run_words(ops: &[PageOp]) -> Vec<Word> {
+    let mut words = Vec::new();
+    for run in ops.iter().filter_map(|op| op.text.as_ref()) {
+        let chars: Vec<char> = run.content.chars().collect();
+        if chars.is_empty() {
+            continue;
+        }
+        let char_width = run.rect.width / chars.len() as f32;
+        let mut start = None;
+        for (i, c) in chars.iter().chain([&' ']).enumerate() {
+            if !c.is_whitespace() {
+                start = start.or(Some(i));
+                continue;
+            }
+            if let Some(start) = start.take() {
+                words.push(Word {
+                    text: chars[start..i].iter().collect(),
+                    rect: Rectangle::new(
+                        Point::new(run.rect.x + start as f32 * char_width, run.rect.y),
+                        Size::new((i - start) as f32 * char_width, run.rect.height),
+                    ),
+                });
+            }
+        }
+    }
+    words
+}
+
+/// Whether each word of two lists is in their longest common subsequence
+fn common_words(words: &[Word], other: &[Word]) -> (Vec<bool>, Vec<bool>) {
+    let same = |a: &Word, b: &Word| a.text == b.text;
+    // Pages usually differ in a few places, so the common start and end are left out
+    let prefix = words
+        .iter()
+        .zip(other)
+        .take_while(|(a, b)| same(a, b))
+        .count();
+    let suffix = words[prefix..]
+        .iter()
+        .rev()
+        .zip(other[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+    let middle = &words[prefix..words.len() - suffix];
+    let other_middle = &other[prefix..other.len() - suffix];
+    let mut common = vec![true; words.len()];
+    let mut other_common = vec![true; other.len()];
+    common[prefix..words.len() - suffix].fill(false);
+    other_common[prefix..other.len() - suffix].fill(false);
+    let (n, m) = (middle.len(), other_middle.len());
+    if n == 0 || m == 0 || n.saturating_mul(m) > DIFF_CELLS_MAX {
+        return (common, other_common);
+    }
+    // Lengths of the longest common subsequences of the ends of both lists
+    let width = m + 1;
+    let mut lengths = vec![0u32; (n + 1) * width];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i * width + j] = if same(&middle[i], &other_middle[j]) {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if same(&middle[i], &other_middle[j]) {
+            common[prefix + i] = true;
+            other_common[prefix + j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    (common, other_common)
+}
+
+/// Operations drawing a page, leaving out contents of the hidden layers
+pub fn page_ops(doc: &Document, page_id: ObjectId, hidden: &HashSet<ObjectId>) -> Vec<PageOp> {
     let mut page_ops = Vec::new();
     let content = match doc.get_and_decode_page_content(page_id) {
         Ok(ok) => ok,
@@ -326,7 +1094,10 @@ pub fn page_ops(doc: &Document, page_id: ObjectId) -> Vec<PageOp> {
     let mut graphics_states = vec![GraphicsState::default()];
     let mut text_states = vec![];
     let mut p = canvas::path::Builder::new();
+    // Whether each open marked content sequence is in a hidden layer
+    let mut marked = Vec::new();
     for op in content.operations.iter() {
+        let len = page_ops.len();
         //TODO: better handle errors with object conversions
         // https://pdfa.org/wp-content/uploads/2023/08/PDF-Operators-CheatSheet.pdf
         match op.operator.as_str() {
@@ -417,9 +1188,32 @@ pub fn page_ops(doc: &Document, page_id: ObjectId) -> Vec<PageOp> {
                         None
                     },
                     image: None,
+                    glyph: false,
+                    text: None,
                 });
             }
 
+            // Marked content
+            "BDC" => {
+                let hide = match op.operands.as_slice() {
+                    [Object::Name(tag), properties] if tag == b"OC" => {
+                        let properties = match properties {
+                            Object::Name(name) => layer::marked_content(doc, page_id, name),
+                            _ => Some(properties),
+                        };
+                        properties.is_some_and(|x| layer::is_hidden(doc, x, hidden))
+                    }
+                    _ => false,
+                };
+                marked.push(hide);
+            }
+            "BMC" => {
+                marked.push(false);
+            }
+            "EMC" => {
+                marked.pop();
+            }
+
             // Text object
             "BT" => {
                 text_states.push(TextState::default());
@@ -710,8 +1504,38 @@ pub fn page_ops(doc: &Document, page_id: ObjectId) -> Vec<PageOp> {
                                 None
                             },
                             image: None,
+                            glyph: true,
+                            text: None,
                         });
                     });
+                    // Fonts usually ascend about 80% of their size above the baseline
+                    let (bottom, top) = (
+                        gs.text_rise - gs.text_size * 0.2,
+                        gs.text_rise + gs.text_size * 0.8,
+                    );
+                    let corners = [(0.0, bottom), (max_w, bottom), (0.0, top), (max_w, top)]
+                        .map(|(x, y)| ts.cursor_tf.transform_point(Point2D::new(x, y)));
+                    let (min, max) = corners.iter().fold(
+                        (
+                            Point2D::new(f32::MAX, f32::MAX),
+                            Point2D::new(f32::MIN, f32::MIN),
+                        ),
+                        |(min, max), corner| (min.min(*corner), max.max(*corner)),
+                    );
+                    page_ops.push(PageOp {
+                        path: None,
+                        fill: None,
+                        stroke: None,
+                        image: None,
+                        glyph: false,
+                        text: Some(TextRun {
+                            content: content.to_string(),
+                            rect: Rectangle::new(
+                                Point::new(min.x, min.y),
+                                Size::new(max.x - min.x, max.y - min.y),
+                            ),
+                        }),
+                    });
                     ts.cursor_tf = ts
                         .cursor_tf
                         .pre_translate(Vector2D::new(max_w - adjustment / 1000.0, 0.0));
@@ -802,6 +1626,9 @@ pub fn page_ops(doc: &Document, page_id: ObjectId) -> Vec<PageOp> {
             "Do" => {
                 let name = op.operands[0].as_name_str().unwrap();
                 log::info!("image {name:?}");
+                if layer::xobject_hidden(doc, page_id, name, hidden) {
+                    continue;
+                }
 
                 match load_image(doc, page_id, name) {
                     Ok((handle, width, height)) => {
@@ -819,6 +1646,8 @@ pub fn page_ops(doc: &Document, page_id: ObjectId) -> Vec<PageOp> {
                                     Size::new((a.x - b.x).abs(), (a.y - b.y).abs())
                                 )
                              }),
+                            glyph: false,
+                            text: None,
                         });
                     }
                     Err(err) => {
@@ -831,6 +1660,10 @@ pub fn page_ops(doc: &Document, page_id: ObjectId) -> Vec<PageOp> {
                 log::warn!("unknown op {:?}", op);
             }
         }
+        // State still changes inside hidden contents, only drawing is left out
+        if marked.iter().any(|&hide| hide) {
+            page_ops.truncate(len);
+        }
     }
 
     page_ops
@@ -0,0 +1,19 @@
+use lopdf::Document;
+use std::{fs, path::Path};
+
+use crate::comic;
+
+/// Extensions of images opened as documents of one page
+const EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "tif", "tiff", "gif", "webp", "bmp"];
+
+/// Whether a file is a plain image, by its extension
+pub fn is_picture(path: &Path) -> bool {
+    comic::has_extension(path, &EXTENSIONS)
+}
+
+/// Open an image as a document with one page the size of the image
+pub fn load(path: &Path) -> Result<Document, String> {
+    let data = fs::read(path).map_err(|err| err.to_string())?;
+    let name = path.to_string_lossy().into_owned();
+    comic::document(vec![(name, data)], path)
+}
@@ -0,0 +1,32 @@
+use std::{
+    ffi::{CString, OsString},
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
+/// Extended attribute the document portal gives its files, with the path of the file outside
+/// the sandbox
+const HOST_PATH: &[u8] = b"user.document-portal.host-path\0";
+
+/// Path outside the sandbox of a document passed in through the document portal, none for other
+/// documents
+pub fn host_path(path: &Path) -> Option<PathBuf> {
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut value = vec![0u8; libc::PATH_MAX as usize];
+    // SAFETY: the path and name end with nul, and the value is as long as the size given
+    let len = unsafe {
+        libc::getxattr(
+            c_path.as_ptr(),
+            HOST_PATH.as_ptr().cast(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+        )
+    };
+    // Files outside the portal do not have the attribute
+    let len = usize::try_from(len).ok().filter(|len| *len > 0)?;
+    value.truncate(len);
+    if value.last() == Some(&0) {
+        value.pop();
+    }
+    Some(PathBuf::from(OsString::from_vec(value)))
+}
@@ -0,0 +1,31 @@
+use std::{fs, path::Path};
+
+/// Whether the computer is running on battery, from the power supplies the kernel reports
+//TODO: also follow the power saver profile of power-profiles-daemon over D-Bus
+pub fn on_battery() -> bool {
+    let Ok(entries) = fs::read_dir("/sys/class/power_supply") else {
+        return false;
+    };
+    let mut battery = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match read(&path, "type").as_deref() {
+            // Any connected charger means power is not limited
+            Some("Mains" | "USB") if read(&path, "online").as_deref() == Some("1") => {
+                return false;
+            }
+            // Batteries of devices such as mice have a scope and do not power the computer
+            Some("Battery") if read(&path, "scope").is_none() => {
+                battery |= read(&path, "status").as_deref() == Some("Discharging");
+            }
+            _ => {}
+        }
+    }
+    battery
+}
+
+fn read(path: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(path.join(name))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
@@ -0,0 +1,547 @@
+use cosmic::iced::{
+    alignment::{Horizontal, Vertical},
+    widget::canvas,
+    Color, Pixels, Point, Rectangle, Size,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::pdf::{Transition, TransitionStyle};
+
+const OVERLAY_MARGIN: f32 = 16.0;
+const OVERLAY_TEXT_SIZE: f32 = 24.0;
+const LASER_RADIUS: f32 = 8.0;
+const SPOTLIGHT_RADIUS: f32 = 120.0;
+const BLINDS: usize = 6;
+/// Pen colors, selected with the number keys
+pub const INK_COLORS: [Color; 5] = [
+    Color::from_rgb(0.9, 0.1, 0.1),
+    Color::from_rgb(0.1, 0.7, 0.1),
+    Color::from_rgb(0.1, 0.3, 0.9),
+    Color::from_rgb(1.0, 0.8, 0.0),
+    Color::WHITE,
+];
+const INK_WIDTH_MIN: f32 = 1.0;
+const INK_WIDTH_MAX: f32 = 32.0;
+const OVERVIEW_CELL_WIDTH: f32 = 240.0;
+pub const OVERVIEW_PADDING: f32 = 16.0;
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+/// How the mouse cursor is shown on the slide
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Pointer {
+    #[default]
+    None,
+    /// A large red dot
+    Laser,
+    /// Dims everything except a circle around the cursor
+    Spotlight,
+}
+
+impl Pointer {
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Laser,
+            Self::Laser => Self::Spotlight,
+            Self::Spotlight => Self::None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InkStroke {
+    pub points: Vec<Point>,
+    pub color: Color,
+    pub width: f32,
+}
+
+/// Changes to the freehand drawing over the slides
+#[derive(Clone, Debug)]
+pub enum Ink {
+    /// Start a stroke at a point in window coordinates
+    Begin(Point),
+    /// Continue the current stroke
+    Point(Point),
+    End,
+    /// Remove all strokes on the current page
+    Erase,
+    Color(Color),
+    /// Multiply the pen width
+    Width(f32),
+    /// Toggle the pen
+    Pen,
+    /// Toggle keeping strokes when changing pages
+    Pin,
+    /// Undo the last stroke or erase
+    Undo,
+    Redo,
+}
+
+/// Grid of slides shown by the overview, scrolled by whole screens to keep the selection visible
+pub struct OverviewLayout {
+    pub columns: usize,
+    pub cell: Size,
+    /// Range of pages on the current screen
+    pub first: usize,
+    pub last: usize,
+}
+
+impl OverviewLayout {
+    pub fn new(size: Size, pages: usize, selected: usize) -> Self {
+        let columns = ((size.width / OVERVIEW_CELL_WIDTH) as usize).max(1);
+        let width = size.width / columns as f32;
+        // Cells use a 4:3 aspect ratio, as most slides do
+        let cell = Size::new(width, width * 3.0 / 4.0);
+        let rows = ((size.height / cell.height) as usize).max(1);
+        let per_screen = rows * columns;
+        let first = (selected / per_screen) * per_screen;
+        Self {
+            columns,
+            cell,
+            first,
+            last: (first + per_screen).min(pages),
+        }
+    }
+
+    pub fn cell_rect(&self, page: usize) -> Rectangle {
+        let i = page - self.first;
+        Rectangle::new(
+            Point::new(
+                (i % self.columns) as f32 * self.cell.width,
+                (i / self.columns) as f32 * self.cell.height,
+            ),
+            self.cell,
+        )
+    }
+
+    pub fn page_at(&self, point: Point) -> Option<usize> {
+        (self.first..self.last).find(|&page| self.cell_rect(page).contains(point))
+    }
+}
+
+pub struct Presentation {
+    /// Kiosk presentations loop on a timer and ignore input
+    pub kiosk: bool,
+    pub start: Instant,
+    /// Target duration, used to show the remaining time
+    pub target: Option<Duration>,
+    pub show_timer: bool,
+    /// Time each page is shown before advancing, unless the page has its own duration
+    pub auto_advance: Option<Duration>,
+    pub paused: bool,
+    /// When the current page was shown, and for how long it should be
+    pub page_start: Instant,
+    pub page_duration: Option<Duration>,
+    /// Show the transitions requested by pages
+    pub transitions: bool,
+    /// Transition to the current page and when it started
+    pub transition: Option<(Transition, Instant)>,
+    /// Color covering the slide, for pausing the visual without leaving the presentation
+    pub blank: Option<Color>,
+    pub pointer: Pointer,
+    /// Mouse drags draw strokes instead of being passed to the page
+    pub pen: bool,
+    pub pen_color: Color,
+    pub pen_width: f32,
+    /// Strokes are not part of the document, they are kept by page position while pinned
+    pub ink: HashMap<u16, Vec<InkStroke>>,
+    pub ink_pinned: bool,
+    /// Strokes of pages before each change, for undo, and before each undo, for redo
+    ink_undo: Vec<(u16, Vec<InkStroke>)>,
+    ink_redo: Vec<(u16, Vec<InkStroke>)>,
+    pub page: u16,
+    drawing: bool,
+    /// Selected page while the overview of all slides is shown
+    pub overview: Option<u16>,
+}
+
+impl Presentation {
+    pub fn new(
+        kiosk: bool,
+        target: Option<Duration>,
+        auto_advance: Option<Duration>,
+        transitions: bool,
+    ) -> Self {
+        Self {
+            kiosk,
+            start: Instant::now(),
+            target,
+            show_timer: false,
+            auto_advance,
+            paused: false,
+            page_start: Instant::now(),
+            page_duration: None,
+            transitions,
+            transition: None,
+            blank: None,
+            pointer: Pointer::None,
+            pen: false,
+            pen_color: INK_COLORS[0],
+            pen_width: 4.0,
+            ink: HashMap::new(),
+            ink_pinned: false,
+            ink_undo: Vec::new(),
+            ink_redo: Vec::new(),
+            page: 0,
+            drawing: false,
+            overview: None,
+        }
+    }
+
+    /// Restart the page timer, with the duration and transition requested by the new page
+    pub fn page_changed(
+        &mut self,
+        page: u16,
+        page_duration: Option<Duration>,
+        transition: Option<Transition>,
+    ) {
+        if !self.ink_pinned {
+            self.ink.clear();
+            self.ink_undo.clear();
+            self.ink_redo.clear();
+        }
+        self.page = page;
+        self.drawing = false;
+        self.page_start = Instant::now();
+        self.page_duration = page_duration;
+        self.transition = if self.transitions {
+            transition.map(|transition| (transition, Instant::now()))
+        } else {
+            None
+        };
+    }
+
+    /// Whether a stroke is being drawn
+    pub fn drawing(&self) -> bool {
+        self.drawing
+    }
+
+    pub fn ink_update(&mut self, ink: Ink) {
+        match ink {
+            Ink::Begin(point) => {
+                self.drawing = true;
+                self.ink_changed();
+                self.ink.entry(self.page).or_default().push(InkStroke {
+                    points: vec![point],
+                    color: self.pen_color,
+                    width: self.pen_width,
+                });
+            }
+            Ink::Point(point) => {
+                if self.drawing {
+                    if let Some(stroke) = self.ink.get_mut(&self.page).and_then(|x| x.last_mut()) {
+                        stroke.points.push(point);
+                    }
+                }
+            }
+            Ink::End => {
+                self.drawing = false;
+            }
+            Ink::Erase => {
+                self.drawing = false;
+                if self.ink.contains_key(&self.page) {
+                    self.ink_changed();
+                    self.ink.remove(&self.page);
+                }
+            }
+            Ink::Color(color) => {
+                self.pen = true;
+                self.pen_color = color;
+            }
+            Ink::Width(factor) => {
+                self.pen_width = (self.pen_width * factor).clamp(INK_WIDTH_MIN, INK_WIDTH_MAX);
+            }
+            Ink::Pen => {
+                self.pen = !self.pen;
+                self.drawing = false;
+            }
+            Ink::Pin => {
+                self.ink_pinned = !self.ink_pinned;
+            }
+            Ink::Undo => {
+                self.drawing = false;
+                if let Some((page, strokes)) = self.ink_undo.pop() {
+                    let current = self.ink.remove(&page).unwrap_or_default();
+                    self.ink_redo.push((page, current));
+                    self.ink.insert(page, strokes);
+                }
+            }
+            Ink::Redo => {
+                self.drawing = false;
+                if let Some((page, strokes)) = self.ink_redo.pop() {
+                    let current = self.ink.remove(&page).unwrap_or_default();
+                    self.ink_undo.push((page, current));
+                    self.ink.insert(page, strokes);
+                }
+            }
+        }
+    }
+
+    /// Remember the strokes of the current page before changing them
+    fn ink_changed(&mut self) {
+        let strokes = self.ink.get(&self.page).cloned().unwrap_or_default();
+        self.ink_undo.push((self.page, strokes));
+        self.ink_redo.clear();
+    }
+
+    /// Whether a transition is still being animated
+    pub fn transitioning(&self) -> bool {
+        match self.transition {
+            Some((transition, start)) => start.elapsed() < transition.duration,
+            None => false,
+        }
+    }
+
+    /// Whether pages advance on their own
+    pub fn auto_advancing(&self) -> bool {
+        self.page_duration.or(self.auto_advance).is_some()
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            // Resuming shows the page for its full duration again
+            self.page_start = Instant::now();
+        }
+    }
+
+    /// Show or hide the overview, pages do not advance on their own while it is shown
+    pub fn set_overview(&mut self, overview: Option<u16>) {
+        if self.overview.is_some() && overview.is_none() {
+            // Closing the overview shows the page for its full duration again
+            self.page_start = Instant::now();
+        }
+        self.overview = overview;
+    }
+
+    /// Whether auto-advance is waiting, paused or behind the overview
+    pub fn advance_waiting(&self) -> bool {
+        self.paused || self.overview.is_some()
+    }
+
+    /// Whether it is time to go to the next page
+    pub fn advance_due(&self) -> bool {
+        match self.page_duration.or(self.auto_advance) {
+            Some(duration) => !self.advance_waiting() && self.page_start.elapsed() >= duration,
+            None => false,
+        }
+    }
+
+    /// Draw overlays in window coordinates, on top of the page
+    pub fn draw_overlay(&self, frame: &mut canvas::Frame, cursor: Option<Point>) {
+        if let Some(color) = self.blank {
+            frame.fill_rectangle(Point::ORIGIN, frame.size(), color);
+            return;
+        }
+        if let Some((transition, start)) = self.transition {
+            let progress = start.elapsed().as_secs_f32() / transition.duration.as_secs_f32();
+            if progress < 1.0 {
+                draw_transition(frame, &transition, progress);
+            }
+        }
+        if let Some(strokes) = self.ink.get(&self.page) {
+            for stroke in strokes.iter() {
+                draw_ink(frame, stroke);
+            }
+        }
+        if let Some(cursor) = cursor {
+            self.draw_pointer(frame, cursor);
+        }
+        if self.show_timer {
+            self.draw_timer(frame);
+        }
+    }
+
+    fn draw_pointer(&self, frame: &mut canvas::Frame, cursor: Point) {
+        match self.pointer {
+            Pointer::None => {}
+            Pointer::Laser => {
+                frame.fill(
+                    &canvas::Path::circle(cursor, LASER_RADIUS * 2.0),
+                    Color::from_rgba(1.0, 0.0, 0.0, 0.3),
+                );
+                frame.fill(
+                    &canvas::Path::circle(cursor, LASER_RADIUS),
+                    Color::from_rgb(1.0, 0.0, 0.0),
+                );
+            }
+            Pointer::Spotlight => {
+                // The circle cuts a hole in the dimmed rectangle with the even-odd rule
+                let path = canvas::Path::new(|builder| {
+                    builder.rectangle(Point::ORIGIN, frame.size());
+                    builder.circle(cursor, SPOTLIGHT_RADIUS);
+                });
+                frame.fill(
+                    &path,
+                    canvas::Fill {
+                        style: canvas::Style::Solid(Color::from_rgba(0.0, 0.0, 0.0, 0.7)),
+                        rule: canvas::fill::Rule::EvenOdd,
+                    },
+                );
+            }
+        }
+    }
+
+    fn draw_timer(&self, frame: &mut canvas::Frame) {
+        let elapsed = self.start.elapsed();
+        let mut lines = vec![
+            (
+                chrono::Local::now().format("%H:%M").to_string(),
+                Color::WHITE,
+            ),
+            (format_duration(elapsed), Color::WHITE),
+        ];
+        if let Some(target) = self.target {
+            match target.checked_sub(elapsed) {
+                Some(remaining) => {
+                    lines.push((format!("-{}", format_duration(remaining)), Color::WHITE))
+                }
+                None => lines.push((
+                    format!("+{}", format_duration(elapsed - target)),
+                    Color::from_rgb(1.0, 0.3, 0.3),
+                )),
+            }
+        }
+
+        //TODO: measure text instead of estimating its width
+        let line_height = OVERLAY_TEXT_SIZE * 1.2;
+        let width = lines
+            .iter()
+            .map(|(line, _)| line.chars().count() as f32 * OVERLAY_TEXT_SIZE * 0.6)
+            .fold(0.0, f32::max);
+        let height = lines.len() as f32 * line_height;
+        let right = frame.width() - OVERLAY_MARGIN;
+        frame.fill_rectangle(
+            Point::new(right - width - OVERLAY_MARGIN, 0.0),
+            Size::new(width + OVERLAY_MARGIN * 2.0, height + OVERLAY_MARGIN * 2.0),
+            Color::from_rgba(0.0, 0.0, 0.0, 0.6),
+        );
+        for (i, (content, color)) in lines.into_iter().enumerate() {
+            frame.fill_text(canvas::Text {
+                content,
+                position: Point::new(right, OVERLAY_MARGIN + i as f32 * line_height),
+                color,
+                size: Pixels(OVERLAY_TEXT_SIZE),
+                horizontal_alignment: Horizontal::Right,
+                vertical_alignment: Vertical::Top,
+                ..Default::default()
+            });
+        }
+    }
+}
+
+fn draw_ink(frame: &mut canvas::Frame, stroke: &InkStroke) {
+    let mut points = stroke.points.iter();
+    let Some(&first) = points.next() else {
+        return;
+    };
+    let path = canvas::Path::new(|builder| {
+        builder.move_to(first);
+        // Single clicks still leave a dot
+        builder.line_to(first);
+        for &point in points {
+            builder.line_to(point);
+        }
+    });
+    frame.stroke(
+        &path,
+        canvas::Stroke::default()
+            .with_color(stroke.color)
+            .with_width(stroke.width)
+            .with_line_cap(canvas::LineCap::Round)
+            .with_line_join(canvas::LineJoin::Round),
+    );
+}
+
+/// Reveal the new page by covering what has not been revealed yet
+fn draw_transition(frame: &mut canvas::Frame, transition: &Transition, progress: f32) {
+    let color = Color::BLACK;
+    let Size { width, height } = frame.size();
+    // Covers span from start to end along the transition axis, as fractions of the frame
+    let mut covers = Vec::new();
+    match transition.style {
+        TransitionStyle::Fade => {
+            frame.fill_rectangle(
+                Point::ORIGIN,
+                frame.size(),
+                Color {
+                    a: 1.0 - progress,
+                    ..color
+                },
+            );
+            return;
+        }
+        TransitionStyle::Box => {
+            let path = canvas::Path::new(|builder| {
+                let size = if transition.inward {
+                    Size::new(width * (1.0 - progress), height * (1.0 - progress))
+                } else {
+                    builder.rectangle(Point::ORIGIN, frame.size());
+                    Size::new(width * progress, height * progress)
+                };
+                builder.rectangle(
+                    Point::new((width - size.width) / 2.0, (height - size.height) / 2.0),
+                    size,
+                );
+            });
+            frame.fill(
+                &path,
+                canvas::Fill {
+                    style: canvas::Style::Solid(color),
+                    rule: canvas::fill::Rule::EvenOdd,
+                },
+            );
+            return;
+        }
+        TransitionStyle::Split => {
+            if transition.inward {
+                covers.push((0.5 - (1.0 - progress) / 2.0, 0.5 + (1.0 - progress) / 2.0));
+            } else {
+                covers.push((0.0, 0.5 - progress / 2.0));
+                covers.push((0.5 + progress / 2.0, 1.0));
+            }
+        }
+        TransitionStyle::Blinds => {
+            let blind = 1.0 / BLINDS as f32;
+            for i in 0..BLINDS {
+                let start = i as f32 * blind;
+                covers.push((start + progress * blind, start + blind));
+            }
+        }
+        TransitionStyle::Wipe => {
+            // Wipes toward the top or the left start at the opposite edge
+            if transition.direction == 90 || transition.direction == 180 {
+                covers.push((0.0, 1.0 - progress));
+            } else {
+                covers.push((progress, 1.0));
+            }
+        }
+    }
+
+    // Split and Blinds sweep across horizontal lines vertically, Wipe depends on the direction
+    let vertical = match transition.style {
+        TransitionStyle::Wipe => transition.direction == 90 || transition.direction == 270,
+        _ => transition.horizontal,
+    };
+    for (start, end) in covers {
+        if vertical {
+            frame.fill_rectangle(
+                Point::new(0.0, start * height),
+                Size::new(width, (end - start) * height),
+                color,
+            );
+        } else {
+            frame.fill_rectangle(
+                Point::new(start * width, 0.0),
+                Size::new((end - start) * width, height),
+                color,
+            );
+        }
+    }
+}
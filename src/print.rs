@@ -0,0 +1,79 @@
+use cosmic::dialog::ashpd::{
+    self,
+    desktop::{
+        print::{PageSetup, PrintProxy, Settings},
+        ResponseError,
+    },
+};
+use std::{
+    io::Write,
+    os::fd::AsFd,
+    process::{Command, Stdio},
+};
+
+use crate::runtime;
+
+/// Why printing did not happen
+#[derive(Clone, Debug)]
+pub enum Error {
+    /// There is no print portal, so printing needs another way
+    Unavailable(String),
+    /// The print dialog was closed without printing
+    Cancelled,
+    Failed(String),
+}
+
+/// Print a document through the xdg print portal, which shows the print dialog of the desktop
+pub async fn portal(data: Vec<u8>, title: String) -> Result<(), Error> {
+    let failed = |err: ashpd::Error| match err {
+        ashpd::Error::Response(ResponseError::Cancelled) => Error::Cancelled,
+        ashpd::Error::Response(_) => Error::Failed(err.to_string()),
+        _ => Error::Unavailable(err.to_string()),
+    };
+    let proxy = PrintProxy::new().await.map_err(failed)?;
+    let prepared = proxy
+        .prepare_print(
+            None,
+            &title,
+            Settings::default(),
+            PageSetup::default(),
+            None,
+            true,
+        )
+        .await
+        .and_then(|request| request.response())
+        .map_err(failed)?;
+    let file = runtime::anonymous(&data).map_err(|err| Error::Failed(err.to_string()))?;
+    proxy
+        .print(None, &title, &file.as_fd(), Some(prepared.token), true)
+        .await
+        .and_then(|request| request.response())
+        .map_err(failed)
+}
+
+/// Print a document on the default CUPS printer, returning the request lp reports
+pub fn lp(data: &[u8], title: &str) -> Result<String, Error> {
+    let failed = |err: std::io::Error| Error::Failed(err.to_string());
+    let mut child = Command::new("lp")
+        .arg("-t")
+        .arg(title)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(failed)?;
+    // Dropping stdin closes it so lp knows the document is complete
+    child
+        .stdin
+        .take()
+        .map_or(Ok(()), |mut stdin| stdin.write_all(data))
+        .map_err(failed)?;
+    let output = child.wait_with_output().map_err(failed)?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(Error::Failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
@@ -0,0 +1,268 @@
+use cosmic::iced::{
+    widget::{
+        canvas::{self, fill::Rule, path::lyon_path::Event, LineCap, LineJoin, Style},
+        image::Handle,
+    },
+    Color,
+};
+use lopdf::{Document, ObjectId};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+use tiny_skia::{
+    FillRule, FilterQuality, IntSize, Paint, PathBuilder, Pixmap, PixmapPaint, Stroke, StrokeDash,
+    Transform,
+};
+
+use crate::{fl, pdf};
+
+/// Resolution of exported images unless another is chosen, in dots per inch
+pub const DPI: u16 = 150;
+
+/// File format of exported page images
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Format {
+    #[default]
+    Png,
+    Jpeg,
+}
+
+impl Format {
+    pub const ALL: [Self; 2] = [Self::Png, Self::Jpeg];
+
+    pub fn name(&self) -> String {
+        match self {
+            Self::Png => fl!("png"),
+            Self::Jpeg => fl!("jpeg"),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+        }
+    }
+
+    fn image_format(&self) -> image::ImageFormat {
+        match self {
+            Self::Png => image::ImageFormat::Png,
+            Self::Jpeg => image::ImageFormat::Jpeg,
+        }
+    }
+}
+
+/// Write a page as displayed to an image file at a resolution
+pub fn export(
+    doc: &Document,
+    page_id: ObjectId,
+    page_box: pdf::PageBox,
+    hidden: &HashSet<ObjectId>,
+    dpi: u16,
+    format: Format,
+    path: &Path,
+) -> Result<(), String> {
+    let ops = pdf::page_ops(doc, page_id, hidden);
+    let pixmap = render(&ops, page_box, dpi)?;
+    let (width, height) = (pixmap.width(), pixmap.height());
+    // Pages are drawn on white, so the premultiplied pixels are the same as straight ones
+    let image = image::RgbaImage::from_raw(width, height, pixmap.take())
+        .ok_or_else(|| "image buffer too small".to_string())?;
+    match format {
+        Format::Png => image.save_with_format(path, format.image_format()),
+        // JPEG has no transparency, pages are drawn on white so nothing is lost
+        Format::Jpeg => image::DynamicImage::ImageRgba8(image)
+            .to_rgb8()
+            .save_with_format(path, format.image_format()),
+    }
+    .map_err(|err| err.to_string())
+}
+
+/// Path for an image of one of several pages, numbered from 1, next to the path chosen
+pub fn page_path(path: &Path, page: u16) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    path.with_file_name(format!("{stem}-page-{page}{extension}"))
+}
+
+/// Positions of the pages in a range such as "1-3, 5", numbered from 1 and checked against
+/// the number of pages
+pub fn parse_pages(input: &str, count: usize) -> Result<Vec<u16>, String> {
+    let invalid = || fl!("invalid-pages", pages = input);
+    let page = |x: &str| -> Result<u16, String> {
+        match x.trim().parse::<u16>() {
+            Ok(page @ 1..) if usize::from(page) <= count => Ok(page - 1),
+            _ => Err(invalid()),
+        }
+    };
+    let mut positions = Vec::new();
+    for part in input.split(',').filter(|part| !part.trim().is_empty()) {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (page(first)?, page(last)?);
+                if first > last {
+                    return Err(invalid());
+                }
+                positions.extend(first..=last);
+            }
+            None => positions.push(page(part)?),
+        }
+    }
+    if positions.is_empty() {
+        return Err(invalid());
+    }
+    Ok(positions)
+}
+
+/// Page as displayed, with its images, at a resolution
+fn render(ops: &[pdf::PageOp], page_box: pdf::PageBox, dpi: u16) -> Result<Pixmap, String> {
+    let scale = f32::from(dpi) / 72.0;
+    let size = page_box.size();
+    let rect = page_box.rect;
+    let mut pixmap = Pixmap::new(
+        (size.width * scale).round().max(1.0) as u32,
+        (size.height * scale).round().max(1.0) as u32,
+    )
+    .ok_or_else(|| "page too large".to_string())?;
+    // Rotate about the center of the page, and flip as PDF's origin is the bottom left
+    let transform = Transform::from_scale(scale, scale)
+        .pre_translate(size.width / 2.0, size.height / 2.0)
+        .pre_rotate(page_box.rotate as f32)
+        .pre_scale(1.0, -1.0)
+        .pre_translate(-(rect.x + rect.width / 2.0), -(rect.y + rect.height / 2.0));
+    //TODO: clip to the crop box like the view should
+    pixmap.fill(tiny_skia::Color::WHITE);
+    for op in ops.iter() {
+        if let Some(path) = op.path.as_ref().and_then(path) {
+            if let Some(fill) = &op.fill {
+                let rule = match fill.rule {
+                    Rule::NonZero => FillRule::Winding,
+                    Rule::EvenOdd => FillRule::EvenOdd,
+                };
+                pixmap.fill_path(&path, &paint(&fill.style), rule, transform, None);
+            }
+            if let Some(stroke) = &op.stroke {
+                let line_cap = match stroke.line_cap {
+                    LineCap::Butt => tiny_skia::LineCap::Butt,
+                    LineCap::Square => tiny_skia::LineCap::Square,
+                    LineCap::Round => tiny_skia::LineCap::Round,
+                };
+                let line_join = match stroke.line_join {
+                    LineJoin::Miter => tiny_skia::LineJoin::Miter,
+                    LineJoin::Round => tiny_skia::LineJoin::Round,
+                    LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
+                };
+                let dash = StrokeDash::new(
+                    stroke.line_dash.segments.to_vec(),
+                    stroke.line_dash.offset as f32,
+                );
+                pixmap.stroke_path(
+                    &path,
+                    &paint(&stroke.style),
+                    &Stroke {
+                        width: stroke.width,
+                        line_cap,
+                        line_join,
+                        dash,
+                        ..Stroke::default()
+                    },
+                    transform,
+                    None,
+                );
+            }
+        }
+        if let Some(image) = &op.image {
+            let Some(data) = decode(&image.handle) else {
+                log::warn!("failed to decode image {:?}", image.name);
+                continue;
+            };
+            // Images are stretched over their area, with the first row at its top
+            let rect = image.rect;
+            let image_transform = Transform::from_row(
+                rect.width / data.width() as f32,
+                0.0,
+                0.0,
+                -rect.height / data.height() as f32,
+                rect.x,
+                rect.y,
+            );
+            pixmap.draw_pixmap(
+                0,
+                0,
+                data.as_ref(),
+                &PixmapPaint {
+                    quality: FilterQuality::Bilinear,
+                    ..PixmapPaint::default()
+                },
+                transform.pre_concat(image_transform),
+                None,
+            );
+        }
+    }
+    Ok(pixmap)
+}
+
+fn path(path: &canvas::Path) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    for event in path.raw().iter() {
+        match event {
+            Event::Begin { at } => builder.move_to(at.x, at.y),
+            Event::Line { to, .. } => builder.line_to(to.x, to.y),
+            Event::Quadratic { ctrl, to, .. } => builder.quad_to(ctrl.x, ctrl.y, to.x, to.y),
+            Event::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => builder.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y),
+            Event::End { close: true, .. } => builder.close(),
+            Event::End { close: false, .. } => {}
+        }
+    }
+    builder.finish()
+}
+
+/// Paint of a fill or stroke, gradients are drawn with their first color
+fn paint(style: &Style) -> Paint<'static> {
+    let color = match style {
+        Style::Solid(color) => *color,
+        Style::Gradient(canvas::Gradient::Linear(linear)) => linear
+            .stops
+            .iter()
+            .flatten()
+            .next()
+            .map_or(Color::BLACK, |stop| stop.color),
+    };
+    let [r, g, b, a] = color.into_rgba8();
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(r, g, b, a);
+    paint.anti_alias = true;
+    paint
+}
+
+/// Pixels of an image on a page, premultiplied as tiny-skia draws them
+fn decode(handle: &Handle) -> Option<Pixmap> {
+    let image = match handle {
+        Handle::Bytes(_, bytes) => image::load_from_memory(bytes).ok()?.to_rgba8(),
+        Handle::Rgba {
+            width,
+            height,
+            pixels,
+            ..
+        } => image::RgbaImage::from_raw(*width, *height, pixels.to_vec())?,
+        Handle::Path(_, path) => image::open(path).ok()?.to_rgba8(),
+    };
+    let size = IntSize::from_wh(image.width(), image.height())?;
+    let mut data = image.into_raw();
+    for pixel in data.chunks_exact_mut(4) {
+        let alpha = u16::from(pixel[3]);
+        for channel in &mut pixel[..3] {
+            *channel = (u16::from(*channel) * alpha / 255) as u8;
+        }
+    }
+    Pixmap::from_vec(data, size)
+}
@@ -0,0 +1,95 @@
+use std::{
+    env,
+    fs::{self, DirBuilder, File},
+    io::{self, Seek, Write},
+    os::unix::fs::DirBuilderExt,
+    path::{Path, PathBuf},
+    process,
+    sync::OnceLock,
+};
+
+/// Attempts at finding a name that is not taken yet
+const ATTEMPTS: u32 = 100;
+
+/// Directory only the user can access for files handed to other programs, in the runtime
+/// directory of the session so it is removed on logout
+pub fn dir() -> io::Result<&'static Path> {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    if let Some(dir) = DIR.get() {
+        return Ok(dir);
+    }
+    let dir = match env::var_os("XDG_RUNTIME_DIR") {
+        // The runtime directory is private to the user already
+        Some(runtime_dir) => {
+            let dir = PathBuf::from(runtime_dir).join("cosmic-reader");
+            match DirBuilder::new().mode(0o700).create(&dir) {
+                Err(err) if err.kind() != io::ErrorKind::AlreadyExists => return Err(err),
+                _ => dir,
+            }
+        }
+        // Others can write to the temporary directory, so a fresh directory is made that
+        // nobody else can have prepared
+        None => {
+            unique(
+                &env::temp_dir(),
+                &format!("cosmic-reader-{}", process::id()),
+                "",
+                |path| DirBuilder::new().mode(0o700).create(path),
+            )?
+            .0
+        }
+    };
+    Ok(DIR.get_or_init(|| dir))
+}
+
+/// Create a new file in the private directory, named after the given file name or with a
+/// number added when that name is taken
+pub fn create(name: &str) -> io::Result<(PathBuf, File)> {
+    let name = Path::new(name);
+    let stem = name
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "file".to_string());
+    let extension = name
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    unique(dir()?, &stem, &extension, |path| {
+        File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+    })
+}
+
+/// File with the given contents that has no name, for passing to portals by descriptor
+pub fn anonymous(data: &[u8]) -> io::Result<File> {
+    let (path, mut file) = create("document")?;
+    fs::remove_file(&path)?;
+    file.write_all(data)?;
+    file.rewind()?;
+    Ok(file)
+}
+
+/// Create something at the first free name in the parent directory
+fn unique<T>(
+    parent: &Path,
+    stem: &str,
+    extension: &str,
+    mut create: impl FnMut(&Path) -> io::Result<T>,
+) -> io::Result<(PathBuf, T)> {
+    for attempt in 0..ATTEMPTS {
+        let name = match attempt {
+            0 => format!("{stem}{extension}"),
+            _ => format!("{stem}-{attempt}{extension}"),
+        };
+        let path = parent.join(name);
+        match create(&path) {
+            Ok(created) => return Ok((path, created)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::ErrorKind::AlreadyExists.into())
+}
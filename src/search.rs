@@ -0,0 +1,159 @@
+use cosmic::iced::{Point, Rectangle, Size};
+use lopdf::Document;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::pdf;
+
+/// Words of a document and the pages they are on, so searches do not scan every page
+#[derive(Debug, Default)]
+pub struct Index {
+    /// Lowercase words and the positions of the pages containing them
+    words: BTreeMap<String, BTreeSet<u16>>,
+}
+
+impl Index {
+    /// Load the cached index of a document, building and caching it the first time
+    pub fn load(doc: &Document, path: &Path) -> Self {
+        let cache = cache_path(path);
+        if let Some(cache) = &cache {
+            match Self::read(cache) {
+                Ok(index) => return index,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => log::warn!("failed to read search index {cache:?}: {err}"),
+            }
+        }
+        let index = Self::build(doc);
+        if let Some(cache) = &cache {
+            if let Err(err) = index.write(cache) {
+                log::warn!("failed to write search index {cache:?}: {err}");
+            }
+        }
+        index
+    }
+
+    fn build(doc: &Document) -> Self {
+        let mut words = BTreeMap::new();
+        for (position, page) in doc.get_pages().keys().enumerate() {
+            let text = doc.extract_text(&[*page]).unwrap_or_default();
+            for word in words_of(&text) {
+                words
+                    .entry(word)
+                    .or_insert_with(BTreeSet::new)
+                    .insert(position as u16);
+            }
+        }
+        Self { words }
+    }
+
+    /// Positions of pages with every word of a term, the last word matching the start of words
+    /// as it may still be typed
+    pub fn search(&self, term: &str) -> Vec<u16> {
+        let terms = words_of(term);
+        let mut pages: Option<BTreeSet<u16>> = None;
+        for (i, term) in terms.iter().enumerate() {
+            let matches = if i + 1 == terms.len() {
+                self.words
+                    .range(term.clone()..)
+                    .take_while(|(word, _)| word.starts_with(term.as_str()))
+                    .flat_map(|(_, pages)| pages.iter().copied())
+                    .collect()
+            } else {
+                self.words.get(term).cloned().unwrap_or_default()
+            };
+            pages = Some(match pages {
+                Some(pages) => pages.intersection(&matches).copied().collect(),
+                None => matches,
+            });
+        }
+        pages.unwrap_or_default().into_iter().collect()
+    }
+
+    /// One line per word, followed by the positions of its pages
+    fn read(path: &Path) -> io::Result<Self> {
+        let mut words = BTreeMap::new();
+        for line in BufReader::new(fs::File::open(path)?).lines() {
+            let line = line?;
+            let mut parts = line.split(' ');
+            let Some(word) = parts.next() else {
+                continue;
+            };
+            let pages = parts.filter_map(|page| page.parse().ok()).collect();
+            words.insert(word.to_string(), pages);
+        }
+        Ok(Self { words })
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write next to the index and rename, so an interrupted write is not read as an index
+        let partial = path.with_extension("partial");
+        let mut file = BufWriter::new(fs::File::create(&partial)?);
+        for (word, pages) in self.words.iter() {
+            write!(file, "{word}")?;
+            for page in pages.iter() {
+                write!(file, " {page}")?;
+            }
+            writeln!(file)?;
+        }
+        file.flush()?;
+        fs::rename(&partial, path)
+    }
+}
+
+/// Boxes around the words of a term in the text of a page, in PDF units with y up
+pub fn matches(ops: &[pdf::PageOp], term: &str) -> Vec<Rectangle> {
+    let words = words_of(term);
+    let mut matches = Vec::new();
+    for run in ops.iter().filter_map(|op| op.text.as_ref()) {
+        let content: Vec<char> = run.content.to_lowercase().chars().collect();
+        if content.is_empty() {
+            continue;
+        }
+        // Characters are assumed to be of equal width along the run
+        let char_width = run.rect.width / content.len() as f32;
+        for word in words.iter() {
+            let word: Vec<char> = word.chars().collect();
+            for (start, window) in content.windows(word.len()).enumerate() {
+                if window == word.as_slice() {
+                    matches.push(Rectangle::new(
+                        Point::new(run.rect.x + start as f32 * char_width, run.rect.y),
+                        Size::new(word.len() as f32 * char_width, run.rect.height),
+                    ));
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// Lowercase words of some text, split at anything that is not a letter or number
+fn words_of(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Cached index of a document, named by a hash of its contents so changed files are indexed again
+fn cache_path(path: &Path) -> Option<PathBuf> {
+    let contents = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    let cache = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".cache")))?;
+    Some(
+        cache
+            .join("cosmic-reader")
+            .join("index")
+            .join(format!("{:016x}.txt", hasher.finish())),
+    )
+}
@@ -0,0 +1,54 @@
+use cosmic::dialog::ashpd::{
+    self,
+    desktop::{email::EmailRequest, ResponseError},
+};
+use std::{
+    fs::File,
+    io,
+    os::fd::AsFd,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::fl;
+
+/// Open a new email with the document attached, in the user's email application, through the
+/// email portal or xdg-email where there is no portal
+pub async fn email(path: PathBuf, subject: String) -> Result<(), String> {
+    let file = File::open(&path).map_err(|err| err.to_string())?;
+    let sent = EmailRequest::default()
+        .subject(subject.as_str())
+        .attach(&file.as_fd())
+        .send()
+        .await
+        .and_then(|request| request.response());
+    match sent {
+        // Closing the email without sending it is not an error
+        Ok(()) | Err(ashpd::Error::Response(ResponseError::Cancelled)) => Ok(()),
+        Err(err @ ashpd::Error::Response(_)) => Err(err.to_string()),
+        Err(err) => {
+            log::info!("email portal unavailable, using xdg-email: {}", err);
+            tokio::task::spawn_blocking(move || xdg_email(&path, &subject))
+                .await
+                .map_err(|err| err.to_string())?
+        }
+    }
+}
+
+fn xdg_email(path: &Path, subject: &str) -> Result<(), String> {
+    let output = Command::new("xdg-email")
+        .arg("--subject")
+        .arg(subject)
+        .arg("--attach")
+        .arg(path)
+        .output()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => fl!("no-email-application"),
+            _ => err.to_string(),
+        })?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+use lopdf::{decode_text_string, Dictionary, Document, Object};
+
+use crate::fl;
+
+/// Digital signature of a signature field
+#[derive(Clone, Debug)]
+pub struct Signature {
+    /// Name of the signer given with the signature, which may differ from its certificate
+    pub name: Option<String>,
+    pub time: Option<DateTime<FixedOffset>>,
+    pub reason: Option<String>,
+    pub location: Option<String>,
+    pub status: Status,
+}
+
+/// What can be told about a signature without verifying it, so none of these mean it is valid
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// The signed bytes run to the end of the file
+    WholeDocument,
+    /// The document was changed after signing, by an update appended to the file
+    ChangedAfter,
+    /// The signed bytes are missing or do not fit in the file
+    Broken,
+}
+
+impl Status {
+    pub fn name(&self) -> String {
+        match self {
+            Self::WholeDocument => fl!("signature-whole-document"),
+            Self::ChangedAfter => fl!("signature-changed-after"),
+            Self::Broken => fl!("signature-broken"),
+        }
+    }
+}
+
+/// Signatures of the signed signature fields of a document, given the length of its file
+//TODO: verify the signed digest and certificate chain, which needs a CMS library
+pub fn signatures(doc: &Document, file_len: u64) -> Vec<Signature> {
+    let fields = doc
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"AcroForm", doc))
+        .and_then(Object::as_dict)
+        .and_then(|form| form.get_deref(b"Fields", doc))
+        .and_then(Object::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let mut signatures = Vec::new();
+    // Fields form a tree, limit depth in case it has a loop
+    let mut stack: Vec<(Object, bool, usize)> =
+        fields.into_iter().map(|field| (field, false, 0)).collect();
+    while let Some((field, inherited_sig, depth)) = stack.pop() {
+        let Ok((_, field)) = doc.dereference(&field) else {
+            continue;
+        };
+        let Ok(field) = field.as_dict() else {
+            continue;
+        };
+        // The field type is inherited by kids that do not have their own
+        let sig = match field.get(b"FT").and_then(Object::as_name) {
+            Ok(kind) => kind == b"Sig",
+            Err(_) => inherited_sig,
+        };
+        if depth < 32 {
+            if let Ok(kids) = field.get_deref(b"Kids", doc).and_then(Object::as_array) {
+                stack.extend(kids.iter().map(|kid| (kid.clone(), sig, depth + 1)));
+            }
+        }
+        if !sig {
+            continue;
+        }
+        if let Ok(value) = field.get_deref(b"V", doc).and_then(Object::as_dict) {
+            signatures.push(signature(doc, value, file_len));
+        }
+    }
+    signatures
+}
+
+fn signature(doc: &Document, value: &Dictionary, file_len: u64) -> Signature {
+    let text = |key: &[u8]| {
+        value
+            .get_deref(key, doc)
+            .ok()
+            .and_then(|x| decode_text_string(x).ok())
+            .filter(|x| !x.trim().is_empty())
+    };
+    let byte_range: Option<Vec<i64>> = value
+        .get_deref(b"ByteRange", doc)
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|range| range.iter().map(|x| x.as_i64().ok()).collect());
+    // Two ranges of signed bytes, from the start of the file to the signature and from after
+    // the signature to the end of the file as it was when signed
+    let status = match byte_range.as_deref() {
+        Some(&[0, len1, start2, len2]) if len1 >= 0 && len2 >= 0 && start2 >= len1 => {
+            let end = (start2 + len2) as u64;
+            if end == file_len {
+                Status::WholeDocument
+            } else if end < file_len {
+                Status::ChangedAfter
+            } else {
+                Status::Broken
+            }
+        }
+        _ => Status::Broken,
+    };
+    Signature {
+        name: text(b"Name"),
+        time: text(b"M").as_deref().and_then(parse_date),
+        reason: text(b"Reason"),
+        location: text(b"Location"),
+        status,
+    }
+}
+
+/// Parse a PDF date such as D:20240131093000+01'00', where only the year is required
+fn parse_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    let date = date.strip_prefix("D:").unwrap_or(date);
+    let digits: String = date.chars().take_while(char::is_ascii_digit).collect();
+    if digits.len() < 4 {
+        return None;
+    }
+    // Missing parts default to the start of the year
+    let mut padded = digits.clone();
+    padded.push_str(&"0101000000"[digits.len().saturating_sub(4).min(10)..]);
+    let local = NaiveDateTime::parse_from_str(&padded[..14], "%Y%m%d%H%M%S").ok()?;
+    let zone = &date[digits.len()..];
+    let offset = match zone.chars().next() {
+        Some(sign @ ('+' | '-')) => {
+            let parts: Vec<i32> = zone[1..]
+                .split('\'')
+                .filter_map(|part| part.parse().ok())
+                .collect();
+            let seconds = parts.first().copied().unwrap_or(0) * 3600
+                + parts.get(1).copied().unwrap_or(0) * 60;
+            FixedOffset::east_opt(if sign == '-' { -seconds } else { seconds })?
+        }
+        // Z is UTC, and dates without a zone are taken as UTC too
+        _ => FixedOffset::east_opt(0)?,
+    };
+    offset.from_local_datetime(&local).single()
+}
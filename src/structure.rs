@@ -0,0 +1,278 @@
+use lopdf::{decode_text_string, Dictionary, Document, Encoding, Object, ObjectId};
+use std::collections::HashMap;
+
+/// Limit nesting in case the structure tree has a loop
+const MAX_DEPTH: usize = 64;
+/// Kerning in TJ arrays past this many thousandths of an em is treated as a space
+const TJ_SPACE: f32 = 200.0;
+
+/// Structure elements useful for navigation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StructKind {
+    /// Heading with its level, starting at 1
+    Heading(u8),
+    List,
+    Table,
+}
+
+/// Element from the structure tree of a tagged PDF
+#[derive(Clone, Debug)]
+pub struct StructItem {
+    pub kind: StructKind,
+    /// Page the element starts on, if the structure tree says
+    pub page_id: Option<ObjectId>,
+    /// Alternate text, title, or contents, empty if the element has none
+    pub text: String,
+}
+
+/// Headings, lists, and tables of a tagged PDF in reading order, empty if it has no structure
+pub fn structure(doc: &Document) -> Vec<StructItem> {
+    let Ok(root) = doc
+        .catalog()
+        .and_then(|catalog| catalog.get_deref(b"StructTreeRoot", doc))
+        .and_then(Object::as_dict)
+    else {
+        return Vec::new();
+    };
+    let mut walker = Walker {
+        doc,
+        role_map: root
+            .get_deref(b"RoleMap", doc)
+            .and_then(Object::as_dict)
+            .ok(),
+        marked: HashMap::new(),
+        items: Vec::new(),
+    };
+    if let Ok(kids) = root.get(b"K") {
+        walker.kids(kids, None, 0);
+    }
+    walker.items
+}
+
+struct Walker<'a> {
+    doc: &'a Document,
+    /// Maps custom structure types to standard ones
+    role_map: Option<&'a Dictionary>,
+    /// Text of marked content by MCID, for each page read so far
+    marked: HashMap<ObjectId, HashMap<i64, String>>,
+    items: Vec<StructItem>,
+}
+
+impl<'a> Walker<'a> {
+    fn kids(&mut self, kids: &'a Object, page_id: Option<ObjectId>, depth: usize) {
+        match kids {
+            Object::Array(array) => {
+                for kid in array.iter() {
+                    self.kid(kid, page_id, depth);
+                }
+            }
+            kid => self.kid(kid, page_id, depth),
+        }
+    }
+
+    fn kid(&mut self, kid: &'a Object, page_id: Option<ObjectId>, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        let Ok((_, kid)) = self.doc.dereference(kid) else {
+            return;
+        };
+        // Marked content and object references have no type to navigate by
+        let Ok(dict) = kid.as_dict() else {
+            return;
+        };
+        let Ok(name) = dict.get(b"S").and_then(Object::as_name_str) else {
+            return;
+        };
+        let page_id = dict
+            .get(b"Pg")
+            .and_then(Object::as_reference)
+            .ok()
+            .or(page_id);
+        match self.kind(name) {
+            Some(kind) => {
+                let text = match kind {
+                    StructKind::Heading(_) => self.text(dict, page_id, depth),
+                    // Contents of lists and tables are too long to show
+                    StructKind::List | StructKind::Table => alt_text(dict).unwrap_or_default(),
+                };
+                self.items.push(StructItem {
+                    kind,
+                    page_id,
+                    text: text.split_whitespace().collect::<Vec<_>>().join(" "),
+                });
+            }
+            None => {
+                if let Ok(kids) = dict.get(b"K") {
+                    self.kids(kids, page_id, depth + 1);
+                }
+            }
+        }
+    }
+
+    fn kind(&self, name: &str) -> Option<StructKind> {
+        let mut name = name;
+        // Custom types can map to other custom types, but not forever
+        for _ in 0..MAX_DEPTH {
+            match name {
+                "H" => return Some(StructKind::Heading(1)),
+                "H1" | "H2" | "H3" | "H4" | "H5" | "H6" => {
+                    return name[1..].parse().ok().map(StructKind::Heading)
+                }
+                "L" => return Some(StructKind::List),
+                "Table" => return Some(StructKind::Table),
+                _ => {}
+            }
+            name = self
+                .role_map?
+                .get(name.as_bytes())
+                .and_then(Object::as_name_str)
+                .ok()?;
+        }
+        None
+    }
+
+    /// Replacement text of an element, or the text of its marked content
+    fn text(&mut self, dict: &'a Dictionary, page_id: Option<ObjectId>, depth: usize) -> String {
+        if let Some(text) = alt_text(dict) {
+            return text;
+        }
+        let mut text = String::new();
+        let Ok(kids) = dict.get(b"K") else {
+            return text;
+        };
+        let kids = match kids {
+            Object::Array(array) => array.iter().collect(),
+            kid => vec![kid],
+        };
+        for kid in kids {
+            if depth > MAX_DEPTH {
+                break;
+            }
+            let Ok((_, kid)) = self.doc.dereference(kid) else {
+                continue;
+            };
+            match kid {
+                Object::Integer(mcid) => {
+                    if let Some(page_id) = page_id {
+                        text.push_str(&self.marked_text(page_id, *mcid));
+                    }
+                }
+                Object::Dictionary(kid) => {
+                    let page_id = kid
+                        .get(b"Pg")
+                        .and_then(Object::as_reference)
+                        .ok()
+                        .or(page_id);
+                    match kid.get(b"MCID").and_then(Object::as_i64) {
+                        Ok(mcid) => {
+                            if let Some(page_id) = page_id {
+                                text.push_str(&self.marked_text(page_id, mcid));
+                            }
+                        }
+                        Err(_) => text.push_str(&self.text(kid, page_id, depth + 1)),
+                    }
+                }
+                _ => {}
+            }
+        }
+        text
+    }
+
+    fn marked_text(&mut self, page_id: ObjectId, mcid: i64) -> String {
+        let doc = self.doc;
+        self.marked
+            .entry(page_id)
+            .or_insert_with(|| page_marked_text(doc, page_id))
+            .get(&mcid)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Text given in place of an element's contents
+fn alt_text(dict: &Dictionary) -> Option<String> {
+    [&b"ActualText"[..], b"Alt", b"T"]
+        .into_iter()
+        .filter_map(|key| dict.get(key).ok())
+        .filter_map(|obj| decode_text_string(obj).ok())
+        .find(|text| !text.trim().is_empty())
+}
+
+/// Text shown in each marked content sequence of a page, by MCID
+fn page_marked_text(doc: &Document, page_id: ObjectId) -> HashMap<i64, String> {
+    let mut marked: HashMap<i64, String> = HashMap::new();
+    let content = match doc.get_and_decode_page_content(page_id) {
+        Ok(ok) => ok,
+        Err(err) => {
+            log::warn!("failed to get page contents for page {page_id:?}: {err}");
+            return marked;
+        }
+    };
+    let fonts = doc.get_page_fonts(page_id).unwrap_or_default();
+    let mut encoding: Option<Encoding> = None;
+    // Marked content can nest, text goes to the innermost sequence with an MCID
+    let mut stack: Vec<Option<i64>> = Vec::new();
+    for op in content.operations.iter() {
+        match op.operator.as_str() {
+            "BMC" => stack.push(None),
+            "BDC" => stack.push(
+                op.operands
+                    .get(1)
+                    .and_then(|props| props.as_dict().ok())
+                    .and_then(|props| props.get(b"MCID").and_then(Object::as_i64).ok()),
+            ),
+            "EMC" => {
+                stack.pop();
+            }
+            "Tf" => {
+                encoding = op
+                    .operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| fonts.get(name))
+                    .and_then(|font| font.get_font_encoding(doc).ok());
+            }
+            "Tj" | "TJ" | "'" | "\"" => {
+                let Some(mcid) = stack.iter().rev().find_map(|mcid| *mcid) else {
+                    continue;
+                };
+                let text = marked.entry(mcid).or_default();
+                // Quote operators move to the next line first
+                if op.operator != "Tj" && op.operator != "TJ" {
+                    text.push(' ');
+                }
+                for operand in op.operands.iter() {
+                    let elements = match operand {
+                        Object::Array(array) => array.as_slice(),
+                        operand => std::slice::from_ref(operand),
+                    };
+                    for element in elements {
+                        match element {
+                            Object::String(bytes, _) => match &encoding {
+                                Some(encoding) => {
+                                    if let Ok(decoded) = Document::decode_text(encoding, bytes) {
+                                        text.push_str(&decoded);
+                                    }
+                                }
+                                None => text.push_str(&String::from_utf8_lossy(bytes)),
+                            },
+                            element => {
+                                if element.as_float().is_ok_and(|x| x < -TJ_SPACE) {
+                                    text.push(' ');
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            "T*" | "Td" | "TD" => {
+                if let Some(mcid) = stack.iter().rev().find_map(|mcid| *mcid) {
+                    marked.entry(mcid).or_default().push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    marked
+}
@@ -0,0 +1,150 @@
+use cosmic::iced::{
+    widget::canvas::{self, fill::Rule, path::lyon_path::Event, LineCap, LineJoin, Style},
+    Color,
+};
+use lopdf::{Document, ObjectId};
+use std::{collections::HashSet, fmt::Write, fs, path::Path};
+
+use crate::pdf;
+
+/// Write a page as displayed to an SVG file, with text as outlines
+pub fn export(
+    doc: &Document,
+    page_id: ObjectId,
+    page_box: pdf::PageBox,
+    hidden: &HashSet<ObjectId>,
+    path: &Path,
+) -> Result<(), String> {
+    let ops = pdf::page_ops(doc, page_id, hidden);
+    fs::write(path, page_svg(&ops, page_box)).map_err(|err| err.to_string())
+}
+
+/// File name suggested for an SVG of a page, numbered from 1
+//TODO: export ranges of pages
+pub fn svg_file_name(path: &Path, page: u16) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    format!("{stem}-page-{page}.svg")
+}
+
+/// SVG of a page as displayed, sized in points
+pub fn page_svg(ops: &[pdf::PageOp], page_box: pdf::PageBox) -> String {
+    let size = page_box.size();
+    let rect = page_box.rect;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}pt\" height=\"{h}pt\" viewBox=\"0 0 {w} {h}\">\n",
+        w = size.width,
+        h = size.height,
+    );
+    // Rotate about the center of the page, and flip as PDF's origin is the bottom left
+    let _ = writeln!(
+        svg,
+        "<g transform=\"translate({} {}) rotate({}) scale(1 -1) translate({} {})\">",
+        size.width / 2.0,
+        size.height / 2.0,
+        page_box.rotate,
+        -(rect.x + rect.width / 2.0),
+        -(rect.y + rect.height / 2.0),
+    );
+    //TODO: clip to the crop box like the view should
+    let _ = writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>",
+        rect.x, rect.y, rect.width, rect.height
+    );
+    for op in ops.iter() {
+        //TODO: embed images
+        let Some(path) = &op.path else {
+            continue;
+        };
+        let data = path_data(path);
+        if let Some(fill) = &op.fill {
+            let rule = match fill.rule {
+                Rule::NonZero => "nonzero",
+                Rule::EvenOdd => "evenodd",
+            };
+            let _ = writeln!(
+                svg,
+                "<path d=\"{data}\" fill-rule=\"{rule}\" {}/>",
+                paint("fill", &fill.style)
+            );
+        }
+        if let Some(stroke) = &op.stroke {
+            let cap = match stroke.line_cap {
+                LineCap::Butt => "butt",
+                LineCap::Square => "square",
+                LineCap::Round => "round",
+            };
+            let join = match stroke.line_join {
+                LineJoin::Miter => "miter",
+                LineJoin::Round => "round",
+                LineJoin::Bevel => "bevel",
+            };
+            let mut attrs = format!(
+                "fill=\"none\" {} stroke-width=\"{}\" stroke-linecap=\"{cap}\" stroke-linejoin=\"{join}\"",
+                paint("stroke", &stroke.style),
+                stroke.width,
+            );
+            if !stroke.line_dash.segments.is_empty() {
+                let segments: Vec<String> = stroke
+                    .line_dash
+                    .segments
+                    .iter()
+                    .map(f32::to_string)
+                    .collect();
+                let _ = write!(
+                    attrs,
+                    " stroke-dasharray=\"{}\" stroke-dashoffset=\"{}\"",
+                    segments.join(" "),
+                    stroke.line_dash.offset
+                );
+            }
+            let _ = writeln!(svg, "<path d=\"{data}\" {attrs}/>");
+        }
+    }
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+fn path_data(path: &canvas::Path) -> String {
+    let mut data = String::new();
+    for event in path.raw().iter() {
+        let _ = match event {
+            Event::Begin { at } => write!(data, "M{} {}", at.x, at.y),
+            Event::Line { to, .. } => write!(data, "L{} {}", to.x, to.y),
+            Event::Quadratic { ctrl, to, .. } => {
+                write!(data, "Q{} {} {} {}", ctrl.x, ctrl.y, to.x, to.y)
+            }
+            Event::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => write!(
+                data,
+                "C{} {} {} {} {} {}",
+                ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y
+            ),
+            Event::End { close: true, .. } => write!(data, "Z"),
+            Event::End { close: false, .. } => Ok(()),
+        };
+    }
+    data
+}
+
+/// Color attributes of a fill or stroke, gradients are drawn with their first color
+fn paint(name: &str, style: &Style) -> String {
+    let color = match style {
+        Style::Solid(color) => *color,
+        Style::Gradient(canvas::Gradient::Linear(linear)) => linear
+            .stops
+            .iter()
+            .flatten()
+            .next()
+            .map_or(Color::BLACK, |stop| stop.color),
+    };
+    let [r, g, b, _] = color.into_rgba8();
+    format!(
+        "{name}=\"#{r:02x}{g:02x}{b:02x}\" {name}-opacity=\"{}\"",
+        color.a
+    )
+}